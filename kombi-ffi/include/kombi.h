@@ -0,0 +1,134 @@
+#ifndef KOMBI_H
+#define KOMBI_H
+
+/* This file is generated by cbindgen. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of a call into kombi.
+ */
+typedef enum KombiStatus {
+  /**
+   * The call succeeded.
+   */
+  KOMBI_STATUS_OK = 0,
+  /**
+   * A pointer argument which must not be null was null.
+   */
+  KOMBI_STATUS_NULL_POINTER = 1,
+  /**
+   * A string argument was not valid UTF-8.
+   */
+  KOMBI_STATUS_INVALID_UTF8 = 2,
+  /**
+   * A source string could not be parsed.
+   */
+  KOMBI_STATUS_PARSE_ERROR = 3,
+  /**
+   * A term was not well-typed.
+   */
+  KOMBI_STATUS_TYPE_ERROR = 4,
+} KombiStatus;
+
+/**
+ * An opaque handle to a term.
+ */
+typedef struct KombiTerm KombiTerm;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Return a description of the most recent error on this thread, or null if there has been none.
+ *
+ * The returned string is owned by kombi, and remains valid until the next failing call on the
+ * same thread.
+ */
+const char *kombi_last_error(void);
+
+/**
+ * Parse a program from the NUL-terminated string `source`, and on success store a handle to its
+ * term in `*out`.
+ *
+ * The program may begin with any number of definitions, but must end with a term.
+ *
+ * # Safety
+ *
+ * `source` must be null or point to a NUL-terminated string, and `out` must be null or point to
+ * memory valid for writing a pointer.
+ */
+enum KombiStatus kombi_parse(const char *source, struct KombiTerm **out);
+
+/**
+ * Type check the term referred to by `term`, and on success store its type, as a string, in
+ * `*out`.
+ *
+ * # Safety
+ *
+ * `term` must be null or a handle returned by kombi which has not yet been freed, and `out` must
+ * be null or point to memory valid for writing a pointer.
+ */
+enum KombiStatus kombi_type_check(const struct KombiTerm *term, char **out);
+
+/**
+ * Type check and β-reduce the term referred to by `term`, and on success store a handle to the
+ * result in `*out`. The original term is left untouched.
+ *
+ * # Safety
+ *
+ * `term` must be null or a handle returned by kombi which has not yet been freed, and `out` must
+ * be null or point to memory valid for writing a pointer.
+ */
+enum KombiStatus kombi_normalize(const struct KombiTerm *term, struct KombiTerm **out);
+
+/**
+ * Parse, type check, and β-reduce the program in the NUL-terminated string `source`, and on
+ * success store the result, in the same format as the `kombi` command line tool prints it but
+ * with the term in its canonical representation, in `*out`.
+ *
+ * # Safety
+ *
+ * `source` must be null or point to a NUL-terminated string, and `out` must be null or point to
+ * memory valid for writing a pointer.
+ */
+enum KombiStatus kombi_evaluate(const char *source, char **out);
+
+/**
+ * Return the canonical representation of the term referred to by `term` as a string, which can
+ * be parsed to produce the same term again, or null if `term` is null.
+ *
+ * # Safety
+ *
+ * `term` must be null or a handle returned by kombi which has not yet been freed.
+ */
+char *kombi_term_to_string(const struct KombiTerm *term);
+
+/**
+ * Free a term handle returned by kombi. Passing null does nothing.
+ *
+ * # Safety
+ *
+ * `term` must be null or a handle returned by kombi which has not yet been freed.
+ */
+void kombi_term_free(struct KombiTerm *term);
+
+/**
+ * Free a string returned by kombi. Passing null does nothing.
+ *
+ * # Safety
+ *
+ * `string` must be null or a string returned by kombi which has not yet been freed. Strings
+ * returned by `kombi_last_error` are owned by kombi, and must not be passed here.
+ */
+void kombi_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KOMBI_H */
@@ -0,0 +1,397 @@
+//! C bindings for kombi.
+//!
+//! Terms are exchanged as source strings, and handled on the C side through the opaque
+//! `KombiTerm` pointer. Every fallible function returns a `KombiStatus`; when it is anything other
+//! than `KOMBI_STATUS_OK`, a description of the error can be retrieved with `kombi_last_error`.
+//! Strings and terms returned to the caller are owned by the caller, and must be released with
+//! `kombi_string_free` and `kombi_term_free` respectively.
+//!
+//! The header in `include/kombi.h` is generated by running `cbindgen --config cbindgen.toml
+//! --output include/kombi.h` in this directory, and should be regenerated whenever the interface
+//! changes. Wherever cbindgen is installed, the tests check that it has been, by running the same
+//! command with `--verify`.
+
+#![warn(clippy::pedantic)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use kombi::environment::Environment;
+use kombi::parse::LambdaTerm;
+
+/// An opaque handle to a term.
+pub struct KombiTerm(LambdaTerm);
+
+/// The result of a call into kombi.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KombiStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A pointer argument which must not be null was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// A source string could not be parsed.
+    ParseError = 3,
+    /// A term was not well-typed.
+    TypeError = 4,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record the given error as the most recent one on this thread, returning `status`.
+fn fail(status: KombiStatus, message: &impl ToString) -> KombiStatus {
+    // NOTE: None of our error messages contain interior NULs, but if one ever does, a truncated
+    // message is better than none at all.
+    let message = message.to_string().replace('\0', "");
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message).ok());
+    status
+}
+
+/// Convert a string into one which can be handed to C.
+fn to_c_string(string: &str) -> *mut c_char {
+    CString::new(string.replace('\0', ""))
+        .expect("string should not contain NULs")
+        .into_raw()
+}
+
+/// Return a description of the most recent error on this thread, or null if there has been none.
+///
+/// The returned string is owned by kombi, and remains valid until the next failing call on the
+/// same thread.
+#[no_mangle]
+pub extern "C" fn kombi_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Parse a program from the NUL-terminated string `source`, and on success store a handle to its
+/// term in `*out`.
+///
+/// The program may begin with any number of definitions, but must end with a term.
+///
+/// # Safety
+///
+/// `source` must be null or point to a NUL-terminated string, and `out` must be null or point to
+/// memory valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_parse(
+    source: *const c_char,
+    out: *mut *mut KombiTerm,
+) -> KombiStatus {
+    if source.is_null() || out.is_null() {
+        return fail(
+            KombiStatus::NullPointer,
+            &"null pointer passed to kombi_parse",
+        );
+    }
+    let Ok(source) = CStr::from_ptr(source).to_str() else {
+        return fail(KombiStatus::InvalidUtf8, &"source is not valid UTF-8");
+    };
+
+    match Environment::new().load(source) {
+        Ok(Some(term)) => {
+            *out = Box::into_raw(Box::new(KombiTerm(term)));
+            KombiStatus::Ok
+        }
+        Ok(None) => fail(KombiStatus::ParseError, &"source does not contain a term"),
+        Err(e) => fail(KombiStatus::ParseError, &e),
+    }
+}
+
+/// Type check the term referred to by `term`, and on success store its type, as a string, in
+/// `*out`.
+///
+/// # Safety
+///
+/// `term` must be null or a handle returned by kombi which has not yet been freed, and `out` must
+/// be null or point to memory valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_type_check(
+    term: *const KombiTerm,
+    out: *mut *mut c_char,
+) -> KombiStatus {
+    if term.is_null() || out.is_null() {
+        return fail(
+            KombiStatus::NullPointer,
+            &"null pointer passed to kombi_type_check",
+        );
+    }
+
+    match (*term).0.get_type() {
+        Ok(typed_term) => {
+            *out = to_c_string(&typed_term.ty().to_canonical_string());
+            KombiStatus::Ok
+        }
+        Err(e) => fail(KombiStatus::TypeError, &e),
+    }
+}
+
+/// Type check and β-reduce the term referred to by `term`, and on success store a handle to the
+/// result in `*out`. The original term is left untouched.
+///
+/// # Safety
+///
+/// `term` must be null or a handle returned by kombi which has not yet been freed, and `out` must
+/// be null or point to memory valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_normalize(
+    term: *const KombiTerm,
+    out: *mut *mut KombiTerm,
+) -> KombiStatus {
+    if term.is_null() || out.is_null() {
+        return fail(
+            KombiStatus::NullPointer,
+            &"null pointer passed to kombi_normalize",
+        );
+    }
+
+    // Only well-typed terms are guaranteed to have a normal form, so anything else is turned away
+    // rather than risking a reduction which never finishes.
+    if let Err(e) = (*term).0.get_type() {
+        return fail(KombiStatus::TypeError, &e);
+    }
+    *out = Box::into_raw(Box::new(KombiTerm((*term).0.beta_reduce())));
+    KombiStatus::Ok
+}
+
+/// Parse, type check, and β-reduce the program in the NUL-terminated string `source`, and on
+/// success store the result, in the same format as the `kombi` command line tool prints it but
+/// with the term in its canonical representation, in `*out`.
+///
+/// # Safety
+///
+/// `source` must be null or point to a NUL-terminated string, and `out` must be null or point to
+/// memory valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_evaluate(
+    source: *const c_char,
+    out: *mut *mut c_char,
+) -> KombiStatus {
+    if out.is_null() {
+        return fail(
+            KombiStatus::NullPointer,
+            &"null pointer passed to kombi_evaluate",
+        );
+    }
+    let mut term = ptr::null_mut();
+    let status = kombi_parse(source, &raw mut term);
+    if status != KombiStatus::Ok {
+        return status;
+    }
+    let term = Box::from_raw(term);
+
+    match term.0.get_type() {
+        Ok(typed_term) => {
+            *out = to_c_string(&format!(
+                "({}):{}",
+                term.0.beta_reduce().to_canonical_string(),
+                typed_term.ty().to_canonical_string()
+            ));
+            KombiStatus::Ok
+        }
+        Err(e) => fail(KombiStatus::TypeError, &e),
+    }
+}
+
+/// Return the canonical representation of the term referred to by `term` as a string, which can
+/// be parsed to produce the same term again, or null if `term` is null.
+///
+/// # Safety
+///
+/// `term` must be null or a handle returned by kombi which has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_term_to_string(term: *const KombiTerm) -> *mut c_char {
+    if term.is_null() {
+        return ptr::null_mut();
+    }
+    to_c_string(&(*term).0.to_canonical_string())
+}
+
+/// Free a term handle returned by kombi. Passing null does nothing.
+///
+/// # Safety
+///
+/// `term` must be null or a handle returned by kombi which has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_term_free(term: *mut KombiTerm) {
+    if !term.is_null() {
+        drop(Box::from_raw(term));
+    }
+}
+
+/// Free a string returned by kombi. Passing null does nothing.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by kombi which has not yet been freed. Strings
+/// returned by `kombi_last_error` are owned by kombi, and must not be passed here.
+#[no_mangle]
+pub unsafe extern "C" fn kombi_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_char, CStr};
+    use std::io::ErrorKind;
+    use std::process::Command;
+    use std::ptr;
+
+    use super::{
+        kombi_evaluate, kombi_last_error, kombi_normalize, kombi_parse, kombi_string_free,
+        kombi_term_free, kombi_term_to_string, kombi_type_check, KombiStatus, KombiTerm,
+    };
+
+    /// Return the most recent error on this thread, which there must have been.
+    fn last_error() -> String {
+        let error = kombi_last_error();
+        assert!(!error.is_null());
+        // SAFETY: The string is owned by kombi, and no call has failed since it was returned.
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Take ownership of a string returned by kombi, freeing it.
+    fn take(string: *mut c_char) -> String {
+        assert!(!string.is_null());
+        // SAFETY: The string was returned by kombi and has not been freed.
+        unsafe {
+            let taken = CStr::from_ptr(string).to_str().unwrap().to_string();
+            kombi_string_free(string);
+            taken
+        }
+    }
+
+    #[test]
+    fn null_arguments_are_rejected() {
+        let mut term = ptr::null_mut::<KombiTerm>();
+        let mut string = ptr::null_mut::<c_char>();
+        // SAFETY: Every pointer is either null or valid for writing.
+        unsafe {
+            assert_eq!(
+                kombi_parse(ptr::null(), &raw mut term),
+                KombiStatus::NullPointer
+            );
+            assert_eq!(
+                kombi_parse(c"\\x:A. x".as_ptr(), ptr::null_mut()),
+                KombiStatus::NullPointer
+            );
+            assert_eq!(
+                kombi_type_check(ptr::null(), &raw mut string),
+                KombiStatus::NullPointer
+            );
+            assert_eq!(
+                kombi_normalize(ptr::null(), &raw mut term),
+                KombiStatus::NullPointer
+            );
+            assert_eq!(
+                kombi_evaluate(c"\\x:A. x".as_ptr(), ptr::null_mut()),
+                KombiStatus::NullPointer
+            );
+            assert_eq!(
+                kombi_evaluate(ptr::null(), &raw mut string),
+                KombiStatus::NullPointer
+            );
+            assert!(kombi_term_to_string(ptr::null()).is_null());
+            kombi_term_free(ptr::null_mut());
+            kombi_string_free(ptr::null_mut());
+        }
+        assert!(term.is_null() && string.is_null());
+        assert!(last_error().contains("null pointer"));
+    }
+
+    #[test]
+    fn invalid_sources_are_reported() {
+        let mut term = ptr::null_mut::<KombiTerm>();
+        // SAFETY: The source is NUL-terminated and `term` is valid for writing.
+        let status = unsafe { kombi_parse(c"\\x:A.".as_ptr(), &raw mut term) };
+        assert_eq!(status, KombiStatus::ParseError);
+        assert!(term.is_null());
+        assert!(!last_error().is_empty());
+    }
+
+    #[test]
+    fn ill_typed_terms_are_reported() {
+        let mut term = ptr::null_mut::<KombiTerm>();
+        let mut string = ptr::null_mut::<c_char>();
+        // SAFETY: The source is NUL-terminated, every pointer written is valid for writing, and
+        // the term is freed only once.
+        unsafe {
+            assert_eq!(
+                kombi_parse(c"\\x:A. x x".as_ptr(), &raw mut term),
+                KombiStatus::Ok
+            );
+            assert_eq!(
+                kombi_type_check(term, &raw mut string),
+                KombiStatus::TypeError
+            );
+            let message = last_error();
+            let mut normal_form = ptr::null_mut::<KombiTerm>();
+            assert_eq!(
+                kombi_normalize(term, &raw mut normal_form),
+                KombiStatus::TypeError
+            );
+            kombi_term_free(term);
+            assert_eq!(
+                kombi_evaluate(c"\\x:A. x x".as_ptr(), &raw mut string),
+                KombiStatus::TypeError
+            );
+            assert!(normal_form.is_null() && string.is_null());
+            assert_eq!(last_error(), message);
+        }
+    }
+
+    #[test]
+    fn well_typed_programs_are_evaluated() {
+        let mut string = ptr::null_mut::<c_char>();
+        let source = c"let K = \\x:A->A. \\y:B. x; K (\\z:A. z)";
+        // SAFETY: The source is NUL-terminated and `string` is valid for writing.
+        let status = unsafe { kombi_evaluate(source.as_ptr(), &raw mut string) };
+        assert_eq!(status, KombiStatus::Ok);
+        assert_eq!(take(string), "(\\x0:B. \\x1:A. x1):B->A->A");
+
+        let mut term = ptr::null_mut::<KombiTerm>();
+        let mut normal_form = ptr::null_mut::<KombiTerm>();
+        // SAFETY: As above, and each term is freed only once.
+        unsafe {
+            assert_eq!(kombi_parse(source.as_ptr(), &raw mut term), KombiStatus::Ok);
+            assert_eq!(kombi_type_check(term, &raw mut string), KombiStatus::Ok);
+            assert_eq!(take(string), "B->A->A");
+            assert_eq!(kombi_normalize(term, &raw mut normal_form), KombiStatus::Ok);
+            assert_eq!(
+                take(kombi_term_to_string(normal_form)),
+                "\\x0:B. \\x1:A. x1"
+            );
+            kombi_term_free(normal_form);
+            kombi_term_free(term);
+        }
+    }
+
+    #[test]
+    fn header_is_current() {
+        // NOTE: cbindgen is not a dependency, so the check is only made where it is installed.
+        let status = Command::new("cbindgen")
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .args(["--config", "cbindgen.toml", "--output", "include/kombi.h"])
+            .arg("--verify")
+            .status();
+        match status {
+            Ok(status) => assert!(
+                status.success(),
+                "include/kombi.h is out of date, and should be regenerated with cbindgen"
+            ),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                eprintln!("cbindgen is not installed, so include/kombi.h was not checked");
+            }
+            Err(e) => panic!("unable to run cbindgen: {e}"),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Python bindings for kombi.
+//!
+//! The module is built with maturin, which enables the `extension-module` feature, and is
+//! imported as `kombi`. Terms may either be parsed from source or built up directly out of
+//! `Term.variable`, `Term.abstraction`, and `Term.application`, with variables given as de Bruijn
+//! indices.
+
+#![warn(clippy::pedantic)]
+
+use std::rc::Rc;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+use kombi::environment::{Environment, EnvironmentError};
+use kombi::parse;
+
+create_exception!(
+    kombi,
+    KombiError,
+    PyException,
+    "The base class of every error raised by kombi."
+);
+create_exception!(
+    kombi,
+    ParseError,
+    KombiError,
+    "Raised when a program cannot be parsed."
+);
+create_exception!(
+    kombi,
+    TypeCheckError,
+    KombiError,
+    "Raised when a term is not well-typed."
+);
+
+fn to_py_err(e: EnvironmentError) -> PyErr {
+    match e {
+        EnvironmentError::Parse(e) => ParseError::new_err(e.to_string()),
+        e => TypeCheckError::new_err(e.to_string()),
+    }
+}
+
+/// A simple type, either a base type or a function type.
+#[pyclass(module = "kombi", frozen, eq)]
+#[derive(Clone, PartialEq)]
+pub struct Type(parse::Type);
+
+#[pymethods]
+impl Type {
+    /// Return the base type with the given name.
+    #[staticmethod]
+    fn base(name: String) -> Self {
+        Self(parse::Type::BaseType(name.into()))
+    }
+
+    /// Return the type of functions from `argument` to `result`.
+    #[staticmethod]
+    fn function(argument: &Self, result: &Self) -> Self {
+        Self(parse::Type::FunctionType(
+            Box::new(argument.0.clone()),
+            Box::new(result.0.clone()),
+        ))
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Type('{}')", self.0)
+    }
+}
+
+/// A term of the simply typed lambda calculus.
+#[pyclass(module = "kombi", frozen, unsendable)]
+#[derive(Clone)]
+pub struct Term(parse::LambdaTerm);
+
+impl Term {
+    /// Check that the term is closed and well-typed, returning its type.
+    fn checked_type(&self) -> PyResult<parse::Type> {
+        if !self.0.is_closed() {
+            return Err(TypeCheckError::new_err("term contains free variables"));
+        }
+        self.0
+            .get_type()
+            .map(kombi::type_check::TypedTerm::into_type)
+            .map_err(|e| to_py_err(EnvironmentError::Type(e)))
+    }
+}
+
+#[pymethods]
+impl Term {
+    /// Return the variable with the given de Bruijn index.
+    #[staticmethod]
+    fn variable(idx: u64) -> Self {
+        Self(parse::LambdaTerm::Variable { idx })
+    }
+
+    /// Return the abstraction binding a variable of type `argument_type` in `body`. The name of
+    /// the variable is only used when the term is printed.
+    #[staticmethod]
+    fn abstraction(variable: String, argument_type: &Type, body: &Self) -> Self {
+        Self(parse::LambdaTerm::Abstraction {
+            variable,
+            argument_type: argument_type.0.clone(),
+            body: Rc::new(body.0.clone()),
+        })
+    }
+
+    /// Return the application of `function` to `argument`.
+    #[staticmethod]
+    fn application(function: &Self, argument: &Self) -> Self {
+        Self(parse::LambdaTerm::Application {
+            function: Rc::new(function.0.clone()),
+            argument: Rc::new(argument.0.clone()),
+        })
+    }
+
+    /// Return the type of the term, raising `TypeCheckError` if it is not well-typed.
+    fn type_check(&self) -> PyResult<Type> {
+        self.checked_type().map(Type)
+    }
+
+    /// Return the result of β-reducing the term, raising `TypeCheckError` if it is not
+    /// well-typed.
+    fn normalize(&self) -> PyResult<Self> {
+        self.checked_type()?;
+        Ok(Self(self.0.beta_reduce()))
+    }
+
+    /// Return every term passed through as the term is β-reduced, beginning with the term itself
+    /// and taking at most `limit` steps if it is given, raising `TypeCheckError` if it is not
+    /// well-typed.
+    #[pyo3(signature = (limit = None))]
+    fn trace(&self, limit: Option<usize>) -> PyResult<Vec<Self>> {
+        self.checked_type()?;
+
+        let mut terms = vec![self.clone()];
+        let mut term = self.0.clone();
+        while limit.is_none_or(|l| terms.len() <= l) {
+            let Some((_, next)) = term.step() else {
+                break;
+            };
+            terms.push(Self(next.clone()));
+            term = next;
+        }
+        Ok(terms)
+    }
+
+    /// Return the number of nodes in the term.
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    /// Return the length of the longest path from the root of the term to a leaf.
+    fn depth(&self) -> usize {
+        self.0.depth()
+    }
+
+    /// Return whether the term contains no free variables.
+    fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Term('{}')", self.0)
+    }
+}
+
+/// Parse a program, which may begin with any number of definitions but must end with a term,
+/// raising `ParseError` if it cannot be parsed.
+#[pyfunction(name = "parse")]
+fn parse_program(source: &str) -> PyResult<Term> {
+    match Environment::new().load(source).map_err(to_py_err)? {
+        Some(term) => Ok(Term(term)),
+        None => Err(ParseError::new_err("expected a term")),
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "kombi")]
+fn kombi_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Type>()?;
+    m.add_class::<Term>()?;
+    m.add_function(wrap_pyfunction!(parse_program, m)?)?;
+    m.add("KombiError", m.py().get_type::<KombiError>())?;
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add("TypeCheckError", m.py().get_type::<TypeCheckError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+
+    use super::{parse_program, Term, Type, TypeCheckError};
+
+    #[test]
+    fn open_terms_are_rejected() {
+        // NOTE: Type checking an open term would panic, so it must be turned away beforehand.
+        let open = Term::abstraction(
+            String::from("x"),
+            &Type::base(String::from("A")),
+            &Term::variable(1),
+        );
+        let error = open.checked_type().unwrap_err();
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| assert!(error.is_instance_of::<TypeCheckError>(py)));
+        assert!(open.normalize().is_err());
+        assert!(open.trace(None).is_err());
+    }
+
+    #[test]
+    fn trace_takes_at_most_limit_steps() {
+        // NOTE: This takes three steps to reduce.
+        let term =
+            parse_program("(\\f:A->A. f) ((\\g:A->A. g) ((\\h:A->A. h) (\\x:A. x)))").unwrap();
+        let length = |limit| term.trace(limit).unwrap().len();
+        assert_eq!(length(Some(0)), 1);
+        assert_eq!(length(Some(1)), 2);
+        assert_eq!(length(Some(3)), 4);
+        assert_eq!(length(None), 4);
+        let last = term.trace(None).unwrap().pop().unwrap();
+        assert!(last.0.step().is_none());
+    }
+}
@@ -0,0 +1,170 @@
+//! WebAssembly bindings for kombi.
+//!
+//! These are intended for a web playground: a program is parsed into a `Term` once, which can
+//! then be type checked, normalized, or traced through its reduction one step at a time. Anything
+//! structured, such as a trace, is handed to JavaScript as plain objects, so that it can be
+//! rendered without further parsing.
+
+#![warn(clippy::pedantic)]
+
+use std::ops::ControlFlow;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use kombi::environment::Environment;
+use kombi::parse::LambdaTerm;
+use kombi::print::DisplayOptions;
+use kombi::traverse::Step;
+
+/// A term, parsed from a program.
+#[wasm_bindgen]
+pub struct Term(LambdaTerm);
+
+/// One state of a reduction, as returned by `Term::trace`.
+#[derive(Serialize)]
+struct TraceStep {
+    /// The term, as it would be printed.
+    term: String,
+    /// The term itself, for playgrounds which want to render its structure.
+    tree: LambdaTerm,
+    /// The path to the redex which will be contracted to reach the next state, or `None` if the
+    /// term is fully reduced.
+    redex: Option<Vec<Step>>,
+}
+
+impl TraceStep {
+    fn new(term: LambdaTerm) -> Self {
+        Self {
+            term: term.to_string(),
+            redex: term.next_redex(),
+            tree: term,
+        }
+    }
+}
+
+/// Parse a program, which may begin with any number of definitions but must end with a term.
+///
+/// # Errors
+///
+/// Returns an error if the program cannot be parsed, any of its definitions are invalid, or it
+/// does not end with a term.
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<Term, JsError> {
+    match Environment::new().load(source)? {
+        Some(term) => Ok(Term(term)),
+        None => Err(JsError::new("expected a term")),
+    }
+}
+
+impl Term {
+    /// Return every state the term passes through as it is β-reduced, as `trace` does, taking at
+    /// most `limit` steps if it is given.
+    fn states(&self, limit: Option<usize>) -> Vec<TraceStep> {
+        let mut states = vec![TraceStep::new(self.0.clone())];
+        let _ = self.0.reduce_with(&mut |_: &[Step], term: &LambdaTerm| {
+            states.push(TraceStep::new(term.clone()));
+            if limit.is_some_and(|l| states.len() > l) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        // NOTE: The observer is only consulted after a step has been taken, so a limit of zero
+        // has to be enforced here instead.
+        if let Some(l) = limit {
+            states.truncate(l + 1);
+        }
+        states
+    }
+}
+
+#[wasm_bindgen]
+impl Term {
+    /// Return the type of the term.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the term is not well-typed.
+    pub fn check(&self) -> Result<String, JsError> {
+        Ok(self.0.get_type()?.ty().to_string())
+    }
+
+    /// Return the result of β-reducing the term.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the term is not well-typed, since only well-typed terms are
+    /// guaranteed to have a normal form.
+    pub fn normalize(&self) -> Result<Term, JsError> {
+        self.0.get_type()?;
+        Ok(Term(self.0.beta_reduce()))
+    }
+
+    /// Return every state the term passes through as it is β-reduced, beginning with the term
+    /// itself, as an array of objects with fields `term` (the state as a string), `tree` (the
+    /// state as a structured term), and `redex` (the path to the redex contracted next, or
+    /// `null` for the final state).
+    ///
+    /// If `limit` is given, at most that many steps are taken.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the term is not well-typed.
+    pub fn trace(&self, limit: Option<u32>) -> Result<JsValue, JsError> {
+        self.0.get_type()?;
+        let states = self.states(limit.map(|l| l as usize));
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_missing_as_null(true);
+        Ok(states.serialize(&serializer)?)
+    }
+
+    /// Return the term as a string, writing `\` and `->` rather than `λ` and `→` if `ascii` is
+    /// set.
+    #[wasm_bindgen(js_name = toString)]
+    #[must_use]
+    pub fn to_string_with(&self, ascii: Option<bool>) -> String {
+        self.0
+            .fmt_with(DisplayOptions {
+                ascii: ascii.unwrap_or(false),
+                ..DisplayOptions::default()
+            })
+            .to_string()
+    }
+
+    /// Return the term as a structured object.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the term cannot be converted, which should never happen.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kombi::environment::Environment;
+
+    use super::Term;
+
+    /// Return the states of the trace of a term which takes three steps to reduce, taking at most
+    /// `limit` of them, along with whether the last state is fully reduced.
+    fn trace(limit: Option<usize>) -> (usize, bool) {
+        let term = Environment::new()
+            .load("(\\f:A->A. f) ((\\g:A->A. g) ((\\h:A->A. h) (\\x:A. x)))")
+            .unwrap()
+            .unwrap();
+        let states = Term(term).states(limit);
+        let finished = states.last().is_some_and(|state| state.redex.is_none());
+        (states.len(), finished)
+    }
+
+    #[test]
+    fn trace_takes_at_most_limit_steps() {
+        assert_eq!(trace(Some(0)), (1, false));
+        assert_eq!(trace(Some(1)), (2, false));
+        assert_eq!(trace(None), (4, true));
+        assert_eq!(trace(Some(10)), trace(None));
+    }
+}
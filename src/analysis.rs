@@ -0,0 +1,150 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ptr;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::symbol::Symbol;
+
+impl Type {
+    /// Return the set of names of the base types occurring in the `Type`.
+    #[must_use]
+    pub fn base_types(&self) -> BTreeSet<Symbol> {
+        let mut names = BTreeSet::new();
+        let mut stack = vec![self];
+        while let Some(ty) = stack.pop() {
+            match ty {
+                Type::BaseType(name) => {
+                    names.insert(name.clone());
+                }
+                Type::FunctionType(argument_type, return_type) => {
+                    stack.push(argument_type);
+                    stack.push(return_type);
+                }
+            }
+        }
+        names
+    }
+}
+
+impl LambdaTerm {
+    /// Return the set of de Bruijn indices which occur free in the `LambdaTerm`, relative to the
+    /// root of the term.
+    #[must_use]
+    pub fn free_indices(&self) -> BTreeSet<u64> {
+        self.subterms()
+            .filter_map(|s| match s.term {
+                LambdaTerm::Variable { idx } if *idx >= s.depth => Some(idx - s.depth),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Return the number of occurrences of the free variable with de Bruijn index `idx`, relative
+    /// to the root of the term.
+    #[must_use]
+    pub fn uses_of(&self, idx: u64) -> usize {
+        self.subterms()
+            .filter(|s| matches!(s.term, LambdaTerm::Variable { idx: i } if *i == idx + s.depth))
+            .count()
+    }
+
+    /// Return the set of de Bruijn indices of the free variables, relative to the root of the
+    /// term, which reducing the `LambdaTerm` with `beta_reduce` is certain to reduce to
+    /// abstractions whatever is substituted for. The term is strict in each of these variables.
+    ///
+    /// This is only an approximation: reduction may demand other variables too, but it always
+    /// demands these.
+    #[must_use]
+    pub fn demanded(&self) -> BTreeSet<u64> {
+        demanded(self, Vec::new())
+    }
+
+    /// Return whether the `LambdaTerm` contains no free variables.
+    ///
+    /// A subterm shared between several parts of the term, as the definitions of a program are, is
+    /// only visited once.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        // NOTE: For each subterm visited, keyed by address, this records one more than the
+        // largest de Bruijn index free in it, or zero if it is closed. Each subterm is taken off
+        // the stack twice, first to visit its children and then, once they have been, itself.
+        let mut free = BTreeMap::<*const LambdaTerm, u64>::new();
+        let mut stack = vec![(self, false)];
+        while let Some((term, finished)) = stack.pop() {
+            let key = ptr::from_ref(term);
+            if free.contains_key(&key) {
+                continue;
+            }
+            match (term, finished) {
+                (LambdaTerm::Variable { idx }, _) => {
+                    free.insert(key, idx + 1);
+                }
+                (LambdaTerm::Abstraction { body, .. }, false) => {
+                    stack.extend([(term, true), (body.as_ref(), false)]);
+                }
+                (LambdaTerm::Application { function, argument }, false) => {
+                    stack.extend([(term, true), (function, false), (argument, false)]);
+                }
+                (LambdaTerm::Abstraction { body, .. }, true) => {
+                    let body = free[&ptr::from_ref(body.as_ref())];
+                    free.insert(key, body.saturating_sub(1));
+                }
+                (LambdaTerm::Application { function, argument }, true) => {
+                    let function = free[&ptr::from_ref(function.as_ref())];
+                    let argument = free[&ptr::from_ref(argument.as_ref())];
+                    free.insert(key, function.max(argument));
+                }
+            }
+        }
+        free[&ptr::from_ref(self)] == 0
+    }
+
+    /// Return the set of names of the base types occurring in the type annotations of the
+    /// `LambdaTerm`.
+    #[must_use]
+    pub fn base_types(&self) -> BTreeSet<Symbol> {
+        self.subterms()
+            .filter_map(|s| match s.term {
+                LambdaTerm::Abstraction { argument_type, .. } => Some(argument_type.base_types()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// Return the variables demanded by reducing `term` applied to `arguments`, the last of which it
+/// is applied to first, each along with the number of binders between the scope of the argument
+/// and that of `term`, as given by `LambdaTerm::demanded`.
+fn demanded<'a>(
+    mut term: &'a LambdaTerm,
+    mut arguments: Vec<(&'a LambdaTerm, u64)>,
+) -> BTreeSet<u64> {
+    while let LambdaTerm::Application { function, argument } = term {
+        arguments.push((argument, 0));
+        term = function;
+    }
+    match term {
+        LambdaTerm::Variable { idx } => BTreeSet::from([*idx]),
+        LambdaTerm::Abstraction { body, .. } => {
+            // NOTE: `beta_reduce` stops at abstractions, so nothing under one is demanded.
+            let Some((argument, depth)) = arguments.pop() else {
+                return BTreeSet::new();
+            };
+            // The body is reduced with the argument substituted for its variable, so if it
+            // demands that variable, it demands whatever the argument demands too.
+            let arguments = arguments.into_iter().map(|(a, d)| (a, d + 1)).collect();
+            let in_body = demanded(body, arguments);
+            let mut demanded: BTreeSet<_> = in_body
+                .iter()
+                .filter_map(|idx| idx.checked_sub(1))
+                .collect();
+            if in_body.contains(&0) {
+                demanded.extend(argument.demanded().into_iter().map(|idx| idx + depth));
+            }
+            demanded
+        }
+        LambdaTerm::Application { .. } => unreachable!("the spine should have been unwound"),
+    }
+}
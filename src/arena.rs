@@ -0,0 +1,463 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::symbol::Symbol;
+use crate::type_check::TypeError;
+
+/// A handle to a term stored in a `TermArena`.
+///
+/// Terms stored by `TermArena::insert` are hash-consed, so two handles returned by it are equal
+/// exactly when the terms they refer to are equal, names included. Nodes allocated in the course
+/// of reduction are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TermId(u32);
+
+/// A handle to a type stored in a `TermArena`.
+///
+/// Types are hash-consed, so two `TypeId`s from the same arena are equal exactly when the types
+/// they refer to are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeId(u32);
+
+/// A handle to a variable name stored in a `TermArena`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NameId(u32);
+
+/// A single node of a term stored in a `TermArena`, referring to its children by handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Node {
+    Variable {
+        idx: u64,
+    },
+    Abstraction {
+        variable: NameId,
+        argument_type: TypeId,
+        body: TermId,
+    },
+    Application {
+        function: TermId,
+        argument: TermId,
+    },
+}
+
+/// A single node of a type stored in a `TermArena`, referring to its children by handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TypeNode {
+    BaseType(NameId),
+    FunctionType(TypeId, TypeId),
+}
+
+/// A store of terms and types in which every node lives in one contiguous allocation and refers
+/// to its children by index.
+///
+/// Nodes are never freed individually; the whole arena is dropped at once. This makes allocation
+/// a matter of pushing onto a `Vec`, and keeps the nodes of a term close together in memory,
+/// which is considerably kinder to the cache than chasing a pointer per node.
+///
+/// Since nodes never change once allocated, the type of every term checked by `type_of` is kept,
+/// keyed by its handle and the types of the variables free in it, so that a subterm which occurs
+/// many times, as the definitions and macros of a program do once they are expanded, is only
+/// checked once.
+#[derive(Debug, Clone, Default)]
+pub struct TermArena {
+    nodes: Vec<Node>,
+    /// For each node, one more than the largest de Bruijn index free in it, or zero if it is
+    /// closed.
+    free: Vec<u64>,
+    node_ids: BTreeMap<Node, TermId>,
+    /// The type of each term checked so far, keyed by its handle and the types of the variables
+    /// free in it, the innermost last.
+    typings: BTreeMap<(TermId, Vec<TypeId>), TypeId>,
+    types: Vec<TypeNode>,
+    type_ids: BTreeMap<TypeNode, TypeId>,
+    names: Vec<String>,
+    name_ids: BTreeMap<String, NameId>,
+}
+
+impl TermArena {
+    /// Create a new, empty `TermArena`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the number of term nodes stored in the arena.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Return whether the arena contains no term nodes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Return the node referred to by the given handle.
+    #[must_use]
+    pub fn node(&self, id: TermId) -> Node {
+        self.nodes[id.0 as usize]
+    }
+
+    /// Return the type node referred to by the given handle.
+    #[must_use]
+    pub fn type_node(&self, id: TypeId) -> TypeNode {
+        self.types[id.0 as usize]
+    }
+
+    /// Return the name referred to by the given handle.
+    #[must_use]
+    pub fn name(&self, id: NameId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    /// Store a new node in the arena, returning its handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena already holds `u32::MAX` nodes.
+    pub fn alloc(&mut self, node: Node) -> TermId {
+        let id = TermId(u32::try_from(self.nodes.len()).expect("arena should not overflow"));
+        let free = match node {
+            Node::Variable { idx } => idx + 1,
+            Node::Abstraction { body, .. } => self.free[body.0 as usize].saturating_sub(1),
+            Node::Application { function, argument } => {
+                self.free[function.0 as usize].max(self.free[argument.0 as usize])
+            }
+        };
+        self.nodes.push(node);
+        self.free.push(free);
+        id
+    }
+
+    /// Return the handle of the node equal to the given one, storing it if there is none.
+    fn intern(&mut self, node: Node) -> TermId {
+        if let Some(id) = self.node_ids.get(&node) {
+            return *id;
+        }
+        let id = self.alloc(node);
+        self.node_ids.insert(node, id);
+        id
+    }
+
+    fn intern_type_node(&mut self, node: TypeNode) -> TypeId {
+        if let Some(id) = self.type_ids.get(&node) {
+            return *id;
+        }
+        let id = TypeId(u32::try_from(self.types.len()).expect("arena should not overflow"));
+        self.types.push(node);
+        self.type_ids.insert(node, id);
+        id
+    }
+
+    fn intern_name(&mut self, name: &str) -> NameId {
+        if let Some(id) = self.name_ids.get(name) {
+            return *id;
+        }
+        let id = NameId(u32::try_from(self.names.len()).expect("arena should not overflow"));
+        self.names.push(name.to_string());
+        self.name_ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Store a `Type` in the arena, returning its handle.
+    pub fn insert_type(&mut self, ty: &Type) -> TypeId {
+        let node = match ty {
+            Type::BaseType(name) => TypeNode::BaseType(self.intern_name(name)),
+            Type::FunctionType(argument_type, return_type) => TypeNode::FunctionType(
+                self.insert_type(argument_type),
+                self.insert_type(return_type),
+            ),
+        };
+        self.intern_type_node(node)
+    }
+
+    /// Store a `LambdaTerm` in the arena, returning the handle of its root.
+    ///
+    /// Equal subterms are stored only once, and a subterm shared between several parts of the
+    /// term, as the definitions of a program are, is only visited once.
+    pub fn insert(&mut self, term: &LambdaTerm) -> TermId {
+        self.insert_shared(term, &mut BTreeMap::new())
+    }
+
+    /// Store a `LambdaTerm` in the arena as `insert` does, where `inserted` holds the handle of
+    /// every subterm stored so far, keyed by its address.
+    fn insert_shared(
+        &mut self,
+        term: &LambdaTerm,
+        inserted: &mut BTreeMap<*const LambdaTerm, TermId>,
+    ) -> TermId {
+        if let Some(id) = inserted.get(&core::ptr::from_ref(term)) {
+            return *id;
+        }
+        let node = match term {
+            LambdaTerm::Variable { idx } => Node::Variable { idx: *idx },
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => Node::Abstraction {
+                variable: self.intern_name(variable),
+                argument_type: self.insert_type(argument_type),
+                body: self.insert_shared(body, inserted),
+            },
+            LambdaTerm::Application { function, argument } => Node::Application {
+                function: self.insert_shared(function, inserted),
+                argument: self.insert_shared(argument, inserted),
+            },
+        };
+        let id = self.intern(node);
+        inserted.insert(core::ptr::from_ref(term), id);
+        id
+    }
+
+    /// Reconstruct the `Type` referred to by the given handle.
+    #[must_use]
+    pub fn ty(&self, id: TypeId) -> Type {
+        match self.type_node(id) {
+            TypeNode::BaseType(name) => Type::BaseType(Symbol::new(self.name(name))),
+            TypeNode::FunctionType(argument_type, return_type) => Type::FunctionType(
+                Box::new(self.ty(argument_type)),
+                Box::new(self.ty(return_type)),
+            ),
+        }
+    }
+
+    /// Reconstruct the `LambdaTerm` referred to by the given handle.
+    #[must_use]
+    pub fn term(&self, id: TermId) -> LambdaTerm {
+        match self.node(id) {
+            Node::Variable { idx } => LambdaTerm::Variable { idx },
+            Node::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: self.name(variable).to_string(),
+                argument_type: self.ty(argument_type),
+                body: Rc::new(self.term(body)),
+            },
+            Node::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(self.term(function)),
+                argument: Rc::new(self.term(argument)),
+            },
+        }
+    }
+
+    /// Return the type of the term referred to by the given handle if it is well-typed, or an
+    /// appropriate `TypeError` if it is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if the term is not well-typed.
+    pub fn type_of(&mut self, id: TermId) -> Result<TypeId, TypeError> {
+        self.type_of_in_context(id, &mut Vec::new())
+    }
+
+    fn type_of_in_context(
+        &mut self,
+        id: TermId,
+        ctx: &mut Vec<TypeId>,
+    ) -> Result<TypeId, TypeError> {
+        let free = usize::try_from(self.free[id.0 as usize])
+            .expect("de Bruijn index should fit in a usize");
+        let key = (id, ctx[ctx.len() - free..].to_vec());
+        if let Some(ty) = self.typings.get(&key) {
+            return Ok(*ty);
+        }
+        let ty = self.check_in_context(id, ctx)?;
+        self.typings.insert(key, ty);
+        Ok(ty)
+    }
+
+    /// Type check the term referred to by the given handle, as `type_of_in_context` does, without
+    /// consulting the types already found for it.
+    fn check_in_context(&mut self, id: TermId, ctx: &mut Vec<TypeId>) -> Result<TypeId, TypeError> {
+        match self.node(id) {
+            Node::Variable { idx } => {
+                let idx = usize::try_from(idx).expect("de Bruijn index should fit in a usize");
+                Ok(ctx[ctx.len() - (idx + 1)])
+            }
+            Node::Abstraction {
+                argument_type,
+                body,
+                ..
+            } => {
+                ctx.push(argument_type);
+                let return_type = self.type_of_in_context(body, ctx);
+                ctx.pop();
+
+                Ok(self.intern_type_node(TypeNode::FunctionType(argument_type, return_type?)))
+            }
+            Node::Application { function, argument } => {
+                let function_type = self.type_of_in_context(function, ctx)?;
+                let argument_type = self.type_of_in_context(argument, ctx)?;
+
+                match self.type_node(function_type) {
+                    // NOTE: Since types are hash-consed, comparing handles is enough to compare
+                    // types, no matter how large they are.
+                    TypeNode::FunctionType(function_argument_type, return_type)
+                        if function_argument_type == argument_type =>
+                    {
+                        Ok(return_type)
+                    }
+                    _ => Err(TypeError::InvalidApplication {
+                        function: Rc::new(self.term(function)),
+                        function_type: self.ty(function_type),
+                        argument: Rc::new(self.term(argument)),
+                        argument_type: self.ty(argument_type),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Shift every free variable with de Bruijn index at least `cutoff` in the term referred to
+    /// by the given handle by `amount`, returning the handle of the result.
+    ///
+    /// Subterms which are unaffected by the shift are shared with the original term rather than
+    /// copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if shifting would make some de Bruijn index negative.
+    pub fn shift(&mut self, id: TermId, amount: i64, cutoff: u64) -> TermId {
+        match self.node(id) {
+            Node::Variable { idx } => {
+                if idx >= cutoff && amount != 0 {
+                    self.alloc(Node::Variable {
+                        idx: idx
+                            .checked_add_signed(amount)
+                            .expect("shifting should not make a de Bruijn index negative"),
+                    })
+                } else {
+                    id
+                }
+            }
+            Node::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => {
+                let shifted = self.shift(body, amount, cutoff + 1);
+                if shifted == body {
+                    id
+                } else {
+                    self.alloc(Node::Abstraction {
+                        variable,
+                        argument_type,
+                        body: shifted,
+                    })
+                }
+            }
+            Node::Application { function, argument } => {
+                let shifted_function = self.shift(function, amount, cutoff);
+                let shifted_argument = self.shift(argument, amount, cutoff);
+                if shifted_function == function && shifted_argument == argument {
+                    id
+                } else {
+                    self.alloc(Node::Application {
+                        function: shifted_function,
+                        argument: shifted_argument,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Replace every occurrence of the free variable with de Bruijn index `idx` in the term
+    /// referred to by the given handle with the term referred to by `replacement`, returning the
+    /// handle of the result.
+    pub fn substitute(&mut self, id: TermId, idx: u64, replacement: TermId) -> TermId {
+        self.substitute_at_depth(id, idx, replacement, 0)
+    }
+
+    fn substitute_at_depth(
+        &mut self,
+        id: TermId,
+        idx: u64,
+        replacement: TermId,
+        depth: u64,
+    ) -> TermId {
+        match self.node(id) {
+            Node::Variable { idx: i } => {
+                if i == idx + depth {
+                    self.shift(
+                        replacement,
+                        i64::try_from(depth).expect("depth should fit in an i64"),
+                        0,
+                    )
+                } else {
+                    id
+                }
+            }
+            Node::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => {
+                let substituted = self.substitute_at_depth(body, idx, replacement, depth + 1);
+                if substituted == body {
+                    id
+                } else {
+                    self.alloc(Node::Abstraction {
+                        variable,
+                        argument_type,
+                        body: substituted,
+                    })
+                }
+            }
+            Node::Application { function, argument } => {
+                let substituted_function =
+                    self.substitute_at_depth(function, idx, replacement, depth);
+                let substituted_argument =
+                    self.substitute_at_depth(argument, idx, replacement, depth);
+                if substituted_function == function && substituted_argument == argument {
+                    id
+                } else {
+                    self.alloc(Node::Application {
+                        function: substituted_function,
+                        argument: substituted_argument,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Treating the term referred to by `body` as the body of an abstraction, replace the
+    /// variable bound by that abstraction with the term referred to by `argument`, returning the
+    /// handle of the result.
+    pub fn open(&mut self, body: TermId, argument: TermId) -> TermId {
+        let argument = self.shift(argument, 1, 0);
+        let substituted = self.substitute(body, 0, argument);
+        self.shift(substituted, -1, 0)
+    }
+
+    /// Apply β-reduction to the term referred to by the given handle, returning the handle of the
+    /// result.
+    ///
+    /// This follows exactly the same lazy strategy as `LambdaTerm::beta_reduce`.
+    pub fn beta_reduce(&mut self, id: TermId) -> TermId {
+        match self.node(id) {
+            Node::Application { function, argument } => {
+                let function = self.beta_reduce(function);
+                match self.node(function) {
+                    Node::Abstraction { body, .. } => {
+                        let reduced = self.open(body, argument);
+                        self.beta_reduce(reduced)
+                    }
+                    _ => {
+                        // NOTE: As in `LambdaTerm::beta_reduce`, this would only be reachable
+                        // when β-reducing terms which contain free variables.
+                        unreachable!()
+                    }
+                }
+            }
+            _ => id,
+        }
+    }
+}
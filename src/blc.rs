@@ -0,0 +1,157 @@
+//! Tromp's Binary Lambda Calculus, in which an untyped term is written as a string of bits.
+//!
+//! An abstraction is written as `00` followed by its body, an application as `01` followed by its
+//! function and then its argument, and the variable with de Bruijn index `n` as `n + 1` ones
+//! followed by a zero. Bits are written as the ASCII characters `0` and `1`.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use crate::untyped::UntypedTerm;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlcError {
+    /// The input ended before the term did.
+    UnexpectedEnd,
+    /// The input contains a character other than a bit or whitespace, at the given byte offset.
+    InvalidCharacter { character: char, position: usize },
+    /// The input continues past the end of the term, from the given byte offset.
+    TrailingInput { position: usize },
+}
+
+impl Display for BlcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => {
+                write!(f, "binary lambda calculus ended in the middle of a term")
+            }
+            Self::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {character:?} at offset {position} in binary lambda calculus"
+            ),
+            Self::TrailingInput { position } => write!(
+                f,
+                "unexpected input after the end of the term at offset {position} in binary lambda \
+                 calculus"
+            ),
+        }
+    }
+}
+
+impl Error for BlcError {}
+
+/// A term whose encoding has begun, but which is still waiting for some of its subterms.
+enum Frame {
+    /// An abstraction, waiting for its body.
+    Abstraction,
+    /// An application, waiting for its function.
+    Function,
+    /// An application, waiting for its argument.
+    Argument(UntypedTerm),
+}
+
+impl UntypedTerm {
+    /// Return the encoding of the `UntypedTerm` in binary lambda calculus.
+    #[must_use]
+    pub fn to_blc(&self) -> String {
+        let mut bits = String::new();
+        let mut stack = vec![self];
+        while let Some(term) = stack.pop() {
+            match term {
+                UntypedTerm::Variable { idx } => {
+                    for _ in 0..=*idx {
+                        bits.push('1');
+                    }
+                    bits.push('0');
+                }
+                UntypedTerm::Abstraction { body, .. } => {
+                    bits.push_str("00");
+                    stack.push(body);
+                }
+                UntypedTerm::Application { function, argument } => {
+                    bits.push_str("01");
+                    stack.push(argument);
+                    stack.push(function);
+                }
+            }
+        }
+        bits
+    }
+
+    /// Decode an `UntypedTerm` from binary lambda calculus, ignoring any whitespace between the
+    /// bits. The encoding records no names, so every abstraction binds the name `x`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BlcError` if the input is not the encoding of exactly one term.
+    pub fn from_blc(input: &str) -> Result<Self, BlcError> {
+        let mut characters = input.char_indices().filter(|(_, c)| !c.is_whitespace());
+        let mut next = || match characters.next() {
+            Some((_, '0')) => Ok(false),
+            Some((_, '1')) => Ok(true),
+            Some((position, character)) => Err(BlcError::InvalidCharacter {
+                character,
+                position,
+            }),
+            None => Err(BlcError::UnexpectedEnd),
+        };
+
+        let mut stack = Vec::new();
+        let term = 'read: loop {
+            let mut term = match (next()?, next()?) {
+                (false, false) => {
+                    stack.push(Frame::Abstraction);
+                    continue;
+                }
+                (false, true) => {
+                    stack.push(Frame::Function);
+                    continue;
+                }
+                (true, false) => UntypedTerm::Variable { idx: 0 },
+                (true, true) => {
+                    let mut idx = 1;
+                    while next()? {
+                        idx += 1;
+                    }
+                    UntypedTerm::Variable { idx }
+                }
+            };
+
+            // A complete term has been read, so it finishes as many of the enclosing frames as
+            // it can, until one needs another subterm.
+            loop {
+                match stack.pop() {
+                    None => break 'read term,
+                    Some(Frame::Abstraction) => {
+                        term = UntypedTerm::Abstraction {
+                            variable: "x".to_string(),
+                            body: Box::new(term),
+                        };
+                    }
+                    Some(Frame::Function) => {
+                        stack.push(Frame::Argument(term));
+                        break;
+                    }
+                    Some(Frame::Argument(function)) => {
+                        term = UntypedTerm::Application {
+                            function: Box::new(function),
+                            argument: Box::new(term),
+                        };
+                    }
+                }
+            }
+        };
+
+        match characters.next() {
+            Some((position, _)) => Err(BlcError::TrailingInput { position }),
+            None => Ok(term),
+        }
+    }
+}
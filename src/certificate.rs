@@ -0,0 +1,312 @@
+//! Certificates of evaluation, which record the position of every redex contracted in evaluating
+//! a term, so that the result can be checked by replaying them, without trusting whatever
+//! evaluated the term in the first place.
+//!
+//! A certificate is written as text, as in
+//!
+//! ```text
+//! kombi certificate
+//! result: \x0:A. x0
+//! redexes: f2 f ε
+//! ```
+//!
+//! where the result is written canonically, and each redex is written as the path to it from the
+//! root of the term, as a sequence of `b` for the body of an abstraction, `f` for the function of
+//! an application and `a` for its argument, each optionally followed by the number of times it is
+//! repeated, or as `ε` for the root itself.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use crate::parse::LambdaTerm;
+use crate::traverse::Step;
+use crate::zipper::TermZipper;
+
+/// The line with which every certificate begins.
+const HEADER: &str = "kombi certificate";
+
+/// A record of the evaluation of some term, made by `LambdaTerm::certify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Certificate {
+    /// The path to each redex contracted, in the order in which they were contracted, as runs of
+    /// each step along with the number of times it is repeated.
+    ///
+    /// Paths are kept as runs, as they are written, so that a certificate claiming some absurdly
+    /// long path takes no more space to check than it does to write.
+    pub redexes: Vec<Vec<(Step, usize)>>,
+    /// The term which evaluation reached.
+    pub result: LambdaTerm,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CertificateError {
+    /// The text does not begin with the line `kombi certificate`.
+    MissingHeader,
+    /// The text has no line giving the field with the given name.
+    MissingField(&'static str),
+    /// The given word of the `redexes:` line is not the path to a redex.
+    InvalidPath(String),
+    /// The result could not be parsed, for the given reason.
+    InvalidResult(String),
+    /// The redex with the given index, counting from zero, is not found in the term which the
+    /// redexes before it reduce the term to.
+    NotARedex { index: usize },
+    /// Contracting every redex reduces the term to the given term, rather than to the result.
+    WrongResult(LambdaTerm),
+    /// The result can be reduced further, by contracting the redex at the root of its spine.
+    Unfinished,
+}
+
+impl Display for CertificateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "the certificate does not begin with `{HEADER}`"),
+            Self::MissingField(field) => write!(f, "the certificate has no `{field}:` line"),
+            Self::InvalidPath(word) => write!(f, "`{word}` is not the path to a redex"),
+            Self::InvalidResult(e) => write!(f, "the result could not be parsed: {e}"),
+            Self::NotARedex { index } => write!(
+                f,
+                "redex {index} is not found in the term which the redexes before it reduce to"
+            ),
+            Self::WrongResult(reached) => write!(
+                f,
+                "the redexes reduce the term to {}, rather than to the result",
+                reached.to_canonical_string()
+            ),
+            Self::Unfinished => write!(f, "the result has not been fully evaluated"),
+        }
+    }
+}
+
+impl Error for CertificateError {}
+
+/// Return the letter with which the given step is written.
+fn letter(step: Step) -> char {
+    match step {
+        Step::Body => 'b',
+        Step::Function => 'f',
+        Step::Argument => 'a',
+    }
+}
+
+/// Return the runs of repeated steps which make up the given path.
+fn runs(path: &[Step]) -> Vec<(Step, usize)> {
+    let mut runs = Vec::new();
+    let mut rest = path;
+    while let Some(&step) = rest.first() {
+        let run = rest.iter().take_while(|s| **s == step).count();
+        runs.push((step, run));
+        rest = &rest[run..];
+    }
+    runs
+}
+
+/// Write the path made up of the given runs in the compact form used by certificates.
+fn write_path(f: &mut Formatter<'_>, runs: &[(Step, usize)]) -> fmt::Result {
+    if runs.is_empty() {
+        return write!(f, "ε");
+    }
+    for &(step, run) in runs {
+        write!(f, "{}", letter(step))?;
+        if run > 1 {
+            write!(f, "{run}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse a path written in the compact form used by certificates, returning its runs.
+fn parse_path(word: &str) -> Result<Vec<(Step, usize)>, CertificateError> {
+    let invalid = || CertificateError::InvalidPath(word.to_string());
+    if word == "ε" {
+        return Ok(Vec::new());
+    }
+    let mut path = Vec::new();
+    let mut chars = word.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let step = match c {
+            'b' => Step::Body,
+            'f' => Step::Function,
+            'a' => Step::Argument,
+            _ => return Err(invalid()),
+        };
+        let mut count = None;
+        while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+            let digit = digit.to_digit(10).expect("the character should be a digit") as usize;
+            count = Some(
+                count
+                    .unwrap_or(0_usize)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit))
+                    .ok_or_else(invalid)?,
+            );
+        }
+        match count {
+            None => path.push((step, 1)),
+            Some(0) => return Err(invalid()),
+            Some(count) => path.push((step, count)),
+        }
+    }
+    if path.is_empty() {
+        return Err(invalid());
+    }
+    Ok(path)
+}
+
+impl Display for Certificate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "result: {}", self.result.to_canonical_string())?;
+        write!(f, "redexes:")?;
+        for path in &self.redexes {
+            write!(f, " ")?;
+            write_path(f, path)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl FromStr for Certificate {
+    type Err = CertificateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err(CertificateError::MissingHeader);
+        }
+        let mut field = |name| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|line| line.strip_prefix(':'))
+                .ok_or(CertificateError::MissingField(name))
+        };
+        let result = field("result")?
+            .parse::<LambdaTerm>()
+            .map_err(|e| CertificateError::InvalidResult(e.to_string()))?;
+        let redexes = field("redexes")?
+            .split_whitespace()
+            .map(parse_path)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { redexes, result })
+    }
+}
+
+impl Certificate {
+    /// Check that contracting the redexes of the `Certificate` one after another reduces the
+    /// given term to its result, and that the result cannot be evaluated any further.
+    ///
+    /// Only redexes are contracted, so this establishes that the result is β-equivalent to the
+    /// term, whatever produced the certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CertificateError` describing the first check which fails.
+    pub fn verify(&self, term: &LambdaTerm) -> Result<(), CertificateError> {
+        let mut term = term.clone();
+        for (index, runs) in self.redexes.iter().enumerate() {
+            // NOTE: Each step is taken as it is read from its run, so that a run longer than the
+            // term is deep fails as soon as it leaves the term.
+            let mut zipper = TermZipper::new(term);
+            for &(step, run) in runs {
+                for _ in 0..run {
+                    if !zipper.down(step) {
+                        return Err(CertificateError::NotARedex { index });
+                    }
+                }
+            }
+            let contracted = zipper
+                .focus()
+                .contract()
+                .ok_or(CertificateError::NotARedex { index })?;
+            zipper.replace(contracted);
+            term = zipper.into_term();
+        }
+        if term != self.result {
+            return Err(CertificateError::WrongResult(term));
+        }
+        if term.next_redex().is_some() {
+            return Err(CertificateError::Unfinished);
+        }
+        Ok(())
+    }
+}
+
+impl LambdaTerm {
+    /// Apply β-reduction one step at a time, following exactly the same strategy as
+    /// `beta_reduce`, and return a `Certificate` recording every redex contracted along the way.
+    #[must_use]
+    pub fn certify(&self) -> Certificate {
+        let mut redexes = Vec::new();
+        let mut term = self.clone();
+        while let Some((path, next)) = term.step() {
+            redexes.push(runs(&path));
+            term = next;
+        }
+        Certificate {
+            redexes,
+            result: term,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::{Certificate, CertificateError};
+    use crate::parse::LambdaTerm;
+
+    /// Return the certificate of the evaluation of `(λf:A->A. λx:A. f (f x)) (λy:A. y)`, along
+    /// with the term.
+    fn certified() -> (LambdaTerm, Certificate) {
+        let term = "(\\f:A->A. \\x:A. f (f x)) (\\y:A. y)"
+            .parse::<LambdaTerm>()
+            .unwrap();
+        let certificate = term.certify();
+        (term, certificate)
+    }
+
+    #[test]
+    fn certificates_round_trip_and_verify() {
+        let (term, certificate) = certified();
+        assert!(!certificate.redexes.is_empty());
+        let written = certificate.to_string();
+        let read = written.parse::<Certificate>().unwrap();
+        assert_eq!(read, certificate);
+        assert_eq!(read.verify(&term), Ok(()));
+    }
+
+    #[test]
+    fn malformed_certificates_are_rejected() {
+        let (term, certificate) = certified();
+        let result = certificate.result.to_canonical_string();
+        let with_redexes =
+            |redexes: &str| format!("kombi certificate\nresult: {result}\nredexes: {redexes}\n");
+
+        assert_eq!(
+            "result: x".parse::<Certificate>(),
+            Err(CertificateError::MissingHeader)
+        );
+        for word in ["b0", "q", "b18446744073709551616"] {
+            assert_eq!(
+                with_redexes(word).parse::<Certificate>(),
+                Err(CertificateError::InvalidPath(word.into()))
+            );
+        }
+        // NOTE: Runs this long must fail as soon as they leave the term, rather than being
+        // followed, or allocated, step by step.
+        for word in ["b999999999999", "b18446744073709551615"] {
+            let certificate = with_redexes(word).parse::<Certificate>().unwrap();
+            assert_eq!(
+                certificate.verify(&term),
+                Err(CertificateError::NotARedex { index: 0 })
+            );
+        }
+        let unfinished = with_redexes("").parse::<Certificate>().unwrap();
+        assert!(unfinished.verify(&term).is_err());
+    }
+}
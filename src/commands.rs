@@ -0,0 +1,467 @@
+//! The subcommands of the command line interface, each of which has its own arguments and entry
+//! point.
+
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::exit;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+
+use kombi::environment::Environment;
+use kombi::inference::InferenceError;
+use kombi::lint::{lint, Level, Levels, Lint, LintKind};
+use kombi::parse::LambdaTerm;
+use kombi::prelude;
+use kombi::reduce::Equivalence;
+use kombi::untyped::UntypedTerm;
+
+use crate::load_or_exit;
+
+pub mod convert;
+pub mod difftest;
+pub mod doc;
+pub mod export;
+pub mod fmt;
+pub mod generate;
+pub mod grade;
+pub mod grammar;
+pub mod hash;
+pub mod interface;
+pub mod lint;
+pub mod lsp;
+pub mod min;
+pub mod repl;
+pub mod selftest;
+pub mod type_at;
+pub mod verify;
+
+/// Return the given seed, or if there is none, one taken from the clock. In the latter case, the
+/// seed is reported on stderr, so that the same run can be reproduced.
+pub fn seed_or_clock(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let seed = now.as_secs() ^ u64::from(now.subsec_nanos());
+        eprintln!("seed: {seed}");
+        seed
+    })
+}
+
+/// Return the line and column, both counted from 1, of the given byte offset in `source`.
+pub fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Return the byte offset in `source` of the given line and column, both counted from 1, or
+/// `None` if there is no such position. A column just past the end of a line is allowed.
+pub fn offset_of(source: &str, line: usize, column: usize) -> Option<usize> {
+    let start = if line == 1 {
+        0
+    } else {
+        source.match_indices('\n').nth(line.checked_sub(2)?)?.0 + 1
+    };
+    let text = source[start..].split('\n').next()?;
+    let column = column.checked_sub(1)?;
+    text.char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .nth(column)
+        .map(|i| start + i)
+}
+
+/// Options controlling the `Level` at which each kind of lint is reported.
+#[derive(Args)]
+pub struct LevelArgs {
+    /// Report lints of the given kind as warnings. May be given more than once
+    #[arg(short = 'W', long = "warn", value_name = "LINT", value_enum)]
+    warn: Vec<LintKind>,
+
+    /// Do not report lints of the given kind. May be given more than once
+    #[arg(short = 'A', long = "allow", value_name = "LINT", value_enum)]
+    allow: Vec<LintKind>,
+
+    /// Report lints of the given kind as errors. May be given more than once
+    #[arg(short = 'D', long = "deny", value_name = "LINT", value_enum)]
+    deny: Vec<LintKind>,
+
+    /// Report every lint which would be a warning as an error
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+impl LevelArgs {
+    /// Return the given `Levels`, adjusted as requested. A kind given to more than one option is
+    /// denied in preference to being warned about, and warned about in preference to being
+    /// allowed.
+    pub fn levels(&self, mut levels: Levels) -> Levels {
+        for (kinds, level) in [
+            (&self.allow, Level::Allow),
+            (&self.warn, Level::Warn),
+            (&self.deny, Level::Deny),
+        ] {
+            for &kind in kinds {
+                levels.set(kind, level);
+            }
+        }
+        if self.deny_warnings {
+            levels.deny_warnings();
+        }
+        levels
+    }
+}
+
+/// Return the given lint, found in `source`, read from the file at `path`, as a line prefixed by
+/// its position and labelled as an error if it is reported at `Level::Deny`, or by its severity
+/// otherwise.
+pub fn describe_lint(path: &Path, source: &str, reported: &Lint, level: Level) -> String {
+    let (line, column) = line_and_column(source, reported.span.start);
+    let label = if level == Level::Deny {
+        String::from("error")
+    } else {
+        reported.kind.severity().to_string()
+    };
+    format!(
+        "{}:{line}:{column}: {label}[{}]: {}",
+        path.display(),
+        reported.kind.name(),
+        reported.message
+    )
+}
+
+/// Report the lints of the program in the given file on stderr, at the given `Levels`, exiting if
+/// any of them are errors. Files which cannot be read or parsed are left for whatever reads them
+/// next to report.
+pub fn lint_or_exit(path: &Path, levels: &Levels) {
+    let Ok(source) = read_to_string(path) else {
+        return;
+    };
+    let Ok(lints) = lint(&source) else {
+        return;
+    };
+    let mut denied = false;
+    for lint in &lints {
+        let level = levels.get(lint.kind);
+        if level > Level::Allow {
+            eprintln!("{}", describe_lint(path, &source, lint, level));
+        }
+        denied |= level == Level::Deny;
+    }
+    if denied {
+        exit(1);
+    }
+}
+
+/// Escape the characters of the given string which are special in HTML text.
+pub fn escape_html(string: &str) -> String {
+    string
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A format in which terms can be read and written.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// kombi's own syntax
+    Kombi,
+    /// The conventional notation of the untyped lambda calculus, as in `\x y. x`
+    Lambda,
+    /// Tromp's binary lambda calculus, written as ASCII `0`s and `1`s, in which types are erased
+    Blc,
+    /// The JSON serialization of kombi's abstract syntax tree, as produced by its serde
+    /// implementations
+    Json,
+}
+
+/// A term read from a file, which only has types if its format records them.
+pub enum Input {
+    Typed(LambdaTerm),
+    Untyped(UntypedTerm),
+}
+
+impl Input {
+    /// Return the application of this term to `argument`, which is only typed if both are.
+    pub fn apply(self, argument: Input) -> Input {
+        match (self, argument) {
+            (Input::Typed(function), Input::Typed(argument)) => {
+                Input::Typed(LambdaTerm::Application {
+                    function: Rc::new(function),
+                    argument: Rc::new(argument),
+                })
+            }
+            (function, argument) => Input::Untyped(UntypedTerm::Application {
+                function: Box::new(function.into_untyped()),
+                argument: Box::new(argument.into_untyped()),
+            }),
+        }
+    }
+
+    /// Return the names of the parameters of the term, which are the variables of the
+    /// abstractions it begins with, outermost first.
+    fn parameters(&self) -> Vec<&str> {
+        let mut parameters = Vec::new();
+        match self {
+            Input::Typed(term) => {
+                let mut term = term;
+                while let LambdaTerm::Abstraction { variable, body, .. } = term {
+                    parameters.push(variable.as_str());
+                    term = body;
+                }
+            }
+            Input::Untyped(term) => {
+                let mut term = term;
+                while let UntypedTerm::Abstraction { variable, body } = term {
+                    parameters.push(variable.as_str());
+                    term = body;
+                }
+            }
+        }
+        parameters
+    }
+
+    /// Return the application of this term to the given arguments, each bound to the parameter
+    /// with the given name, wherever it comes among the parameters. Those parameters which are not
+    /// bound are left as parameters of the result, in the same order.
+    ///
+    /// The result is only typed if this term and every argument are, in which case each argument
+    /// is checked to have the type of its parameter.
+    pub fn bind(self, bindings: Vec<(String, Input)>) -> Result<Input, String> {
+        let parameters = self.parameters();
+        let mut arguments: Vec<Option<Input>> = parameters.iter().map(|_| None).collect();
+        for (name, argument) in bindings {
+            let mut positions = parameters.iter().enumerate().filter(|(_, p)| **p == name);
+            let (i, _) = positions
+                .next()
+                .ok_or_else(|| format!("the term has no parameter named {name}"))?;
+            if positions.next().is_some() {
+                return Err(format!("the term has more than one parameter named {name}"));
+            }
+            if arguments[i].replace(argument).is_some() {
+                return Err(format!("parameter {name} is bound more than once"));
+            }
+        }
+        // NOTE: Parameters after the last one bound are left alone, rather than being taken
+        // apart and put back together.
+        let bound = arguments
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |i| i + 1);
+        arguments.truncate(bound);
+
+        // NOTE: Every argument is closed, so it can be applied underneath the parameters before
+        // it without being shifted.
+        let typed = arguments
+            .iter()
+            .flatten()
+            .all(|argument| matches!(argument, Input::Typed(_)));
+        match self {
+            Input::Typed(term) if typed => {
+                let mut parameters = Vec::new();
+                let mut body = term;
+                for argument in &arguments {
+                    let LambdaTerm::Abstraction {
+                        variable,
+                        argument_type,
+                        body: inner,
+                    } = body
+                    else {
+                        unreachable!("every parameter should be an abstraction");
+                    };
+                    if let Some(Input::Typed(argument)) = argument {
+                        let ty = argument.type_of().map_err(|e| {
+                            format!("the term bound to {variable} is not well-typed: {e}")
+                        })?;
+                        if ty != argument_type {
+                            return Err(format!(
+                                "the term bound to {variable} has type {ty}, but the parameter \
+                                 takes {argument_type}"
+                            ));
+                        }
+                    }
+                    parameters.push((variable, argument_type));
+                    body = Rc::unwrap_or_clone(inner);
+                }
+                for ((variable, argument_type), argument) in
+                    parameters.into_iter().zip(arguments).rev()
+                {
+                    body = LambdaTerm::Abstraction {
+                        variable,
+                        argument_type,
+                        body: Rc::new(body),
+                    };
+                    if let Some(Input::Typed(argument)) = argument {
+                        body = LambdaTerm::Application {
+                            function: Rc::new(body),
+                            argument: Rc::new(argument),
+                        };
+                    }
+                }
+                Ok(Input::Typed(body))
+            }
+            term => {
+                let mut parameters = Vec::new();
+                let mut body = term.into_untyped();
+                for _ in &arguments {
+                    let UntypedTerm::Abstraction {
+                        variable,
+                        body: inner,
+                    } = body
+                    else {
+                        unreachable!("every parameter should be an abstraction");
+                    };
+                    parameters.push(variable);
+                    body = *inner;
+                }
+                for (variable, argument) in parameters.into_iter().zip(arguments).rev() {
+                    body = UntypedTerm::Abstraction {
+                        variable,
+                        body: Box::new(body),
+                    };
+                    if let Some(argument) = argument {
+                        body = Input::Untyped(body).apply(argument).into_untyped();
+                    }
+                }
+                Ok(Input::Untyped(body))
+            }
+        }
+    }
+
+    /// Return the term with its types erased, if it has any.
+    pub fn into_untyped(self) -> UntypedTerm {
+        match self {
+            Input::Typed(term) => term.erase_types(),
+            Input::Untyped(term) => term,
+        }
+    }
+
+    /// Return the term with its types, inferring them if it has none, or the untyped term along
+    /// with the reason it cannot be typed.
+    pub fn into_typed(self) -> Result<LambdaTerm, (UntypedTerm, InferenceError)> {
+        match self {
+            Input::Typed(term) => Ok(term),
+            Input::Untyped(term) => term.infer_types().map_err(|e| (term, e)),
+        }
+    }
+}
+
+/// Deserialize a `LambdaTerm` from the JSON in the given string, printing the error and exiting
+/// if it is invalid or the term is not closed.
+fn from_json_or_exit(string: &str, path: &Path) -> LambdaTerm {
+    // NOTE: By default, serde_json refuses to nest more than 128 levels deep, and every level of
+    // a term takes two, one for the variant and one for its fields, so even modestly deep terms
+    // would be rejected. Parsing kombi syntax is no less recursive, so the limit buys nothing.
+    let mut deserializer = serde_json::Deserializer::from_str(string);
+    deserializer.disable_recursion_limit();
+    let lambda_term = LambdaTerm::deserialize(&mut deserializer)
+        .and_then(|lambda_term| deserializer.end().map(|()| lambda_term))
+        .unwrap_or_else(|e| {
+            eprintln!("File {} does not contain a valid term: {e}", path.display());
+            exit(1);
+        });
+    if !lambda_term.is_closed() {
+        eprintln!("Term in file {} contains free variables", path.display());
+        exit(1);
+    }
+    lambda_term
+}
+
+/// The definitions of the bundled modules which are loaded before a file is read.
+#[derive(Default)]
+pub struct Prelude {
+    environment: Environment,
+    untyped: Vec<(&'static str, UntypedTerm)>,
+}
+
+impl Prelude {
+    /// Load the bundled modules with the given names, in order, printing the error and exiting
+    /// if any of them does not exist.
+    pub fn load_or_exit(names: &[String]) -> Self {
+        let mut prelude = Self::default();
+        for name in names {
+            let Some(module) = prelude::module(name) else {
+                let available: Vec<_> = prelude::MODULES.iter().map(|m| m.name).collect();
+                eprintln!(
+                    "There is no module named {name}; the available modules are {}",
+                    available.join(", ")
+                );
+                exit(1);
+            };
+            let checked = prelude
+                .environment
+                .load_checked(module.source, Equivalence::default())
+                .expect("bundled modules should be valid");
+            assert!(
+                checked.failures.is_empty(),
+                "the assertions of bundled modules should hold"
+            );
+            for (name, term) in module.untyped {
+                let term = term.parse().expect("bundled modules should be valid");
+                prelude.untyped.push((name, term));
+            }
+        }
+        prelude
+    }
+
+    /// Return the environment containing every typed definition of the prelude.
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// Return the untyped term which the given name stands for, which is either one of the
+    /// definitions which have no simple type or the erasure of one which does.
+    fn untyped(&self, name: &str) -> Option<UntypedTerm> {
+        self.untyped
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, term)| term.clone())
+            .or_else(|| self.environment.get(name).map(|d| d.term.erase_types()))
+    }
+}
+
+/// Read the term in the given file, written in the given format, printing the error and exiting
+/// if it cannot be read. Files in kombi's own syntax and in the conventional notation of the
+/// untyped lambda calculus may refer to the definitions of the prelude, and the assertions in the
+/// former are checked up to the given `Equivalence`. The modules imported by the former are
+/// cached if `cache` is set.
+pub fn read_or_exit(
+    path: &Path,
+    format: Format,
+    prelude: &Prelude,
+    equivalence: Equivalence,
+    cache: bool,
+) -> Input {
+    let source = read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {}", path.display(), e);
+        exit(1);
+    });
+    let untyped = match format {
+        Format::Kombi => {
+            return Input::Typed(load_or_exit(
+                &source,
+                path,
+                prelude.environment(),
+                equivalence,
+                cache,
+            ))
+        }
+        Format::Json => return Input::Typed(from_json_or_exit(&source, path)),
+        Format::Lambda => UntypedTerm::parse_with(&source, &|name| prelude.untyped(name))
+            .map_err(|e| e.to_string()),
+        Format::Blc => UntypedTerm::from_blc(&source).map_err(|e| e.to_string()),
+    };
+    Input::Untyped(untyped.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    }))
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::reduce::Equivalence;
+
+use super::{read_or_exit, Format, Prelude};
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// File containing the term to be converted
+    file: PathBuf,
+
+    /// Format of the input
+    #[arg(long, value_enum, default_value_t = Format::Kombi)]
+    from: Format,
+
+    /// Format of the output
+    #[arg(long, value_enum, default_value_t = Format::Kombi)]
+    to: Format,
+}
+
+pub fn run(args: &ConvertArgs) {
+    let input = read_or_exit(
+        &args.file,
+        args.from,
+        &Prelude::default(),
+        Equivalence::default(),
+        true,
+    );
+
+    // Only writing typed formats needs types, so inference is never attempted otherwise, and
+    // untyped terms can be converted between untyped formats whether or not they could be typed.
+    let output = match args.to {
+        Format::Kombi | Format::Json => match input.into_typed() {
+            Ok(lambda_term) if args.to == Format::Json => serde_json::to_string(&lambda_term)
+                .expect("terms should always be serializable as JSON"),
+            Ok(lambda_term) => lambda_term.to_canonical_string(),
+            Err((term, e)) => {
+                eprintln!("Term {term} cannot be given a type: {e}");
+                exit(1);
+            }
+        },
+        Format::Lambda => input.into_untyped().to_string(),
+        Format::Blc => input.into_untyped().to_blc(),
+    };
+    println!("{output}");
+}
@@ -0,0 +1,261 @@
+//! Differential testing of the ways kombi has of evaluating terms, which run random well-typed
+//! terms through every one of them and report any pair which disagree.
+//!
+//! The engines which evaluate lazily, finding the same term as `LambdaTerm::beta_reduce`, must
+//! agree exactly, and those which count their steps must agree on how many they took. The
+//! strict engine is only expected to agree with them up to β-equivalence, and the engines which
+//! normalize fully, with the normal form of what the lazy engines find.
+
+use std::fmt::{self, Display, Formatter};
+use std::process::exit;
+
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+
+use kombi::arena::TermArena;
+use kombi::environment::Environment;
+use kombi::generate::TermGenerator;
+use kombi::parse::LambdaTerm;
+use kombi::reduce::Equivalence;
+
+use super::seed_or_clock;
+
+#[derive(Args)]
+pub struct DifftestArgs {
+    /// Number of random terms to evaluate
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    count: usize,
+
+    /// Maximum number of nodes in each random term
+    #[arg(
+        short,
+        long,
+        default_value_t = 20,
+        value_parser = RangedU64ValueParser::<usize>::new().range(2..)
+    )]
+    size: usize,
+
+    /// Maximum number of β-steps a term may take to be evaluated, beyond which it is skipped
+    #[arg(short, long, default_value_t = 10_000)]
+    fuel: u64,
+
+    /// Seed for the random number generator, taken from the clock if not given
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// A way of evaluating terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    /// `LambdaTerm::beta_reduce`, which substitutes arguments without reducing them.
+    Substitution,
+    /// `LambdaTerm::step`, contracting one redex at a time.
+    Stepping,
+    /// `TermArena::beta_reduce`, evaluating terms stored in an arena.
+    Arena,
+    /// `Environment::profile`, which steps through evaluation, counting its work.
+    Profile,
+    /// `LambdaTerm::beta_reduce_strict`, which reduces demanded arguments before substituting them.
+    Strict,
+    /// `LambdaTerm::normalize`, which reduces under abstractions too.
+    Normalize,
+    /// `UntypedTerm::normalize`, which normalizes the term with its types erased.
+    Untyped,
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Substitution => write!(f, "substitution"),
+            Self::Stepping => write!(f, "stepping"),
+            Self::Arena => write!(f, "arena"),
+            Self::Profile => write!(f, "profile"),
+            Self::Strict => write!(f, "strict"),
+            Self::Normalize => write!(f, "normalize"),
+            Self::Untyped => write!(f, "untyped"),
+        }
+    }
+}
+
+/// The number of engines which every term is evaluated by.
+const ENGINES: usize = 7;
+
+/// A disagreement between two engines about some term.
+struct Disagreement {
+    engines: (Engine, Engine),
+    detail: String,
+}
+
+impl Disagreement {
+    fn new(engines: (Engine, Engine), detail: String) -> Self {
+        Self { engines, detail }
+    }
+}
+
+/// What came of evaluating a term by every engine.
+enum Outcome {
+    Agreed,
+    /// The term could not be evaluated within the fuel bound, so the engines were not compared.
+    Exhausted,
+    Disagreed(Disagreement),
+}
+
+/// Evaluate the given well-typed term by every engine, and compare the results.
+fn compare(term: &LambdaTerm, fuel: u64) -> Outcome {
+    // NOTE: Stepping comes first, so that a term which takes too long to evaluate is given up on
+    // before any engine which cannot be stopped part of the way through is run on it.
+    let mut stepped = term.clone();
+    let mut steps = 0;
+    while let Some((_, next)) = stepped.step() {
+        steps += 1;
+        if steps > fuel {
+            return Outcome::Exhausted;
+        }
+        stepped = next;
+    }
+    match compare_evaluated(term, &stepped, steps, fuel) {
+        Ok(()) => Outcome::Agreed,
+        Err(disagreement) => Outcome::Disagreed(disagreement),
+    }
+}
+
+/// Compare every engine with the result of stepping through the evaluation of the given term,
+/// which took `steps` β-steps to reach `stepped`.
+fn compare_evaluated(
+    term: &LambdaTerm,
+    stepped: &LambdaTerm,
+    steps: u64,
+    fuel: u64,
+) -> Result<(), Disagreement> {
+    let mismatch = |engine: Engine, result: &LambdaTerm| {
+        Disagreement::new(
+            (Engine::Stepping, engine),
+            format!(
+                "stepping produced {}, but {engine} produced {}",
+                stepped.to_canonical_string(),
+                result.to_canonical_string()
+            ),
+        )
+    };
+
+    let reduced = term.beta_reduce();
+    if reduced != *stepped {
+        return Err(mismatch(Engine::Substitution, &reduced));
+    }
+
+    let mut arena = TermArena::new();
+    let id = arena.insert(term);
+    let arena_reduced = arena.beta_reduce(id);
+    let arena_reduced = arena.term(arena_reduced);
+    if arena_reduced != *stepped {
+        return Err(mismatch(Engine::Arena, &arena_reduced));
+    }
+
+    let (profiled, profile) = Environment::new().profile(term);
+    if profiled != *stepped {
+        return Err(mismatch(Engine::Profile, &profiled));
+    }
+    let profiled_steps = profile.total().steps;
+    if u64::try_from(profiled_steps).ok() != Some(steps) {
+        return Err(Disagreement::new(
+            (Engine::Stepping, Engine::Profile),
+            format!("stepping took {steps} β-steps, but profile counted {profiled_steps}"),
+        ));
+    }
+
+    let strict = term.beta_reduce_strict();
+    if !matches!(strict, LambdaTerm::Abstraction { .. })
+        || !strict.is_equivalent(stepped, Equivalence::Beta)
+    {
+        return Err(Disagreement::new(
+            (Engine::Substitution, Engine::Strict),
+            format!(
+                "strict produced {}, which is not equivalent to {}",
+                strict.to_canonical_string(),
+                stepped.to_canonical_string()
+            ),
+        ));
+    }
+
+    let normalized = term.normalize();
+    let stepped_normalized = stepped.normalize();
+    if normalized != stepped_normalized {
+        return Err(Disagreement::new(
+            (Engine::Stepping, Engine::Normalize),
+            format!(
+                "normalize produced {}, but the result of stepping normalizes to {}",
+                normalized.to_canonical_string(),
+                stepped_normalized.to_canonical_string()
+            ),
+        ));
+    }
+
+    // NOTE: Untyped terms cannot be compared directly, but they are displayed with every variable
+    // named after its position, so that α-equivalent terms are displayed identically. A term whose
+    // normal form cannot be reached within the fuel bound is simply not compared.
+    let fuel = usize::try_from(fuel).unwrap_or(usize::MAX);
+    let erased = normalized.erase_types().to_string();
+    if let Some(untyped) = term.erase_types().normalize(fuel) {
+        let untyped = untyped.to_string();
+        if untyped != erased {
+            return Err(Disagreement::new(
+                (Engine::Normalize, Engine::Untyped),
+                format!("normalize produced {erased}, but untyped produced {untyped}"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run(args: &DifftestArgs) {
+    let seed = seed_or_clock(args.seed);
+    let mut generator = TermGenerator::new(seed);
+    let mut failures = 0;
+    let mut exhausted = 0;
+    let mut ill_typed = 0;
+
+    for i in 0..args.count {
+        let (term, _) = generator.term(args.size).unwrap_or_else(|| {
+            eprintln!("Unable to find a term with at most {} nodes", args.size);
+            exit(1);
+        });
+        // NOTE: Whether the generator produces well-typed terms is for selftest to check.
+        if term.get_type().is_err() {
+            ill_typed += 1;
+            continue;
+        }
+        let disagreement = match compare(&term, args.fuel) {
+            Outcome::Agreed => continue,
+            Outcome::Exhausted => {
+                exhausted += 1;
+                continue;
+            }
+            Outcome::Disagreed(disagreement) => disagreement,
+        };
+        failures += 1;
+
+        let minimized = term.minimize(|t| {
+            t.get_type().is_ok()
+                && matches!(
+                    compare(t, args.fuel),
+                    Outcome::Disagreed(d) if d.engines == disagreement.engines
+                )
+        });
+
+        let (first, second) = disagreement.engines;
+        println!("disagreement between {first} and {second} (term {i}):");
+        println!("  term:      {}", term.to_canonical_string());
+        println!("  minimized: {}", minimized.to_canonical_string());
+        println!("  {}", disagreement.detail);
+    }
+
+    println!(
+        "evaluated {} terms by {ENGINES} engines with seed {seed}: {failures} disagreements, \
+         {exhausted} skipped for running out of fuel, {ill_typed} skipped for being ill-typed",
+        args.count
+    );
+    if failures > 0 {
+        exit(1);
+    }
+}
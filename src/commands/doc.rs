@@ -0,0 +1,199 @@
+use std::fmt::Write;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::{Args, ValueEnum};
+
+use kombi::environment::{Environment, Importer};
+use kombi::parse::Type;
+use kombi::print::DisplayOptions;
+use kombi::surface::{Item, Program};
+
+use super::escape_html;
+use crate::loader::Loader;
+
+/// The number of columns within which the term of each definition is laid out.
+const WIDTH: usize = 80;
+
+/// A format in which documentation can be written.
+#[derive(Clone, Copy, ValueEnum)]
+enum DocFormat {
+    /// Markdown, with a heading for every file and for every definition in it
+    Markdown,
+    /// A self-contained HTML page, with a list of every definition linking to its documentation
+    Html,
+}
+
+#[derive(Args)]
+pub struct DocArgs {
+    /// Files containing the definitions to be documented
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Format in which the documentation is written
+    #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+    format: DocFormat,
+
+    /// File to write the documentation to, rather than stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// The documentation of a single definition.
+struct Entry {
+    name: String,
+    doc: String,
+    ty: Type,
+    term: String,
+}
+
+/// Return the documentation of every definition in the given file, in order, printing the error
+/// and exiting if any of them is invalid.
+fn entries_or_exit(path: &Path) -> Vec<Entry> {
+    let source = read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {}", path.display(), e);
+        exit(1);
+    });
+    let program = source.parse::<Program>().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+
+    // Each definition is checked just as it would be when the file is loaded, so that its type
+    // is the one it has where it is defined, even if the name is later given a new definition.
+    let options = DisplayOptions {
+        width: Some(WIDTH),
+        ..DisplayOptions::default()
+    };
+    let mut environment = Environment::new();
+    let mut entries = Vec::new();
+    for item in program.items() {
+        let definition = match item {
+            Item::Definition(definition) => definition,
+            Item::Macro(r#macro) => {
+                environment
+                    .define_macro(&r#macro.name, &r#macro.parameters, &r#macro.body)
+                    .unwrap_or_else(|e| {
+                        eprintln!("In file {}: {e}", path.display());
+                        exit(1);
+                    });
+                continue;
+            }
+            Item::Import(import) => {
+                let module = Loader::new(path, true)
+                    .import(&import.path)
+                    .unwrap_or_else(|e| {
+                        eprintln!(
+                            "In file {}: unable to import {}: {e}",
+                            path.display(),
+                            import.path
+                        );
+                        exit(1);
+                    });
+                environment.import(&module);
+                continue;
+            }
+            Item::Assertion(_) => continue,
+        };
+        let ty = environment
+            .resolve(&definition.term)
+            .map_err(|e| e.to_string())
+            .and_then(|term| {
+                environment
+                    .define(&definition.name, term)
+                    .map(|d| d.ty.clone())
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap_or_else(|e| {
+                eprintln!("In file {}: {e}", path.display());
+                exit(1);
+            });
+        entries.push(Entry {
+            name: definition.name.clone(),
+            doc: definition.doc.clone(),
+            ty,
+            term: definition.term.fmt_with(options).to_string(),
+        });
+    }
+    entries
+}
+
+fn markdown(files: &[(String, Vec<Entry>)]) -> String {
+    let mut out = String::new();
+    for (path, entries) in files {
+        writeln!(out, "# {path}\n").expect("writing to a string should not fail");
+        for entry in entries {
+            writeln!(out, "## `{}` : `{}`\n", entry.name, entry.ty)
+                .expect("writing to a string should not fail");
+            if !entry.doc.is_empty() {
+                writeln!(out, "{}\n", entry.doc).expect("writing to a string should not fail");
+            }
+            writeln!(out, "```\n{}\n```\n", entry.term)
+                .expect("writing to a string should not fail");
+        }
+    }
+    out
+}
+
+fn html(files: &[(String, Vec<Entry>)]) -> String {
+    let mut contents = String::new();
+    let mut body = String::new();
+    for (i, (path, entries)) in files.iter().enumerate() {
+        let path = escape_html(path);
+        writeln!(contents, "<li>{path}<ul>").expect("writing to a string should not fail");
+        writeln!(body, "<h1>{path}</h1>").expect("writing to a string should not fail");
+        for entry in entries {
+            // NOTE: Names are identifiers, so they are safe to use in ids and need no escaping.
+            let id = format!("{i}-{}", entry.name);
+            let ty = escape_html(&entry.ty.to_string());
+            writeln!(
+                contents,
+                "<li><a href=\"#{id}\"><code>{}</code></a> : <code>{ty}</code></li>",
+                entry.name
+            )
+            .expect("writing to a string should not fail");
+            writeln!(
+                body,
+                "<h2 id=\"{id}\"><code>{}</code> : <code>{ty}</code></h2>",
+                entry.name
+            )
+            .expect("writing to a string should not fail");
+            for paragraph in entry.doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                writeln!(body, "<p>{}</p>", escape_html(paragraph))
+                    .expect("writing to a string should not fail");
+            }
+            writeln!(body, "<pre>{}</pre>", escape_html(&entry.term))
+                .expect("writing to a string should not fail");
+        }
+        writeln!(contents, "</ul></li>").expect("writing to a string should not fail");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Documentation</title>\n<style>\n  \
+         body {{ font-family: sans-serif; margin: 2em; max-width: 60em; }}\n  \
+         pre {{ background: #f4f4f4; padding: 0.5em; }}\n\
+         </style>\n</head>\n<body>\n<nav>\n<ul>\n{contents}</ul>\n</nav>\n{body}</body>\n</html>\n"
+    )
+}
+
+pub fn run(args: &DocArgs) {
+    let files: Vec<_> = args
+        .files
+        .iter()
+        .map(|path| (path.display().to_string(), entries_or_exit(path)))
+        .collect();
+    let documentation = match args.format {
+        DocFormat::Markdown => markdown(&files),
+        DocFormat::Html => html(&files),
+    };
+
+    match &args.output {
+        Some(path) => write(path, documentation).unwrap_or_else(|e| {
+            eprintln!("Unable to write file {}: {}", path.display(), e);
+            exit(1);
+        }),
+        None => print!("{documentation}"),
+    }
+}
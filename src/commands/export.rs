@@ -0,0 +1,93 @@
+use std::fs::write;
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+use serde_json::json;
+
+use kombi::reduce::Equivalence;
+
+use super::{escape_html, read_or_exit, Format, Prelude};
+
+/// The page into which the trace is embedded, with `{{TITLE}}` and `{{TRACE}}` standing for the
+/// title and the JSON of the trace.
+const TEMPLATE: &str = include_str!("export.html");
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// File containing the term to be exported
+    file: PathBuf,
+
+    /// Format of <FILE>
+    #[arg(long, value_enum, default_value_t = Format::Kombi)]
+    from: Format,
+
+    /// Write a self-contained HTML page showing the tree of the term at each step of its
+    /// evaluation, with a slider to move between steps
+    #[arg(long, required = true)]
+    html: bool,
+
+    /// File to write the page to, rather than stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Maximum number of steps of evaluation to include
+    #[arg(short, long, default_value_t = 1000)]
+    limit: usize,
+}
+
+pub fn run(args: &ExportArgs) {
+    let lambda_term = read_or_exit(
+        &args.file,
+        args.from,
+        &Prelude::default(),
+        Equivalence::default(),
+        true,
+    )
+    .into_typed()
+    .unwrap_or_else(|(term, e)| {
+        eprintln!("Term {term} cannot be given a type: {e}");
+        exit(1);
+    });
+    let ty = lambda_term.get_type().unwrap_or_else(|e| {
+        eprintln!("Term {lambda_term} is not well-typed: {e}");
+        exit(1);
+    });
+
+    // Each state records the redex to be contracted next, in the same format as the trace
+    // produced by the WebAssembly bindings, so that the viewer can highlight it.
+    let mut states = Vec::new();
+    let mut term = lambda_term;
+    let truncated = loop {
+        let next = term.step();
+        states.push(json!({
+            "term": term.to_string(),
+            "tree": term,
+            "redex": next.as_ref().map(|(redex, _)| redex),
+        }));
+        match next {
+            None => break false,
+            Some(_) if states.len() > args.limit => break true,
+            Some((_, next)) => term = next,
+        }
+    };
+    let trace = json!({
+        "type": ty.ty().to_string(),
+        "states": states,
+        "truncated": truncated,
+    });
+
+    // NOTE: The trace is embedded in a script element, which ends at the first `</` in it, so
+    // that has to be escaped, which JSON allows as `<\/`.
+    let page = TEMPLATE
+        .replace("{{TITLE}}", &escape_html(&args.file.display().to_string()))
+        .replace("{{TRACE}}", &trace.to_string().replace("</", "<\\/"));
+
+    match &args.output {
+        Some(path) => write(path, page).unwrap_or_else(|e| {
+            eprintln!("Unable to write file {}: {}", path.display(), e);
+            exit(1);
+        }),
+        None => print!("{page}"),
+    }
+}
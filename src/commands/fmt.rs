@@ -0,0 +1,63 @@
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::print::DisplayOptions;
+use kombi::surface::Program;
+
+#[derive(Args)]
+pub struct FmtArgs {
+    /// Files to be formatted in place
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Rather than rewriting any file, report every file which is not already formatted, and exit
+    /// unsuccessfully if there are any
+    #[arg(long)]
+    check: bool,
+
+    /// Write terms and types using ASCII symbols rather than Unicode
+    #[arg(long)]
+    ascii: bool,
+
+    /// Break definitions and terms across lines to fit within <WIDTH> columns
+    #[arg(short, long, default_value_t = 80)]
+    width: usize,
+}
+
+pub fn run(args: &FmtArgs) {
+    let options = DisplayOptions {
+        ascii: args.ascii,
+        width: Some(args.width),
+        ..DisplayOptions::default()
+    };
+
+    let mut unformatted = false;
+    for path in &args.files {
+        let source = read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Unable to open file {}: {}", path.display(), e);
+            exit(1);
+        });
+        let program = source.parse::<Program>().unwrap_or_else(|e| {
+            eprintln!("Unable to parse file {}: {e}", path.display());
+            exit(1);
+        });
+
+        let formatted = program.fmt_with(options).to_string();
+        if formatted == source {
+            continue;
+        }
+        if args.check {
+            eprintln!("File {} is not formatted", path.display());
+            unformatted = true;
+        } else if let Err(e) = write(path, formatted) {
+            eprintln!("Unable to write file {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+    if unformatted {
+        exit(1);
+    }
+}
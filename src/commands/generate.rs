@@ -0,0 +1,81 @@
+use std::process::exit;
+
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+
+use kombi::generate::TermGenerator;
+use kombi::parse::Type;
+
+use super::seed_or_clock;
+
+/// The number of times the generator is asked for a term of a given type before giving up.
+const ATTEMPTS: usize = 100;
+
+#[derive(Args)]
+pub struct GenArgs {
+    /// Maximum number of nodes in each generated term
+    #[arg(
+        short,
+        long,
+        default_value_t = 10,
+        value_parser = RangedU64ValueParser::<usize>::new().range(2..)
+    )]
+    size: usize,
+
+    /// Type of the generated terms, chosen at random for each term if not given
+    #[arg(short, long = "type", value_name = "TYPE")]
+    ty: Option<Type>,
+
+    /// Number of terms to generate
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Seed for the random number generator, taken from the clock if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Print the type of each term after it, in the same format as evaluation
+    #[arg(long)]
+    show_type: bool,
+}
+
+pub fn run(args: &GenArgs) {
+    let seed = seed_or_clock(args.seed);
+
+    // Every base type mentioned by the requested type is made available to the generator, so
+    // that it does not have to reach for unrelated ones.
+    let mut generator = if let Some(ty) = &args.ty {
+        TermGenerator::with_base_types(seed, ty.base_types().into_iter().collect())
+    } else {
+        TermGenerator::new(seed)
+    };
+
+    for _ in 0..args.count {
+        let (term, ty) = if let Some(ty) = &args.ty {
+            let Some(term) = (0..ATTEMPTS).find_map(|_| generator.term_of_type(ty, args.size))
+            else {
+                eprintln!(
+                    "Unable to find a term of type {ty} with at most {} nodes",
+                    args.size
+                );
+                exit(1);
+            };
+            (term, ty.clone())
+        } else {
+            generator.term(args.size).unwrap_or_else(|| {
+                eprintln!("Unable to find a term with at most {} nodes", args.size);
+                exit(1);
+            })
+        };
+
+        if args.show_type {
+            println!(
+                "({}):{}",
+                term.to_canonical_string(),
+                ty.to_canonical_string()
+            );
+        } else {
+            println!("{}", term.to_canonical_string());
+        }
+    }
+}
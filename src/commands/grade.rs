@@ -0,0 +1,214 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::rc::Rc;
+
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+
+use kombi::environment::{Environment, EnvironmentError};
+use kombi::lint::{lint, Level, Levels};
+use kombi::parse::{LambdaTerm, Type};
+
+use super::{line_and_column, LevelArgs};
+use crate::loader::Loader;
+
+/// A format in which the report can be printed.
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    /// One line per submission, followed by a summary
+    Text,
+    /// A JSON array with an object for each submission, recording whether it passed, and if not,
+    /// why not
+    Json,
+}
+
+#[derive(Args)]
+pub struct GradeArgs {
+    /// File containing the reference solution
+    #[arg(short, long)]
+    reference: PathBuf,
+
+    /// Files containing the submissions to be graded
+    #[arg(required = true)]
+    submissions: Vec<PathBuf>,
+
+    /// File containing a term to which both the reference and each submission are applied, and
+    /// whose results must be βη-equivalent. May be given more than once, in which case
+    /// submissions are judged by these tests alone, rather than by being βη-equivalent to the
+    /// reference
+    #[arg(short, long = "test", value_name = "TEST")]
+    tests: Vec<PathBuf>,
+
+    /// Format in which the report is printed
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+
+    /// Submissions with lints which are reported as errors fail, whatever they evaluate to
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by the reference,
+    /// the submissions and the tests are cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// Load the term in the given file, along with its type, or return a description of the problem.
+fn load(path: &Path, cache: bool) -> Result<(LambdaTerm, Type), String> {
+    let source = read_to_string(path).map_err(|e| format!("unable to open file: {e}"))?;
+    let term = Environment::new()
+        .load_with(&source, None, &mut Loader::new(path, cache))
+        .map_err(|e| match e {
+            // NOTE: Parse errors are displayed with the offending line of source, which would
+            // break up the report, so only their position is given instead.
+            EnvironmentError::Parse(e) => {
+                let (line, column) = line_and_column(&source, e.span().start);
+                format!("{line}:{column}: {}", e.message())
+            }
+            e => e.to_string(),
+        })?
+        .term
+        .ok_or_else(|| String::from("file does not contain a term"))?;
+    let ty = term
+        .type_of()
+        .map_err(|e| format!("term is not well-typed: {e}"))?;
+    Ok((term, ty))
+}
+
+/// Return a description of the first lint of the program in the given file which is reported at
+/// `Level::Deny`, if there is one.
+fn denied(path: &Path, levels: &Levels) -> Option<String> {
+    let source = read_to_string(path).ok()?;
+    let found = lint(&source)
+        .ok()?
+        .into_iter()
+        .find(|lint| levels.get(lint.kind) == Level::Deny)?;
+    let (line, column) = line_and_column(&source, found.span.start);
+    Some(format!(
+        "{line}:{column}: error[{}]: {}",
+        found.kind.name(),
+        found.message
+    ))
+}
+
+/// Return the application of `function` to `argument`, if it is well-typed.
+fn apply(function: &LambdaTerm, argument: &LambdaTerm) -> Option<LambdaTerm> {
+    let application = LambdaTerm::Application {
+        function: Rc::new(function.clone()),
+        argument: Rc::new(argument.clone()),
+    };
+    application.type_of().ok().map(|_| application)
+}
+
+/// Grade the submission in the given file against the reference, returning `Ok` if it passes,
+/// or a description of why it does not.
+fn grade(
+    path: &Path,
+    reference: &(LambdaTerm, Type),
+    tests: &[(PathBuf, LambdaTerm)],
+    levels: &Levels,
+    cache: bool,
+) -> Result<(), String> {
+    let (submission, ty) = load(path, cache)?;
+    if let Some(lint) = denied(path, levels) {
+        return Err(lint);
+    }
+    if ty != reference.1 {
+        return Err(format!("has type {ty}, but {} was expected", reference.1));
+    }
+
+    if tests.is_empty() {
+        return if submission.is_beta_eta_equivalent(&reference.0) {
+            Ok(())
+        } else {
+            Err(String::from("is not βη-equivalent to the reference"))
+        };
+    }
+    for (test, argument) in tests {
+        let expected = apply(&reference.0, argument)
+            .expect("tests should have been checked against the reference");
+        let actual = apply(&submission, argument)
+            .expect("submission should have the same type as the reference");
+        if !actual.is_beta_eta_equivalent(&expected) {
+            return Err(format!(
+                "test {} gives {}, but {} was expected",
+                test.display(),
+                actual.normalize(),
+                expected.normalize()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Grade every submission given by the user, exiting unsuccessfully if any of them fail.
+pub fn run(args: &GradeArgs) {
+    let reference = load(&args.reference, !args.no_cache).unwrap_or_else(|e| {
+        eprintln!("Reference {}: {e}", args.reference.display());
+        exit(1);
+    });
+
+    // Tests which the reference itself cannot be applied to would fail every submission, so they
+    // are rejected up front.
+    let tests: Vec<_> = args
+        .tests
+        .iter()
+        .map(|path| {
+            let (argument, _) = load(path, !args.no_cache).unwrap_or_else(|e| {
+                eprintln!("Test {}: {e}", path.display());
+                exit(1);
+            });
+            if apply(&reference.0, &argument).is_none() {
+                eprintln!(
+                    "Test {} is not a valid argument for the reference",
+                    path.display()
+                );
+                exit(1);
+            }
+            (path.clone(), argument)
+        })
+        .collect();
+
+    let levels = args.levels.levels(Levels::default());
+    let results: Vec<_> = args
+        .submissions
+        .iter()
+        .map(|path| {
+            (
+                path,
+                grade(path, &reference, &tests, &levels, !args.no_cache),
+            )
+        })
+        .collect();
+    let passed = results.iter().filter(|(_, result)| result.is_ok()).count();
+
+    match args.format {
+        ReportFormat::Text => {
+            for (path, result) in &results {
+                match result {
+                    Ok(()) => println!("{}: pass", path.display()),
+                    Err(reason) => println!("{}: fail: {reason}", path.display()),
+                }
+            }
+            println!("{passed} of {} submissions passed", results.len());
+        }
+        ReportFormat::Json => {
+            let report: Vec<Value> = results
+                .iter()
+                .map(|(path, result)| {
+                    json!({
+                        "file": path.display().to_string(),
+                        "passed": result.is_ok(),
+                        "reason": result.as_ref().err(),
+                    })
+                })
+                .collect();
+            println!("{}", Value::Array(report));
+        }
+    }
+
+    if passed < results.len() {
+        exit(1);
+    }
+}
@@ -0,0 +1,358 @@
+//! Syntax definitions for editors, generated from the grammar which kombi itself parses with.
+//!
+//! Only the tokens of the language, its keywords, operators and comments, are read out of
+//! `kombi.pest` whenever a definition is generated, so that a new spelling of an operator reaches
+//! editors without anyone having to remember to update them. The shapes of the rules, the
+//! identifier pattern, and the tree-sitter rule of each construct introduced by a keyword are
+//! written out here, and the tests check that they still agree with the grammar, so that a new
+//! keyword or a renamed rule fails them rather than `kombi grammar`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use clap::{Args, ValueEnum};
+use serde_json::json;
+
+/// The grammar of kombi, as given to pest.
+const GRAMMAR: &str = include_str!("../kombi.pest");
+
+/// The pattern matched by identifiers, which the grammar builds out of pest's character classes.
+const IDENTIFIER: &str = "[A-Za-z_][A-Za-z0-9_]*";
+
+/// An editor syntax format which can be generated.
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    /// A tree-sitter `grammar.js`
+    TreeSitter,
+    /// A `TextMate` grammar, as used by Visual Studio Code, Sublime Text and others, in JSON
+    Textmate,
+}
+
+#[derive(Args)]
+pub struct GrammarArgs {
+    /// Format of the syntax definition
+    #[arg(long, value_enum)]
+    target: Target,
+}
+
+/// The tokens of the language, as read out of its grammar.
+struct Tokens {
+    /// The words which cannot be used as identifiers.
+    keywords: Vec<String>,
+    /// The keywords which are followed by the name of what they define.
+    definers: Vec<String>,
+    /// The ways of writing the λ of an abstraction.
+    lambdas: Vec<String>,
+    /// The ways of writing the arrow of a function type.
+    arrows: Vec<String>,
+    /// What a documentation comment begins with.
+    comment: String,
+    /// What the path of an import begins and ends with.
+    quote: String,
+    /// Every other symbol which appears in the grammar.
+    punctuation: Vec<String>,
+}
+
+/// Return every string literal in the given pest expression, in order.
+fn literals(expression: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = expression.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut literal = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => literal.extend(chars.next()),
+                c => literal.push(c),
+            }
+        }
+        literals.push(literal);
+    }
+    literals
+}
+
+/// Return the string literals of the first parenthesized choice between literals in the given
+/// pest expression, as in `("→" | "->")`.
+fn first_choice(expression: &str) -> Vec<String> {
+    let start = expression
+        .find("(\"")
+        .expect("the rule should have a choice between literals");
+    let end = start
+        + expression[start..]
+            .find("\")")
+            .expect("the choice should be closed");
+    literals(&expression[start..=end + 1])
+}
+
+impl Tokens {
+    /// Read the tokens out of the grammar.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grammar no longer has the rules which the tokens are read from.
+    fn from_grammar() -> Self {
+        let rules: BTreeMap<&str, &str> = GRAMMAR
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, expression)| (name.trim(), expression.trim()))
+            .collect();
+        let rule = |name: &str| {
+            *rules
+                .get(name)
+                .unwrap_or_else(|| panic!("the grammar should have a rule {name}"))
+        };
+
+        let is_word = |literal: &String| {
+            literal
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        let keywords: Vec<_> = literals(rule("keyword"))
+            .into_iter()
+            .filter(|literal| is_word(literal) && literal != "_")
+            .collect();
+        let definers = keywords
+            .iter()
+            .filter(|k| GRAMMAR.contains(&format!("\"{k}\" ~ variable")))
+            .cloned()
+            .collect();
+        let comment = literals(rule("doc_comment")).swap_remove(0);
+        let quote = literals(rule("import"))
+            .into_iter()
+            .find(|literal| !is_word(literal) && literal != ";")
+            .expect("an import should have a quoted path");
+
+        let mut punctuation = Vec::new();
+        for literal in literals(GRAMMAR) {
+            let special = [&comment, &quote].contains(&&literal);
+            if !is_word(&literal)
+                && !special
+                && !literal.trim().is_empty()
+                && !punctuation.contains(&literal)
+            {
+                punctuation.push(literal);
+            }
+        }
+        let lambdas = first_choice(rule("abstraction"));
+        let arrows = first_choice(rule("function_type"));
+        punctuation.retain(|literal| !lambdas.contains(literal) && !arrows.contains(literal));
+        // NOTE: Longer symbols come first, so that `->` is never matched as `-` followed by `>`.
+        punctuation.sort_by_key(|literal| std::cmp::Reverse(literal.chars().count()));
+
+        Self {
+            keywords,
+            definers,
+            lambdas,
+            arrows,
+            comment,
+            quote,
+            punctuation,
+        }
+    }
+}
+
+/// Return the given string escaped for use in a regular expression.
+fn regex_escape(string: &str) -> String {
+    let mut escaped = String::new();
+    for c in string.chars() {
+        if "\\^$.|?*+()[]{}/-".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Return a regular expression matching any of the given strings.
+fn regex_choice(strings: &[String]) -> String {
+    strings
+        .iter()
+        .map(|s| regex_escape(s))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Return the given strings as a JavaScript expression for a tree-sitter rule matching any of
+/// them.
+fn js_choice(strings: &[String]) -> String {
+    let strings: Vec<_> = strings
+        .iter()
+        .map(|s| serde_json::to_string(s).expect("strings should be serializable"))
+        .collect();
+    match &strings[..] {
+        [string] => string.clone(),
+        strings => format!("choice({})", strings.join(", ")),
+    }
+}
+
+fn textmate(tokens: &Tokens) -> String {
+    let quote = regex_escape(&tokens.quote);
+    let grammar = json!({
+        "name": "kombi",
+        "scopeName": "source.kombi",
+        "fileTypes": ["kombi"],
+        "patterns": [
+            {
+                "name": "comment.line.documentation.kombi",
+                "match": format!("{}.*$", regex_escape(&tokens.comment)),
+            },
+            {
+                "name": "string.quoted.double.kombi",
+                "match": format!("{quote}[^{quote}\\n]*{quote}"),
+            },
+            {
+                "match": format!("\\b({})\\s+({IDENTIFIER})", tokens.definers.join("|")),
+                "captures": {
+                    "1": { "name": "keyword.other.kombi" },
+                    "2": { "name": "entity.name.function.kombi" },
+                },
+            },
+            {
+                "name": "keyword.other.kombi",
+                "match": format!("\\b({})\\b", tokens.keywords.join("|")),
+            },
+            {
+                "match": format!("({})\\s*({IDENTIFIER})", regex_choice(&tokens.lambdas)),
+                "captures": {
+                    "1": { "name": "storage.type.function.kombi" },
+                    "2": { "name": "variable.parameter.kombi" },
+                },
+            },
+            {
+                "name": "keyword.operator.arrow.kombi",
+                "match": regex_choice(&tokens.arrows),
+            },
+            {
+                "name": "punctuation.kombi",
+                "match": regex_choice(&tokens.punctuation),
+            },
+        ],
+    });
+    serde_json::to_string_pretty(&grammar).expect("grammars should be serializable") + "\n"
+}
+
+/// The tree-sitter rule for each construct introduced by a keyword, which must be given for
+/// every keyword of the grammar.
+const CONSTRUCTS: &[(&str, &str)] = &[
+    (
+        "let",
+        "definition: $ => seq(repeat($.doc_comment), 'let', field('name', $.identifier), '=', \
+         $._term, ';')",
+    ),
+    (
+        "assert",
+        "assertion: $ => seq('assert', $._term, choice(seq('=', $._term), seq(':', $._type)), \
+         ';')",
+    ),
+    (
+        "macro",
+        "macro_definition: $ => seq('macro', field('name', $.identifier), \
+         repeat(field('parameter', $.identifier)), '=', $._term, ';')",
+    ),
+    ("import", "import: $ => seq('import', $.path, ';')"),
+];
+
+fn tree_sitter(tokens: &Tokens) -> String {
+    let mut items = Vec::new();
+    let mut rules = Vec::new();
+    for keyword in &tokens.keywords {
+        let Some((_, rule)) = CONSTRUCTS.iter().find(|(k, _)| k == keyword) else {
+            panic!("the keyword {keyword} should have a tree-sitter rule");
+        };
+        let name = rule.split(':').next().expect("rules should be named");
+        items.push(format!("$.{name}"));
+        rules.push((*rule).to_string());
+    }
+
+    let quote = regex_escape(&tokens.quote);
+    rules.extend([
+        format!("path: $ => /{quote}[^{quote}\\n]*{quote}/"),
+        format!(
+            "doc_comment: $ => token(seq({}, /.*/))",
+            js_choice(std::slice::from_ref(&tokens.comment))
+        ),
+        String::from("_term: $ => choice($.abstraction, $.application, $._atom)"),
+        format!(
+            "abstraction: $ => prec.right(seq({}, field('variable', $.identifier), ':', \
+             field('type', $._type), '.', field('body', $._term)))",
+            js_choice(&tokens.lambdas)
+        ),
+        String::from(
+            "application: $ => prec.left(seq(choice($.application, $._atom), choice($._atom, \
+             $.abstraction)))",
+        ),
+        String::from("_atom: $ => choice($.identifier, $.parenthesized_term)"),
+        String::from("parenthesized_term: $ => seq('(', $._term, ')')"),
+        String::from(
+            "_type: $ => choice(alias($.identifier, $.type_identifier), $.function_type, \
+             seq('(', $._type, ')'))",
+        ),
+        format!(
+            "function_type: $ => prec.right(seq($._type, {}, $._type))",
+            js_choice(&tokens.arrows)
+        ),
+        format!("identifier: $ => /{IDENTIFIER}/"),
+    ]);
+
+    let mut grammar = String::from(
+        "// Generated by `kombi grammar --target tree-sitter` from the grammar kombi parses with.\n\
+         module.exports = grammar({\n  name: 'kombi',\n  extras: $ => [/\\s/],\n  \
+         word: $ => $.identifier,\n  rules: {\n",
+    );
+    writeln!(
+        grammar,
+        "    program: $ => seq(repeat(choice({})), optional($._term)),",
+        items.join(", ")
+    )
+    .expect("writing to a string should not fail");
+    for rule in rules {
+        writeln!(grammar, "    {rule},").expect("writing to a string should not fail");
+    }
+    grammar.push_str("  },\n});\n");
+    grammar
+}
+
+/// Print the syntax definition requested by the user.
+pub fn run(args: &GrammarArgs) {
+    let tokens = Tokens::from_grammar();
+    let definition = match args.target {
+        Target::TreeSitter => tree_sitter(&tokens),
+        Target::Textmate => textmate(&tokens),
+    };
+    print!("{definition}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{textmate, tree_sitter, Tokens, CONSTRUCTS};
+
+    #[test]
+    fn constructs_match_the_keywords() {
+        let tokens = Tokens::from_grammar();
+        let mut keywords: Vec<_> = CONSTRUCTS.iter().map(|(k, _)| k.to_string()).collect();
+        let mut expected = tokens.keywords.clone();
+        keywords.sort();
+        expected.sort();
+        assert_eq!(keywords, expected);
+        for (keyword, rule) in CONSTRUCTS {
+            assert!(rule.contains(&format!("'{keyword}'")));
+        }
+    }
+
+    #[test]
+    fn both_targets_are_generated() {
+        let tokens = Tokens::from_grammar();
+        assert!(!tokens.lambdas.is_empty() && !tokens.arrows.is_empty());
+        assert!(tokens.definers.contains(&String::from("let")));
+        let (tree_sitter, textmate) = (tree_sitter(&tokens), textmate(&tokens));
+        serde_json::from_str::<serde_json::Value>(&textmate).unwrap();
+        for keyword in &tokens.keywords {
+            assert!(tree_sitter.contains(&format!("'{keyword}'")));
+            assert!(textmate.contains(keyword.as_str()));
+        }
+    }
+}
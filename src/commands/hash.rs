@@ -0,0 +1,149 @@
+use std::fmt::Write;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::Args;
+use serde::Deserialize;
+
+use kombi::environment::{Definition, Environment};
+use kombi::store::{Insertion, Store};
+use kombi::surface::{Item, Program};
+
+use crate::loader::Loader;
+
+#[derive(Args)]
+pub struct HashArgs {
+    /// Files containing the programs whose definitions are hashed
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Store in which the terms defined are kept, read before hashing them if it exists and
+    /// written back afterwards, so that a definition whose term has changed since it was last
+    /// stored is reported
+    #[arg(long, value_name = "FILE")]
+    store: Option<PathBuf>,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by <FILES> are
+    /// cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// Load the program at the given path, returning each definition it makes itself, rather than
+/// importing, in the order in which they are first made, printing the error and exiting if it
+/// cannot be loaded.
+fn definitions_or_exit(path: &Path, cache: bool) -> Vec<Definition> {
+    let source = read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {e}", path.display());
+        exit(1);
+    });
+    let mut environment = Environment::new();
+    let mut loader = Loader::new(path, cache);
+    if let Err(e) = environment.load_with(&source, None, &mut loader) {
+        eprintln!("In file {}: {e}", path.display());
+        exit(1);
+    }
+    // NOTE: The program is known to parse, since it has just been loaded. A name defined more
+    // than once is only hashed as it is defined at the end of the program.
+    let program = source
+        .parse::<Program>()
+        .expect("the program should have been parsed already");
+    let mut names = Vec::new();
+    for item in program.items() {
+        if let Item::Definition(definition) = item {
+            if !names.contains(&definition.name) {
+                names.push(definition.name.clone());
+            }
+        }
+    }
+    names
+        .iter()
+        .map(|name| {
+            environment
+                .get(name)
+                .expect("every definition of the program should have been made")
+                .clone()
+        })
+        .collect()
+}
+
+/// Read the store at the given path, or return an empty one if there is no file there, printing
+/// the error and exiting if the file is not a valid store.
+fn read_store_or_exit(path: &Path) -> Store {
+    let Ok(json) = read_to_string(path) else {
+        return Store::new();
+    };
+    // NOTE: As when reading terms from JSON, the recursion limit would reject deep terms.
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    deserializer.disable_recursion_limit();
+    Store::deserialize(&mut deserializer).unwrap_or_else(|e| {
+        eprintln!(
+            "File {} does not contain a valid store: {e}",
+            path.display()
+        );
+        exit(1);
+    })
+}
+
+/// Print the digest of every definition in the programs given by the user, noting those which
+/// define the same term as another, and, if a store is given, those whose terms have changed
+/// since they were last stored.
+pub fn run(args: &HashArgs) {
+    let mut store = args
+        .store
+        .as_deref()
+        .map_or_else(Store::new, read_store_or_exit);
+
+    // NOTE: Every definition is stored before any is printed, so that each is noted as being the
+    // same as every other with the same term, wherever it comes in the files.
+    let mut insertions = Vec::<(String, Insertion)>::new();
+    for path in &args.files {
+        for definition in definitions_or_exit(path, !args.no_cache) {
+            let name = format!("{}:{}", path.display(), definition.name);
+            let insertion = store.insert(&name, &definition);
+            insertions.push((name, insertion));
+        }
+    }
+
+    for (name, insertion) in &insertions {
+        let mut line = format!("{}  {name}", insertion.digest);
+        let same = store
+            .names_of(insertion.digest)
+            .filter(|other| other != name)
+            .collect::<Vec<_>>();
+        if !same.is_empty() {
+            write!(line, " (same as {})", same.join(", "))
+                .expect("writing to a string should not fail");
+        }
+        if args.store.is_some() {
+            match insertion.previous {
+                None => line.push_str(" (new)"),
+                Some(previous) if insertion.changed() => {
+                    write!(line, " (changed from {previous})")
+                        .expect("writing to a string should not fail");
+                }
+                Some(_) => {}
+            }
+        }
+        println!("{line}");
+    }
+
+    if let Some(path) = &args.store {
+        let json = serde_json::to_string(&store).expect("a store should always serialize");
+        write(path, json).unwrap_or_else(|e| {
+            eprintln!("Unable to write file {}: {e}", path.display());
+            exit(1);
+        });
+        let changed = insertions.iter().filter(|(_, i)| i.changed()).count();
+        let added = insertions
+            .iter()
+            .filter(|(_, i)| i.previous.is_none())
+            .count();
+        eprintln!(
+            "Store {} holds {} terms: {added} definitions added, {changed} changed",
+            path.display(),
+            store.len()
+        );
+    }
+}
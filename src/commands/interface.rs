@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::interface::Interface;
+
+use crate::loader::{cycle, resolve};
+
+/// The extension of interface files.
+const EXTENSION: &str = "kombii";
+
+#[derive(Args)]
+pub struct InterfaceArgs {
+    /// Files containing the modules whose interfaces are written, each to a file of the same name
+    /// with the extension `.kombii`
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Rather than writing the interface of each module, check that it is the one already
+    /// written, reporting every difference, and exit unsuccessfully if there are any
+    #[arg(long)]
+    check: bool,
+}
+
+/// The interfaces of modules, which are read from their interface files where those exist, and
+/// otherwise found by checking the modules against the interfaces they import in turn.
+struct Interfaces {
+    /// Whether the interfaces of imported modules which have no interface files are written.
+    write: bool,
+    /// The modules being checked, outermost first, each of which is importing the next.
+    stack: Vec<PathBuf>,
+    /// The interface of every module imported so far.
+    imported: HashMap<PathBuf, Interface>,
+}
+
+impl Interfaces {
+    /// Check the module at the given path against the interfaces it imports, and return its
+    /// interface.
+    fn check(&mut self, path: &Path) -> Result<Interface, String> {
+        let source = read_to_string(path).map_err(|e| format!("unable to open file: {e}"))?;
+        self.stack.push(path.to_path_buf());
+        let interface = Interface::check(&source, &mut |import| self.import(path, import));
+        self.stack.pop();
+        interface.map_err(|e| e.to_string())
+    }
+
+    /// Return the interface of the module imported as `import` by the module at `path`.
+    fn import(&mut self, path: &Path, import: &str) -> Result<Interface, String> {
+        let path = resolve(path, import)?;
+        if let Some(cycle) = cycle(self.stack.iter().map(PathBuf::as_path), &path) {
+            return Err(cycle);
+        }
+        if let Some(interface) = self.imported.get(&path) {
+            return Ok(interface.clone());
+        }
+
+        let file = path.with_extension(EXTENSION);
+        let interface = if let Ok(source) = read_to_string(&file) {
+            source
+                .parse()
+                .map_err(|e| format!("in file {}: {e}", file.display()))?
+        } else {
+            let interface = self
+                .check(&path)
+                .map_err(|e| format!("in file {}: {e}", path.display()))?;
+            if self.write {
+                write(&file, interface.to_string())
+                    .map_err(|e| format!("unable to write file {}: {e}", file.display()))?;
+            }
+            interface
+        };
+        self.imported.insert(path, interface.clone());
+        Ok(interface)
+    }
+}
+
+/// Write or check the interface of every module given by the user, exiting unsuccessfully if any
+/// of them cannot be checked, or, when checking, differs from the one already written.
+pub fn run(args: &InterfaceArgs) {
+    let mut interfaces = Interfaces {
+        write: !args.check,
+        stack: Vec::new(),
+        imported: HashMap::new(),
+    };
+    let mut drifted = 0;
+    for path in &args.files {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let interface = interfaces.check(&canonical).unwrap_or_else(|e| {
+            eprintln!("In file {}: {e}", path.display());
+            exit(1);
+        });
+        let file = path.with_extension(EXTENSION);
+
+        if !args.check {
+            write(&file, interface.to_string()).unwrap_or_else(|e| {
+                eprintln!("Unable to write file {}: {e}", file.display());
+                exit(1);
+            });
+            continue;
+        }
+        let written: Interface = read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|source| source.parse().map_err(|e| format!("{e}")))
+            .unwrap_or_else(|e| {
+                eprintln!("Unable to read interface {}: {e}", file.display());
+                exit(1);
+            });
+        let changes = written.changes(&interface);
+        for change in &changes {
+            println!("{}: {change}", path.display());
+        }
+        if !changes.is_empty() {
+            drifted += 1;
+        }
+    }
+
+    if drifted > 0 {
+        eprintln!(
+            "{drifted} of {} modules differ from their interfaces",
+            args.files.len()
+        );
+        exit(1);
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+
+use kombi::lint::{lint, Level, Levels, Severity};
+
+use super::{describe_lint, line_and_column, LevelArgs};
+
+/// A format in which lints can be printed.
+#[derive(Clone, Copy, ValueEnum)]
+enum LintFormat {
+    /// One line per lint, prefixed by its position in the file
+    Text,
+    /// A JSON array of lints, each with its kind, severity, message, and the position of the
+    /// start and end of its span
+    Json,
+}
+
+#[derive(Args)]
+pub struct LintArgs {
+    /// File containing the program to be checked
+    file: PathBuf,
+
+    /// Format in which lints are printed
+    #[arg(long, value_enum, default_value_t = LintFormat::Text)]
+    format: LintFormat,
+
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    /// Only report lints at least as serious as the given severity
+    #[arg(long, value_enum, default_value_t = Severity::Hint)]
+    severity: Severity,
+}
+
+/// Check the program in the file given by the user, exiting unsuccessfully if any warnings or
+/// errors are reported.
+///
+/// Unlike elsewhere, every kind of lint is reported by default, since the user asked to see them.
+pub fn run(args: &LintArgs) {
+    let levels = args.levels.levels(Levels::new(|_| Level::Warn));
+    let source = read_to_string(&args.file).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {}", args.file.display(), e);
+        exit(1);
+    });
+    let lints: Vec<_> = lint(&source)
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            exit(1);
+        })
+        .into_iter()
+        .filter(|lint| {
+            levels.get(lint.kind) > Level::Allow && lint.kind.severity() >= args.severity
+        })
+        .collect();
+
+    match args.format {
+        LintFormat::Text => {
+            for lint in &lints {
+                let level = levels.get(lint.kind);
+                println!("{}", describe_lint(&args.file, &source, lint, level));
+            }
+        }
+        LintFormat::Json => {
+            let position = |offset| {
+                let (line, column) = line_and_column(&source, offset);
+                json!({ "offset": offset, "line": line, "column": column })
+            };
+            let lints: Vec<Value> = lints
+                .iter()
+                .map(|lint| {
+                    json!({
+                        "kind": lint.kind.name(),
+                        "severity": lint.kind.severity().to_string(),
+                        "denied": levels.get(lint.kind) == Level::Deny,
+                        "message": lint.message,
+                        "start": position(lint.span.start),
+                        "end": position(lint.span.end),
+                    })
+                })
+                .collect();
+            println!("{}", Value::Array(lints));
+        }
+    }
+
+    if lints.iter().any(|lint| {
+        lint.kind.severity() == Severity::Warning || levels.get(lint.kind) == Level::Deny
+    }) {
+        exit(1);
+    }
+}
@@ -0,0 +1,368 @@
+//! A language server, speaking the Language Server Protocol over stdin and stdout.
+//!
+//! Only the small part of the protocol which kombi has a use for is implemented: documents are
+//! synchronized in full, diagnostics are published whenever a document is opened or saved, and
+//! hovering over a subterm shows its type, while going to the definition of a variable finds the
+//! abstraction or definition which binds it, following a name brought into scope by an `import`
+//! into the module which defines it. Completion offers every name in scope, along with its type.
+//! Types are shown even in documents with errors in them, wherever they can be known.
+//!
+//! The modules imported by a document are read from disk, relative to the file it is saved in,
+//! so only documents with `file:` URIs can import anything.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::read_to_string;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use serde_json::{json, Value};
+
+use kombi::document::{Binding, Completion, CompletionKind, Document};
+use kombi::surface::Span;
+
+use crate::loader::{resolve, Loader};
+
+/// The JSON-RPC error code for a request whose method the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Return the given completion as a completion item.
+fn completion_item(completion: &Completion) -> Value {
+    // NOTE: These are the kinds `Variable`, `Constant` and `Function` of the protocol.
+    let (kind, detail) = match completion.kind {
+        CompletionKind::Variable => (6, None),
+        CompletionKind::Definition => (21, None),
+        CompletionKind::Macro(1) => (3, Some(String::from("macro with 1 parameter"))),
+        CompletionKind::Macro(parameters) => {
+            (3, Some(format!("macro with {parameters} parameters")))
+        }
+    };
+    let mut item = json!({ "label": completion.name, "kind": kind });
+    if let Some(detail) = detail.or_else(|| completion.ty.as_ref().map(ToString::to_string)) {
+        item["detail"] = Value::String(detail);
+    }
+    item
+}
+
+/// Read a single message, returning `None` once the input is closed.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message has no Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(io::Error::from)
+}
+
+/// Write a single message.
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+/// Return the byte offset in `text` of the given LSP position, whose character is counted in
+/// UTF-16 code units, clamping positions past the end of a line or of the text.
+fn offset_of(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0);
+    let character = position["character"]
+        .as_u64()
+        .map_or(0, |c| usize::try_from(c).unwrap_or(usize::MAX));
+
+    let mut start = 0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (i, c) in text[start..].char_indices() {
+        if units >= character || c == '\n' {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Return the LSP position of the given byte offset in `text`.
+fn position_of(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": line, "character": character })
+}
+
+/// Return the LSP range of the given span of `text`.
+fn range_of(text: &str, span: Span) -> Value {
+    json!({ "start": position_of(text, span.start), "end": position_of(text, span.end) })
+}
+
+/// Return the path of the file with the given `file:` URI, or `None` if it has some other scheme.
+fn path_of(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // NOTE: Any byte of the path may be percent-encoded, and those which are not ASCII always
+    // are, so the bytes are decoded before the path is.
+    let mut bytes = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| after.get(..2))
+            .flatten()
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        if let Some(decoded) = decoded {
+            bytes.push(decoded);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// Return the `file:` URI of the file at the given path.
+fn uri_of(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            write!(uri, "%{byte:02X}").expect("writing to a string should not fail");
+        }
+    }
+    uri
+}
+
+/// Parse and check the document with the given URI and text, loading the modules it imports
+/// relative to its file.
+fn document(uri: &str, text: &str) -> Document {
+    match path_of(uri) {
+        Some(path) => Document::with_importer(text, &mut Loader::new(&path, true)),
+        None => Document::new(text),
+    }
+}
+
+/// Return the location of the given binding, made in the document with the given URI and text,
+/// following a name which is imported into the module it is imported from, and on into the
+/// module which defines it. If some module cannot be read, the `import` directive through which
+/// it is reached is given instead.
+fn location(uri: &str, text: &str, binding: Binding) -> Value {
+    let (mut uri, mut text, mut binding) = (uri.to_string(), text.to_string(), binding);
+    loop {
+        let (span, path, name) = match binding {
+            Binding::Local(span) => return json!({ "uri": uri, "range": range_of(&text, span) }),
+            Binding::Imported { span, path, name } => (span, path, name),
+        };
+        let module = path_of(&uri)
+            .and_then(|importing| resolve(&importing, &path).ok())
+            .and_then(|path| Some((uri_of(&path), read_to_string(&path).ok()?)));
+        let Some((module_uri, module_text)) = module else {
+            return json!({ "uri": uri, "range": range_of(&text, span) });
+        };
+        let Some(next) = document(&module_uri, &module_text).binding_of(&name) else {
+            return json!({ "uri": uri, "range": range_of(&text, span) });
+        };
+        (uri, text, binding) = (module_uri, module_text, next);
+    }
+}
+
+/// The state of the server: the text of every open document, by URI.
+#[derive(Default)]
+struct Server {
+    documents: BTreeMap<String, String>,
+    shut_down: bool,
+}
+
+impl Server {
+    /// Return the notification publishing the diagnostics for the document with the given URI.
+    fn diagnostics(&self, uri: &str) -> Value {
+        let diagnostics: Vec<Value> = self.documents.get(uri).map_or_else(Vec::new, |text| {
+            document(uri, text)
+                .diagnostics()
+                .iter()
+                .map(|d| {
+                    json!({
+                        "range": range_of(text, d.span),
+                        "severity": 1,
+                        "source": "kombi",
+                        "message": d.message,
+                    })
+                })
+                .collect()
+        });
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    /// Return the text and the byte offset of the position given in the parameters of a
+    /// request, if the document it refers to is open.
+    fn position<'a>(&'a self, params: &Value) -> Option<(&'a str, &'a str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let (uri, text) = self.documents.get_key_value(uri)?;
+        Some((uri, text, offset_of(text, &params["position"])))
+    }
+
+    /// Handle a request, returning its result, or `None` if the method is not implemented.
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        match method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        "change": 1,
+                        "save": { "includeText": true },
+                    },
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "kombi", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shut_down = true;
+                Some(Value::Null)
+            }
+            "textDocument/hover" => Some(self.position(params).map_or(
+                Value::Null,
+                |(uri, text, offset)| {
+                    document(uri, text)
+                        .type_at(offset)
+                        .map_or(Value::Null, |(span, ty)| {
+                            json!({
+                                "contents": {
+                                    "kind": "markdown",
+                                    "value": format!("```\n{ty}\n```"),
+                                },
+                                "range": range_of(text, span),
+                            })
+                        })
+                },
+            )),
+            "textDocument/definition" => Some(self.position(params).map_or(
+                Value::Null,
+                |(uri, text, offset)| {
+                    document(uri, text)
+                        .definition_at(offset)
+                        .map_or(Value::Null, |binding| location(uri, text, binding))
+                },
+            )),
+            "textDocument/completion" => Some(self.position(params).map_or(
+                Value::Null,
+                |(uri, text, offset)| {
+                    let completions = document(uri, text).completions_at(offset);
+                    Value::Array(completions.iter().map(completion_item).collect())
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// Handle a notification, returning any notifications to be sent in response.
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                vec![self.diagnostics(&uri)]
+            }
+            "textDocument/didChange" => {
+                // NOTE: Only full synchronization is offered, so the last change holds the whole
+                // text of the document.
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri, text.to_string());
+                }
+                Vec::new()
+            }
+            "textDocument/didSave" => {
+                if let Some(text) = params["text"].as_str() {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                vec![self.diagnostics(&uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![self.diagnostics(&uri)]
+            }
+            "exit" => exit(i32::from(!self.shut_down)),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Serve requests read from stdin until it is closed or the client asks the server to exit.
+pub fn run() {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut server = Server::default();
+
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Unable to read message: {e}");
+                exit(1);
+            }
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let responses = match message.get("id") {
+            Some(id) => vec![match server.request(method, params) {
+                Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("unknown method {method}"),
+                    },
+                }),
+            }],
+            None => server.notification(method, params),
+        };
+        for response in responses {
+            if let Err(e) = write_message(&mut output, &response) {
+                eprintln!("Unable to write message: {e}");
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::reduce::Equivalence;
+
+use super::{read_or_exit, Format, Prelude};
+
+#[derive(Args)]
+pub struct MinArgs {
+    /// File containing the term to be made smaller
+    file: PathBuf,
+
+    /// Maximum number of terms to explore in searching for a smaller one
+    #[arg(short, long, default_value_t = 1000)]
+    budget: usize,
+
+    /// Format of <FILE>
+    #[arg(long, value_enum, default_value_t = Format::Kombi)]
+    from: Format,
+
+    /// Load the definitions of the bundled module with the given name, such as `combinators`,
+    /// before reading <FILE>. May be given more than once
+    #[arg(short, long = "prelude", value_name = "MODULE")]
+    prelude: Vec<String>,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by <FILE> are
+    /// cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// Print the smallest term βη-equivalent to the user's term which could be found, and report on
+/// stderr how its size compares with those of the term and its normal form.
+pub fn run(args: &MinArgs) {
+    let prelude = Prelude::load_or_exit(&args.prelude);
+    let input = read_or_exit(
+        &args.file,
+        args.from,
+        &prelude,
+        Equivalence::default(),
+        !args.no_cache,
+    );
+    let lambda_term = input.into_typed().unwrap_or_else(|(term, e)| {
+        eprintln!("Term {term} cannot be given a type: {e}");
+        exit(1);
+    });
+    if let Err(e) = lambda_term.type_of() {
+        eprintln!("Term {lambda_term} is not well-typed: {e}");
+        exit(1);
+    }
+
+    let (smallest, explored) = lambda_term.golf(args.budget);
+    let normal_form = lambda_term.normal_form(Equivalence::BetaEta);
+    println!("{smallest}");
+
+    let (size, smallest_size) = (lambda_term.size(), smallest.size());
+    let saved = size - smallest_size;
+    eprintln!(
+        "size {smallest_size}, down from {size} ({}% smaller), and from {} for the βη-normal \
+         form, exploring {explored} terms",
+        saved * 100 / size,
+        normal_form.size()
+    );
+}
@@ -0,0 +1,262 @@
+//! An interactive session, in which terms are evaluated one line at a time, against definitions
+//! which accumulate over the course of the session.
+//!
+//! The result of every evaluation is defined as `it`, and `:let name = term` defines `name` as the
+//! result of evaluating `term`, so that large intermediate results can be used in later inputs
+//! without being written out again. `:save` writes every definition to a file, from which `:load`
+//! restores them in a later session.
+//!
+//! Input which is plainly unfinished, with a parenthesis or an abstraction left open or a directive
+//! with no `;` yet, is continued on the next line. Text pasted into a terminal which supports
+//! bracketed paste is taken as a single input however many lines it spans, so that a block of
+//! definitions can be pasted in at once.
+
+use std::fs::{read_to_string, write};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::environment::Environment;
+use kombi::parse::{LambdaTerm, Type};
+use kombi::print::DisplayOptions;
+use kombi::reduce::Equivalence;
+use kombi::surface::SurfaceTerm;
+
+use super::Prelude;
+use crate::loader::Loader;
+
+/// The file which input to the session is taken to have been read from, relative to which the
+/// modules it imports are found, so that they are found in the current directory.
+const INPUT: &str = "<input>";
+
+/// The name under which the result of the last evaluation is defined.
+const IT: &str = "it";
+
+/// The number of columns within which the terms of a saved session are laid out.
+const WIDTH: usize = 80;
+
+/// What a terminal in bracketed paste mode sends before and after pasted text.
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// The keywords which begin a directive, which is only finished by a `;`.
+const DIRECTIVES: [&str; 4] = ["let", "assert", "macro", "import"];
+
+const HELP: &str = "\
+Enter a term to evaluate it, or definitions, macros and assertions to add them to the session.
+The result of the last evaluation is defined as `it`. Unfinished input is continued on the next
+line, until it is finished or an empty line is entered.
+
+:let NAME = TERM  Evaluate TERM and define NAME as its result
+:save FILE        Write every definition and macro of the session to FILE, as a program
+:load FILE        Load the definitions and macros of the program in FILE into the session
+:help             Show this message
+:quit             End the session";
+
+#[derive(Args)]
+pub struct ReplArgs {
+    /// Files containing programs whose definitions are loaded before the session starts
+    files: Vec<PathBuf>,
+
+    /// Load the definitions of the bundled module with the given name, such as `combinators`,
+    /// before the session starts. May be given more than once
+    #[arg(short, long = "prelude", value_name = "MODULE")]
+    prelude: Vec<String>,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by the files, or
+    /// by input to the session, are cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// The state of an interactive session.
+struct Session {
+    environment: Environment,
+    /// Whether the modules imported by loaded files and by input are cached.
+    cache: bool,
+}
+
+impl Session {
+    /// Load the definitions and macros of the program in the given file into the session.
+    fn load(&mut self, path: &Path) -> Result<(), String> {
+        let source = read_to_string(path)
+            .map_err(|e| format!("Unable to open file {}: {e}", path.display()))?;
+        let mut loader = Loader::new(path, self.cache);
+        self.environment
+            .load_with(&source, None, &mut loader)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Write every definition and macro of the session to the given file, as a program which
+    /// `load` restores them from, returning a description of what was written.
+    fn save(&self, path: &Path) -> Result<String, String> {
+        let program = self.environment.to_program();
+        let options = DisplayOptions {
+            width: Some(WIDTH),
+            ..DisplayOptions::default()
+        };
+        write(path, program.fmt_with(options).to_string())
+            .map_err(|e| format!("Unable to write file {}: {e}", path.display()))?;
+        Ok(format!(
+            "Saved {} definitions and {} macros to {}",
+            program.definitions.len(),
+            program.macros.len(),
+            path.display()
+        ))
+    }
+
+    /// Type check and evaluate the given term, returning the result along with its type.
+    fn evaluate(lambda_term: &LambdaTerm) -> Result<(LambdaTerm, Type), String> {
+        let ty = lambda_term
+            .type_of()
+            .map_err(|e| format!("Term {lambda_term} is not well-typed: {e}"))?;
+        Ok((lambda_term.beta_reduce(), ty))
+    }
+
+    /// Handle a single line of input, returning the text to be printed in response, or an error.
+    fn handle(&mut self, line: &str) -> Result<Option<String>, String> {
+        if let Some(path) = line.strip_prefix(":save") {
+            let path = Some(path.trim()).filter(|p| !p.is_empty());
+            let path = path.ok_or_else(|| String::from("Expected :save FILE"))?;
+            return self.save(Path::new(path)).map(Some);
+        }
+        if let Some(path) = line.strip_prefix(":load") {
+            let path = Some(path.trim()).filter(|p| !p.is_empty());
+            let path = path.ok_or_else(|| String::from("Expected :load FILE"))?;
+            return self.load(Path::new(path)).map(|()| None);
+        }
+        if let Some(definition) = line.strip_prefix(":let") {
+            let (name, term) = definition
+                .split_once('=')
+                .ok_or_else(|| String::from("Expected :let NAME = TERM"))?;
+            let name = name.trim();
+            if !matches!(name.parse(), Ok(SurfaceTerm::Variable { .. })) {
+                return Err(format!("{name} is not a valid name"));
+            }
+            let term = self.environment.parse(term).map_err(|e| e.to_string())?;
+            let (result, _) = Self::evaluate(&term)?;
+            let definition = self
+                .environment
+                .define(name, result)
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(format!("{} : {}", definition.name, definition.ty)));
+        }
+
+        let mut loader = Loader::new(Path::new(INPUT), self.cache);
+        let program = self
+            .environment
+            .load_with(line, Some(Equivalence::default()), &mut loader)
+            .map_err(|e| e.to_string())?;
+        if let Some((_, failure)) = program.failures.first() {
+            return Err(format!("Assertion failed: {failure}"));
+        }
+        let Some(term) = program.term else {
+            return Ok(None);
+        };
+        let (result, ty) = Self::evaluate(&term)?;
+        let output = format!("({result}):{ty}");
+        self.environment
+            .define(IT, result)
+            .expect("the result of evaluating a closed, well-typed term should be too");
+        Ok(Some(output))
+    }
+}
+
+/// Return whether the given input is plainly unfinished, so that the next line should continue
+/// it: whether it leaves a parenthesis open, ends where a term or type must follow, or ends with
+/// a directive which is not yet ended by a `;`.
+fn unfinished(input: &str) -> bool {
+    // NOTE: Documentation comments run to the end of their lines, and may say anything at all.
+    let code: String = input
+        .lines()
+        .map(|line| line.split_once("///").map_or(line, |(code, _)| code))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let code = code.trim_end();
+    let depth = code.chars().fold(0_i64, |depth, c| match c {
+        '(' => depth + 1,
+        ')' => depth - 1,
+        _ => depth,
+    });
+    // NOTE: Only what follows the last `;` can still be part of an unfinished directive.
+    let directive = code
+        .rsplit(';')
+        .next()
+        .and_then(|last| last.split_whitespace().next())
+        .is_some_and(|word| DIRECTIVES.contains(&word));
+    depth > 0
+        || [".", "=", ":", "λ", "\\", "->", "→"]
+            .iter()
+            .any(|end| code.ends_with(end))
+        || directive
+}
+
+/// Run an interactive session on stdin and stdout, until the input is closed or the user quits.
+pub fn run(args: &ReplArgs) {
+    let mut session = Session {
+        environment: Prelude::load_or_exit(&args.prelude).environment().clone(),
+        cache: !args.no_cache,
+    };
+    for path in &args.files {
+        if let Err(e) = session.load(path) {
+            eprintln!("{e}");
+            exit(1);
+        }
+    }
+
+    // NOTE: Only a terminal can be asked to mark pasted text, and only one which is reading the
+    // input as it is typed will send the marks.
+    let terminal = io::stdin().is_terminal() && io::stdout().is_terminal();
+    if terminal {
+        print!("\x1b[?2004h");
+    }
+    let mut lines = io::stdin().lock().lines();
+    let mut input = String::new();
+    let mut pasting = false;
+    loop {
+        if !pasting {
+            print!("{}", if input.is_empty() { "> " } else { "| " });
+            io::stdout().flush().expect("stdout should be writable");
+        }
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line.unwrap_or_else(|e| {
+            eprintln!("Unable to read input: {e}");
+            exit(1);
+        });
+
+        let pasted = line.contains(PASTE_END);
+        pasting = (pasting || line.contains(PASTE_START)) && !pasted;
+        let line = line.replace(PASTE_START, "").replace(PASTE_END, "");
+        let commands = input.is_empty()
+            && line.trim_start().starts_with(':')
+            && !line.trim_start().starts_with(":let");
+        let blank = line.trim().is_empty();
+        input.push_str(&line);
+        input.push('\n');
+        if pasting || (!pasted && !commands && !blank && unfinished(&input)) {
+            continue;
+        }
+        let line = std::mem::take(&mut input);
+
+        match line.trim() {
+            "" => {}
+            ":quit" | ":q" => break,
+            ":help" | ":h" => println!("{HELP}"),
+            line => match session.handle(line) {
+                Ok(Some(output)) => println!("{output}"),
+                Ok(None) => {}
+                Err(e) => eprintln!("{e}"),
+            },
+        }
+    }
+    if terminal {
+        print!("\x1b[?2004l");
+        io::stdout().flush().expect("stdout should be writable");
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt::{self, Display, Formatter};
+use std::process::exit;
+
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+
+use kombi::arena::TermArena;
+use kombi::generate::TermGenerator;
+use kombi::parse::{LambdaTerm, Type};
+use kombi::reduce::Equivalence;
+
+use super::seed_or_clock;
+
+#[derive(Args)]
+pub struct SelftestArgs {
+    /// Number of random terms to check
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    count: usize,
+
+    /// Maximum number of nodes in each random term
+    #[arg(
+        short,
+        long,
+        default_value_t = 20,
+        value_parser = RangedU64ValueParser::<usize>::new().range(2..)
+    )]
+    size: usize,
+
+    /// Maximum number of β-steps a term may take to reach its normal form
+    #[arg(short, long, default_value_t = 10_000)]
+    fuel: u64,
+
+    /// Seed for the random number generator, taken from the clock if not given
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// A property which every well-typed term is expected to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Property {
+    /// The type checker agrees with the generator about the type of the term.
+    Typing,
+    /// Parsing the canonical representation of the term produces the same term.
+    RoundTrip,
+    /// Every step of reduction preserves the type of the term.
+    Preservation,
+    /// Reduction only stops once the term is an abstraction.
+    Progress,
+    /// Reduction stops within the fuel bound.
+    Termination,
+    /// Every implementation of type checking and reduction agrees on the result.
+    Agreement,
+}
+
+impl Display for Property {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Typing => write!(f, "typing"),
+            Self::RoundTrip => write!(f, "round trip"),
+            Self::Preservation => write!(f, "preservation"),
+            Self::Progress => write!(f, "progress"),
+            Self::Termination => write!(f, "termination"),
+            Self::Agreement => write!(f, "agreement"),
+        }
+    }
+}
+
+/// A failure of some term to have a `Property`.
+struct Violation {
+    property: Property,
+    detail: String,
+}
+
+impl Violation {
+    fn new(property: Property, detail: String) -> Self {
+        Self { property, detail }
+    }
+}
+
+/// Check every property other than `Property::Typing` of the given term, which must be
+/// well-typed with type `ty`.
+fn check(term: &LambdaTerm, ty: &Type, fuel: u64) -> Result<(), Violation> {
+    check_round_trip(term)?;
+    let reduced = check_reduction(term, ty, fuel)?;
+    check_agreement(term, ty, &reduced)
+}
+
+fn check_round_trip(term: &LambdaTerm) -> Result<(), Violation> {
+    let canonical = term.to_canonical_string();
+    match canonical.parse::<LambdaTerm>() {
+        Ok(parsed) if parsed == *term => {}
+        Ok(parsed) => {
+            return Err(Violation::new(
+                Property::RoundTrip,
+                format!("{canonical} was parsed as {}", parsed.to_canonical_string()),
+            ))
+        }
+        Err(e) => {
+            return Err(Violation::new(
+                Property::RoundTrip,
+                format!("{canonical} could not be parsed: {e}"),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Check preservation, progress, and termination, returning the normal form of the term.
+fn check_reduction(term: &LambdaTerm, ty: &Type, fuel: u64) -> Result<LambdaTerm, Violation> {
+    // Reduction is carried out one step at a time, so that preservation can be checked along the
+    // way, and so that a term which never stops reducing is caught by the fuel bound rather than
+    // hanging the test.
+    let mut current = term.clone();
+    let mut steps = 0;
+    while let Some((_, next)) = current.step() {
+        steps += 1;
+        if steps > fuel {
+            return Err(Violation::new(
+                Property::Termination,
+                format!("no normal form was reached within {fuel} steps"),
+            ));
+        }
+        match next.get_type() {
+            Ok(typed) if typed.ty() == ty => {}
+            Ok(typed) => {
+                return Err(Violation::new(
+                    Property::Preservation,
+                    format!(
+                        "step {steps} produced {}, of type {} rather than {ty}",
+                        next.to_canonical_string(),
+                        typed.ty()
+                    ),
+                ))
+            }
+            Err(e) => {
+                return Err(Violation::new(
+                    Property::Preservation,
+                    format!(
+                        "step {steps} produced {}, which is not well-typed: {e}",
+                        next.to_canonical_string()
+                    ),
+                ))
+            }
+        }
+        current = next;
+    }
+    if !matches!(current, LambdaTerm::Abstraction { .. }) {
+        return Err(Violation::new(
+            Property::Progress,
+            format!(
+                "reduction got stuck at {}, which is not an abstraction",
+                current.to_canonical_string()
+            ),
+        ));
+    }
+    Ok(current)
+}
+
+/// Check that `beta_reduce` and the arena agree with the normal form of the term found by
+/// stepping, that `type_of` and the arena agree with its type, and that `beta_reduce_strict`
+/// agrees with them up to equivalence, even once the term's dead bindings are pruned and its
+/// common subterms shared.
+fn check_agreement(term: &LambdaTerm, ty: &Type, stepped: &LambdaTerm) -> Result<(), Violation> {
+    let reduced = term.beta_reduce();
+    if reduced != *stepped {
+        return Err(Violation::new(
+            Property::Agreement,
+            format!(
+                "beta_reduce produced {}, but stepping produced {}",
+                reduced.to_canonical_string(),
+                stepped.to_canonical_string()
+            ),
+        ));
+    }
+
+    match term.type_of() {
+        Ok(memoized_ty) if memoized_ty == *ty => {}
+        Ok(memoized_ty) => {
+            return Err(Violation::new(
+                Property::Agreement,
+                format!("type_of gave type {memoized_ty}, rather than {ty}"),
+            ))
+        }
+        Err(e) => {
+            return Err(Violation::new(
+                Property::Agreement,
+                format!("type_of rejected the term: {e}"),
+            ))
+        }
+    }
+
+    let mut arena = TermArena::new();
+    let id = arena.insert(term);
+    match arena.type_of(id) {
+        Ok(arena_ty) if arena.ty(arena_ty) == *ty => {}
+        Ok(arena_ty) => {
+            return Err(Violation::new(
+                Property::Agreement,
+                format!(
+                    "the arena gave type {}, rather than {ty}",
+                    arena.ty(arena_ty)
+                ),
+            ))
+        }
+        Err(e) => {
+            return Err(Violation::new(
+                Property::Agreement,
+                format!("the arena rejected the term: {e}"),
+            ))
+        }
+    }
+    let arena_reduced = arena.beta_reduce(id);
+    let arena_reduced = arena.term(arena_reduced);
+    if arena_reduced != reduced {
+        return Err(Violation::new(
+            Property::Agreement,
+            format!(
+                "the arena produced {}, rather than {}",
+                arena_reduced.to_canonical_string(),
+                reduced.to_canonical_string()
+            ),
+        ));
+    }
+
+    let shared = term
+        .prune()
+        .share()
+        .expect("the subterms of a well-typed term should be well-typed");
+    let strict = shared.beta_reduce_strict();
+    if !matches!(strict, LambdaTerm::Abstraction { .. })
+        || !strict.is_equivalent(&reduced, Equivalence::Beta)
+    {
+        return Err(Violation::new(
+            Property::Agreement,
+            format!(
+                "beta_reduce_strict produced {}, which is not equivalent to {}",
+                strict.to_canonical_string(),
+                reduced.to_canonical_string()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn run(args: &SelftestArgs) {
+    let seed = seed_or_clock(args.seed);
+    let mut generator = TermGenerator::new(seed);
+    let mut failures = 0;
+
+    for i in 0..args.count {
+        let (term, ty) = generator.term(args.size).unwrap_or_else(|| {
+            eprintln!("Unable to find a term with at most {} nodes", args.size);
+            exit(1);
+        });
+
+        let violation = match term.get_type() {
+            Ok(typed) if *typed.ty() == ty => check(&term, &ty, args.fuel).err(),
+            Ok(typed) => Some(Violation::new(
+                Property::Typing,
+                format!(
+                    "the term was generated at type {ty}, but has type {}",
+                    typed.ty()
+                ),
+            )),
+            Err(e) => Some(Violation::new(
+                Property::Typing,
+                format!("the term was generated at type {ty}, but is not well-typed: {e}"),
+            )),
+        };
+        let Some(violation) = violation else {
+            continue;
+        };
+        failures += 1;
+
+        // NOTE: A failure of typing cannot be minimized, since there is nothing for a smaller
+        // term to disagree with the type checker about.
+        let minimized = if violation.property == Property::Typing {
+            term.clone()
+        } else {
+            term.minimize(|t| {
+                t.get_type().is_ok_and(|typed| {
+                    check(t, typed.ty(), args.fuel).is_err_and(|v| v.property == violation.property)
+                })
+            })
+        };
+
+        println!("counterexample to {} (term {i}):", violation.property);
+        println!("  term:      {}", term.to_canonical_string());
+        println!("  minimized: {}", minimized.to_canonical_string());
+        println!("  {}", violation.detail);
+    }
+
+    println!(
+        "checked {} terms with seed {seed}: {failures} counterexamples",
+        args.count
+    );
+    if failures > 0 {
+        exit(1);
+    }
+}
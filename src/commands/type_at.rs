@@ -0,0 +1,81 @@
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::document::Document;
+
+use super::{line_and_column, offset_of};
+use crate::loader::Loader;
+
+#[derive(Args)]
+pub struct TypeArgs {
+    /// File containing the program
+    file: PathBuf,
+
+    /// Position of the term whose type is printed, as a line and a column, both counted from 1
+    #[arg(long, value_name = "LINE:COL", value_parser = parse_position)]
+    at: (usize, usize),
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by <FILE> are
+    /// cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// Parse a position written as `LINE:COL`.
+fn parse_position(position: &str) -> Result<(usize, usize), String> {
+    let (line, column) = position
+        .split_once(':')
+        .ok_or_else(|| String::from("expected LINE:COL"))?;
+    let parse = |n: &str| match n.parse() {
+        Ok(0) | Err(_) => Err(format!("{n} is not a line or column, which count from 1")),
+        Ok(n) => Ok(n),
+    };
+    Ok((parse(line)?, parse(column)?))
+}
+
+/// Print the type of the innermost term at the position requested by the user, along with the
+/// variables bound around it and any errors in it.
+pub fn run(args: &TypeArgs) {
+    let source = read_to_string(&args.file).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {e}", args.file.display());
+        exit(1);
+    });
+    let (line, column) = args.at;
+    let offset = offset_of(&source, line, column).unwrap_or_else(|| {
+        eprintln!(
+            "There is no position {line}:{column} in {}",
+            args.file.display()
+        );
+        exit(1);
+    });
+
+    let document = Document::with_importer(&source, &mut Loader::new(&args.file, !args.no_cache));
+    let Some((span, ty)) = document.type_at(offset) else {
+        eprintln!(
+            "There is no term with a type which can be known at {}:{line}:{column}",
+            args.file.display()
+        );
+        exit(1);
+    };
+    let (start_line, start_column) = line_and_column(&source, span.start);
+    let (end_line, end_column) = line_and_column(&source, span.end);
+    let term = source[span.start..span.end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("{start_line}:{start_column}-{end_line}:{end_column}: {term} : {ty}");
+    for (variable, ty) in document.context_at(offset) {
+        println!("  {variable} : {ty}");
+    }
+    // NOTE: The type printed is the one the term would have if its errors were fixed, so the
+    // errors themselves are printed too, wherever they overlap it.
+    for diagnostic in document.diagnostics() {
+        if diagnostic.span.start < span.end && span.start < diagnostic.span.end {
+            let (line, column) = line_and_column(&source, diagnostic.span.start);
+            println!("{line}:{column}: error: {}", diagnostic.message);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Args;
+
+use kombi::certificate::Certificate;
+use kombi::reduce::Equivalence;
+
+use super::{read_or_exit, Format, Prelude};
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// File containing the term which was evaluated
+    file: PathBuf,
+
+    /// File containing the certificate, as written by --certificate
+    certificate: PathBuf,
+
+    /// Check the evaluation of the application of the term contained in <FILE> to the term
+    /// contained in <ARG>
+    #[arg(short, long)]
+    arg: Option<PathBuf>,
+
+    /// Format of <FILE> and <ARG>
+    #[arg(long, value_enum, default_value_t = Format::Kombi)]
+    from: Format,
+
+    /// Load the definitions of the bundled module with the given name, such as `combinators`,
+    /// before reading <FILE> and <ARG>. May be given more than once
+    #[arg(short, long = "prelude", value_name = "MODULE")]
+    prelude: Vec<String>,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by <FILE> and
+    /// <ARG> are cached
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// Check the certificate supplied by the user against the term it claims to have evaluated,
+/// exiting unsuccessfully if it does not hold.
+pub fn run(args: &VerifyArgs) {
+    let prelude = Prelude::load_or_exit(&args.prelude);
+    let read = |path| {
+        read_or_exit(
+            path,
+            args.from,
+            &prelude,
+            Equivalence::default(),
+            !args.no_cache,
+        )
+    };
+    let input = read(&args.file);
+    let input = match &args.arg {
+        Some(path) => input.apply(read(path)),
+        None => input,
+    };
+    let lambda_term = input.into_typed().unwrap_or_else(|(term, e)| {
+        eprintln!("Term {term} cannot be given a type: {e}");
+        exit(1);
+    });
+
+    let source = read_to_string(&args.certificate).unwrap_or_else(|e| {
+        eprintln!("Unable to open file {}: {e}", args.certificate.display());
+        exit(1);
+    });
+    let certificate: Certificate = source.parse().unwrap_or_else(|e| {
+        eprintln!("Invalid certificate {}: {e}", args.certificate.display());
+        exit(1);
+    });
+    if let Err(e) = certificate.verify(&lambda_term) {
+        eprintln!(
+            "Certificate {} does not hold: {e}",
+            args.certificate.display()
+        );
+        exit(1);
+    }
+    println!(
+        "Certificate {} holds: {} β-steps reduce the term to {}",
+        args.certificate.display(),
+        certificate.redexes.len(),
+        certificate.result
+    );
+}
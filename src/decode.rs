@@ -0,0 +1,521 @@
+//! Encoding values as terms, such as the Church numerals, and reading them back out of the terms
+//! which encode them.
+//!
+//! Embedders can go between Rust values and terms through the `Encodable` trait, which is
+//! implemented for `u64`, `bool`, `Vec` and pairs of encodable types.
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::untyped::UntypedTerm;
+
+/// A way in which values are encoded as terms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    /// Church numerals, in which `n` is `λf. λx. f (f ... (f x))`, applying `f` `n` times.
+    Nat,
+    /// Church booleans, in which true is `λt. λf. t` and false is `λt. λf. f`.
+    Bool,
+    /// Church-encoded lists, in which `[x, y, ...]` is `λc. λn. c x (c y ... n)`, whose elements
+    /// are encoded in the given encoding.
+    List(Box<Encoding>),
+    /// Church-encoded pairs, in which `(x, y)` is `λp. p x y`, whose values are encoded in the
+    /// given encodings.
+    Pair(Box<Encoding>, Box<Encoding>),
+}
+
+impl Encoding {
+    /// Write the `Encoding` as the argument of another, in parentheses unless it is a single
+    /// word.
+    fn fmt_argument(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nat | Self::Bool => self.fmt(f),
+            Self::List(_) | Self::Pair(..) => write!(f, "({self})"),
+        }
+    }
+
+    /// Return a description of the terms which encode values in the `Encoding`.
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Nat => "a Church numeral, λf. λx. f (f (... x))",
+            Self::Bool => "a Church boolean, λt. λf. t or λt. λf. f",
+            Self::List(_) => "a Church-encoded list, λc. λn. c x (c y (... n))",
+            Self::Pair(..) => "a Church-encoded pair, λp. p x y",
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nat => write!(f, "nat"),
+            Self::Bool => write!(f, "bool"),
+            Self::List(element) => {
+                write!(f, "list ")?;
+                element.fmt_argument(f)
+            }
+            Self::Pair(first, second) => {
+                write!(f, "pair ")?;
+                first.fmt_argument(f)?;
+                write!(f, " ")?;
+                second.fmt_argument(f)
+            }
+        }
+    }
+}
+
+/// The error returned when a string does not name an `Encoding`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEncoding(pub String);
+
+impl Display for UnknownEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown encoding {}; expected nat, bool, list followed by an encoding, or pair \
+             followed by two",
+            self.0
+        )
+    }
+}
+
+impl Error for UnknownEncoding {}
+
+/// Parse the `Encoding` written at the start of the given words, consuming its words.
+fn parse_encoding<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Encoding> {
+    match words.next()? {
+        "(" => {
+            let encoding = parse_encoding(words)?;
+            (words.next()? == ")").then_some(encoding)
+        }
+        "nat" => Some(Encoding::Nat),
+        "bool" => Some(Encoding::Bool),
+        "list" => Some(Encoding::List(Box::new(parse_encoding(words)?))),
+        "pair" => {
+            let first = parse_encoding(words)?;
+            let second = parse_encoding(words)?;
+            Some(Encoding::Pair(Box::new(first), Box::new(second)))
+        }
+        _ => None,
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = UnknownEncoding;
+
+    /// Parse an `Encoding` written as its name, with the encodings it is made up of following
+    /// `list` or `pair`, as in `list nat` or `pair nat (list bool)`, optionally in parentheses.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let spaced = string.replace('(', " ( ").replace(')', " ) ");
+        let mut words = spaced.split_whitespace();
+        match parse_encoding(&mut words) {
+            Some(encoding) if words.next().is_none() => Ok(encoding),
+            _ => Err(UnknownEncoding(String::from(string))),
+        }
+    }
+}
+
+/// A value decoded from a term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Nat(u64),
+    Bool(bool),
+    List(Vec<Value>),
+    Pair(Box<Value>, Box<Value>),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nat(n) => n.fmt(f),
+            Self::Bool(b) => b.fmt(f),
+            Self::List(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+            Self::Pair(first, second) => write!(f, "({first}, {second})"),
+        }
+    }
+}
+
+/// The error returned when a term does not encode a value, because some part of it is not of the
+/// shape which its encoding requires.
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    /// The encoding which the part was expected to be in.
+    pub encoding: Encoding,
+    /// The part of the term which is not of the shape of the encoding, in normal form.
+    pub term: UntypedTerm,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {}, but found {}",
+            self.encoding.describe(),
+            self.term
+        )
+    }
+}
+
+impl Error for DecodeError {}
+
+/// The error returned when a value cannot be encoded in an encoding, since it is not of its
+/// shape, as when a list is to be encoded as a numeral.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeError {
+    /// The encoding which the value was to be encoded in.
+    pub encoding: Encoding,
+    /// The part of the value which is not of the shape of the encoding.
+    pub value: Value,
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} cannot be encoded as {}", self.value, self.encoding)
+    }
+}
+
+impl Error for EncodeError {}
+
+impl UntypedTerm {
+    /// Return the value which the `UntypedTerm`, which must be in normal form, encodes in the
+    /// given `Encoding`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DecodeError` locating the first part of the term which is not of the shape of
+    /// the encoding it should be in.
+    pub fn decode(&self, encoding: &Encoding) -> Result<Value, DecodeError> {
+        let error = || DecodeError {
+            encoding: encoding.clone(),
+            term: self.clone(),
+        };
+        if let Encoding::Pair(first, second) = encoding {
+            let UntypedTerm::Abstraction { body, .. } = self else {
+                return Err(error());
+            };
+            let UntypedTerm::Application {
+                function,
+                argument: y,
+            } = body.as_ref()
+            else {
+                return Err(error());
+            };
+            let UntypedTerm::Application {
+                function: p,
+                argument: x,
+            } = function.as_ref()
+            else {
+                return Err(error());
+            };
+            // The values are closed, so they do not refer to the variable bound by the encoding,
+            // and do not need shifting out from under it.
+            if !matches!(p.as_ref(), UntypedTerm::Variable { idx: 0 })
+                || !x.is_closed()
+                || !y.is_closed()
+            {
+                return Err(error());
+            }
+            return Ok(Value::Pair(
+                Box::new(x.decode(first)?),
+                Box::new(y.decode(second)?),
+            ));
+        }
+
+        // Every other encoding is an abstraction over two variables, and those of numerals and
+        // lists apply the first along a spine ending in the second.
+        let UntypedTerm::Abstraction { body, .. } = self else {
+            return Err(error());
+        };
+        let UntypedTerm::Abstraction { body, .. } = body.as_ref() else {
+            return Err(error());
+        };
+        let mut term = body.as_ref();
+        let value = match encoding {
+            Encoding::Nat => {
+                let mut n = 0;
+                while let UntypedTerm::Application { function, argument } = term {
+                    if !matches!(function.as_ref(), UntypedTerm::Variable { idx: 1 }) {
+                        return Err(error());
+                    }
+                    n += 1;
+                    term = argument;
+                }
+                Value::Nat(n)
+            }
+            Encoding::Bool => {
+                return match term {
+                    UntypedTerm::Variable { idx: 1 } => Ok(Value::Bool(true)),
+                    UntypedTerm::Variable { idx: 0 } => Ok(Value::Bool(false)),
+                    _ => Err(error()),
+                };
+            }
+            Encoding::List(element) => {
+                let mut elements = Vec::new();
+                while let UntypedTerm::Application { function, argument } = term {
+                    let UntypedTerm::Application {
+                        function: cons,
+                        argument: head,
+                    } = function.as_ref()
+                    else {
+                        return Err(error());
+                    };
+                    // An element is closed, so it refers to neither of the variables bound by
+                    // the encoding, and does not need shifting out from under them.
+                    if !matches!(cons.as_ref(), UntypedTerm::Variable { idx: 1 })
+                        || !head.is_closed()
+                    {
+                        return Err(error());
+                    }
+                    elements.push(head.decode(element)?);
+                    term = argument;
+                }
+                Value::List(elements)
+            }
+            Encoding::Pair(..) => unreachable!("pairs should have been decoded already"),
+        };
+        if matches!(term, UntypedTerm::Variable { idx: 0 }) {
+            Ok(value)
+        } else {
+            Err(error())
+        }
+    }
+}
+
+impl LambdaTerm {
+    /// Return the value which the well-typed `LambdaTerm` encodes in the given `Encoding`. The
+    /// term is normalized first, so it need not be written in the form of the encoding itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DecodeError` locating the first part of the normal form of the term which is
+    /// not of the shape of the encoding it should be in.
+    pub fn decode(&self, encoding: &Encoding) -> Result<Value, DecodeError> {
+        self.normalize().erase_types().decode(encoding)
+    }
+}
+
+/// Return the function type from the first type to the second.
+fn arrow(argument_type: Type, return_type: Type) -> Type {
+    Type::FunctionType(Box::new(argument_type), Box::new(return_type))
+}
+
+/// Return the abstraction over a variable of the given name and type.
+fn abstraction(variable: &str, argument_type: Type, body: LambdaTerm) -> LambdaTerm {
+    LambdaTerm::Abstraction {
+        variable: String::from(variable),
+        argument_type,
+        body: Rc::new(body),
+    }
+}
+
+/// Return the application of the given function to the given argument.
+fn application(function: LambdaTerm, argument: LambdaTerm) -> LambdaTerm {
+    LambdaTerm::Application {
+        function: Rc::new(function),
+        argument: Rc::new(argument),
+    }
+}
+
+impl Encoding {
+    /// Return the type of the terms which encode values in the `Encoding`, taking `base` to be
+    /// the type of the results which they are eliminated to, as `A` is in `(A→A)→A→A`.
+    #[must_use]
+    pub fn ty(&self, base: &Type) -> Type {
+        match self {
+            Self::Nat => arrow(
+                arrow(base.clone(), base.clone()),
+                arrow(base.clone(), base.clone()),
+            ),
+            Self::Bool => arrow(base.clone(), arrow(base.clone(), base.clone())),
+            Self::List(element) => {
+                let cons = arrow(element.ty(base), arrow(base.clone(), base.clone()));
+                arrow(cons, arrow(base.clone(), base.clone()))
+            }
+            Self::Pair(first, second) => {
+                let consumer = arrow(first.ty(base), arrow(second.ty(base), base.clone()));
+                arrow(consumer, base.clone())
+            }
+        }
+    }
+
+    /// Return the closed term which encodes the given value in the `Encoding`, whose type is
+    /// given by `ty` for the same `base`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EncodeError` locating the first part of the value which is not of the shape
+    /// of the encoding it should be in.
+    pub fn encode(&self, value: &Value, base: &Type) -> Result<LambdaTerm, EncodeError> {
+        let variable = |idx| LambdaTerm::Variable { idx };
+        Ok(match (self, value) {
+            (Self::Nat, Value::Nat(n)) => {
+                let mut body = variable(0);
+                for _ in 0..*n {
+                    body = application(variable(1), body);
+                }
+                abstraction(
+                    "f",
+                    arrow(base.clone(), base.clone()),
+                    abstraction("x", base.clone(), body),
+                )
+            }
+            (Self::Bool, Value::Bool(b)) => abstraction(
+                "t",
+                base.clone(),
+                abstraction("f", base.clone(), variable(u64::from(*b))),
+            ),
+            (Self::List(element), Value::List(elements)) => {
+                let mut body = variable(0);
+                for value in elements.iter().rev() {
+                    let head = application(variable(1), element.encode(value, base)?);
+                    body = application(head, body);
+                }
+                let cons = arrow(element.ty(base), arrow(base.clone(), base.clone()));
+                abstraction("c", cons, abstraction("n", base.clone(), body))
+            }
+            (Self::Pair(first, second), Value::Pair(x, y)) => {
+                let consumer = arrow(first.ty(base), arrow(second.ty(base), base.clone()));
+                let body = application(
+                    application(variable(0), first.encode(x, base)?),
+                    second.encode(y, base)?,
+                );
+                abstraction("p", consumer, body)
+            }
+            _ => {
+                return Err(EncodeError {
+                    encoding: self.clone(),
+                    value: value.clone(),
+                })
+            }
+        })
+    }
+}
+
+/// A Rust type whose values can be encoded as terms, and decoded from them.
+pub trait Encodable: Sized {
+    /// Return the encoding in which values of the type are encoded.
+    fn encoding() -> Encoding;
+
+    /// Return the `Value` which the Rust value stands for.
+    fn into_value(self) -> Value;
+
+    /// Return the Rust value which the given `Value` stands for, or `None` if it is not of the
+    /// shape of the encoding of the type.
+    fn from_value(value: Value) -> Option<Self>;
+
+    /// Return the closed term which encodes the Rust value, taking `base` to be the type of the
+    /// results which it is eliminated to, as `Encoding::ty` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not of the shape of the encoding of the type, which should never
+    /// happen.
+    #[must_use]
+    fn encode(self, base: &Type) -> LambdaTerm {
+        Self::encoding()
+            .encode(&self.into_value(), base)
+            .expect("a value should be of the shape of the encoding of its type")
+    }
+
+    /// Return the Rust value which the given well-typed term encodes, normalizing it first.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DecodeError` locating the first part of the normal form of the term which is
+    /// not of the shape of the encoding it should be in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value decoded is not of the shape of the encoding of the type, which should
+    /// never happen.
+    fn decode(term: &LambdaTerm) -> Result<Self, DecodeError> {
+        let value = term.decode(&Self::encoding())?;
+        Ok(
+            Self::from_value(value)
+                .expect("a decoded value should be of the shape of its encoding"),
+        )
+    }
+}
+
+impl Encodable for u64 {
+    fn encoding() -> Encoding {
+        Encoding::Nat
+    }
+
+    fn into_value(self) -> Value {
+        Value::Nat(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Nat(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl Encodable for bool {
+    fn encoding() -> Encoding {
+        Encoding::Bool
+    }
+
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Encodable> Encodable for Vec<T> {
+    fn encoding() -> Encoding {
+        Encoding::List(Box::new(T::encoding()))
+    }
+
+    fn into_value(self) -> Value {
+        Value::List(self.into_iter().map(T::into_value).collect())
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::List(elements) => elements.into_iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<S: Encodable, T: Encodable> Encodable for (S, T) {
+    fn encoding() -> Encoding {
+        Encoding::Pair(Box::new(S::encoding()), Box::new(T::encoding()))
+    }
+
+    fn into_value(self) -> Value {
+        Value::Pair(Box::new(self.0.into_value()), Box::new(self.1.into_value()))
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Pair(first, second) => Some((S::from_value(*first)?, T::from_value(*second)?)),
+            _ => None,
+        }
+    }
+}
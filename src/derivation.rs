@@ -0,0 +1,196 @@
+//! Typing derivations, written as LaTeX proof trees.
+//!
+//! A derivation is built from three rules: a variable has the type its binder gives it (Var),
+//! an abstraction has a function type if its body has the return type in the extended context
+//! (→I), and an application has the return type of its function if its argument has the
+//! argument type (→E).
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::surface::SurfaceTerm;
+use crate::type_check::{TypeError, TypedNode, TypedTerm};
+
+/// A LaTeX package for typesetting proof trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ProofStyle {
+    /// The `bussproofs` package, in which inferences are written `\UnaryInfC` and so on.
+    Bussproofs,
+    /// The `ebproof` package, in which inferences are written `\infer1` and so on.
+    Ebproof,
+}
+
+impl ProofStyle {
+    /// Return the lines inferring `conclusion` from the given number of premises, which have
+    /// already been written, by the rule with the given name.
+    fn inference(self, premises: usize, rule: &str, conclusion: &str) -> Vec<String> {
+        match self {
+            ProofStyle::Bussproofs => {
+                let mut lines = Vec::new();
+                if premises == 0 {
+                    lines.push(String::from("\\AxiomC{}"));
+                }
+                let inference = match premises {
+                    0 | 1 => "Unary",
+                    2 => "Binary",
+                    _ => unreachable!("no rule has more than two premises"),
+                };
+                lines.push(format!("\\RightLabel{{\\scriptsize {rule}}}"));
+                lines.push(format!("\\{inference}InfC{{${conclusion}$}}"));
+                lines
+            }
+            ProofStyle::Ebproof => {
+                vec![format!(
+                    "\\infer{premises}[\\scriptsize {rule}]{{{conclusion}}}"
+                )]
+            }
+        }
+    }
+}
+
+/// Write the given identifier in LaTeX math mode, in italics as a single letter would be.
+fn latex_identifier(name: &str) -> String {
+    let name = name.replace('_', "\\_");
+    if name.chars().count() == 1 {
+        name
+    } else {
+        format!("\\mathit{{{name}}}")
+    }
+}
+
+fn latex_type(ty: &Type) -> String {
+    match ty {
+        Type::BaseType(name) => latex_identifier(name.as_ref()),
+        Type::FunctionType(argument_type, return_type) => {
+            let argument = latex_type(argument_type);
+            let argument = if let Type::FunctionType(..) = **argument_type {
+                format!("({argument})")
+            } else {
+                argument
+            };
+            format!("{argument} \\to {}", latex_type(return_type))
+        }
+    }
+}
+
+/// Write the given term in LaTeX math mode, with as few parentheses as possible, where
+/// `rightmost` is whether it extends to the right end of the enclosing term.
+fn latex_term(term: &SurfaceTerm, rightmost: bool) -> String {
+    match term {
+        SurfaceTerm::Variable { name, .. } => latex_identifier(name),
+        SurfaceTerm::Abstraction {
+            variable,
+            argument_type,
+            body,
+            ..
+        } => {
+            let abstraction = format!(
+                "\\lambda {}{{:}}{}.\\, {}",
+                latex_identifier(variable),
+                latex_type(argument_type),
+                latex_term(body, true)
+            );
+            if rightmost {
+                abstraction
+            } else {
+                format!("({abstraction})")
+            }
+        }
+        SurfaceTerm::Application {
+            function, argument, ..
+        } => {
+            let argument = if let SurfaceTerm::Application { .. } = **argument {
+                format!("({})", latex_term(argument, true))
+            } else {
+                latex_term(argument, rightmost)
+            };
+            format!("{}\\; {argument}", latex_term(function, false))
+        }
+    }
+}
+
+/// Push the lines of the derivation of the typing of `term`, whose types are given by `typed`,
+/// in the context `ctx`, onto `lines`, premises first.
+fn derive(
+    term: &SurfaceTerm,
+    typed: &TypedTerm,
+    ctx: &mut Vec<(String, Type)>,
+    style: ProofStyle,
+    lines: &mut Vec<String>,
+) {
+    let context = ctx
+        .iter()
+        .map(|(name, ty)| format!("{} : {}", latex_identifier(name), latex_type(ty)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let conclusion = format!(
+        "{context}{}\\vdash {} : {}",
+        if context.is_empty() { "" } else { " " },
+        latex_term(term, true),
+        latex_type(typed.ty())
+    );
+
+    let (premises, rule) = match (term, &typed.node) {
+        (SurfaceTerm::Variable { .. }, TypedNode::Variable { .. }) => (0, "(Var)"),
+        (
+            SurfaceTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+                ..
+            },
+            TypedNode::Abstraction {
+                body: typed_body, ..
+            },
+        ) => {
+            ctx.push((variable.clone(), argument_type.clone()));
+            derive(body, typed_body, ctx, style, lines);
+            ctx.pop();
+            (1, "($\\to$I)")
+        }
+        (
+            SurfaceTerm::Application {
+                function, argument, ..
+            },
+            TypedNode::Application {
+                function: typed_function,
+                argument: typed_argument,
+            },
+        ) => {
+            derive(function, typed_function, ctx, style, lines);
+            derive(argument, typed_argument, ctx, style, lines);
+            (2, "($\\to$E)")
+        }
+        _ => unreachable!("a term and its typing should have the same shape"),
+    };
+    lines.extend(style.inference(premises, rule, &conclusion));
+}
+
+impl LambdaTerm {
+    /// Return the derivation of the type of the closed `LambdaTerm` as a LaTeX proof tree,
+    /// typeset with the given package, in a `prooftree` environment.
+    ///
+    /// Variables are named as `SurfaceTerm::from_core` names them, so that every variable in the
+    /// derivation refers to the binder which it appears to.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if the `LambdaTerm` is not well-typed.
+    pub fn to_latex_derivation(&self, style: ProofStyle) -> Result<String, TypeError> {
+        let typed = self.get_type()?;
+        let mut lines = vec![String::from("\\begin{prooftree}")];
+        derive(
+            &SurfaceTerm::from_core(self),
+            &typed,
+            &mut Vec::new(),
+            style,
+            &mut lines,
+        );
+        lines.push(String::from("\\end{prooftree}"));
+        Ok(lines.join("\n") + "\n")
+    }
+}
@@ -0,0 +1,156 @@
+//! Tromp's lambda diagrams, which draw the binding structure of a term.
+//!
+//! In a diagram, every abstraction is a horizontal bar, under which its body is drawn, and every
+//! variable is a vertical line hanging from the bar of the abstraction which binds it. An
+//! application is drawn as its function and argument side by side, with a horizontal link joining
+//! the leftmost line of the argument to the leftmost line of the function, which carries on below
+//! it. Types and names play no part, so α-equivalent terms have the same diagram.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::parse::LambdaTerm;
+use crate::untyped::UntypedTerm;
+
+/// The size, in SVG user units, of half of a cell of the grid on which diagrams are laid out.
+const UNIT: usize = 4;
+
+/// The lines of a diagram, as they are being laid out on a grid of cells, in which every variable
+/// has its own column, and every abstraction and application its own row.
+#[derive(Default)]
+struct Diagram {
+    /// The bars of abstractions, as their row and their first and last columns.
+    bars: Vec<(usize, usize, usize)>,
+    /// The links of applications, as their row and the columns of the lines they join.
+    links: Vec<(usize, usize, usize)>,
+    /// Vertical lines, as their column and their top and bottom, each measured in half-rows, so
+    /// that lines can end in the middle of a row, where bars and links are drawn.
+    vertical: Vec<(usize, usize, usize)>,
+}
+
+/// The extent of a term laid out in a `Diagram`, which occupies `width` columns and `height`
+/// rows from its top left corner, and whose leftmost line leaves its bottom edge.
+struct Extent {
+    width: usize,
+    height: usize,
+}
+
+impl Diagram {
+    /// Lay out the given term with its top left corner in the given column and row, where
+    /// `binders` holds the row of the bar of every enclosing abstraction, innermost last.
+    fn lay_out(
+        &mut self,
+        term: &UntypedTerm,
+        column: usize,
+        row: usize,
+        binders: &mut Vec<usize>,
+    ) -> Extent {
+        match term {
+            UntypedTerm::Variable { idx } => {
+                // A free variable hangs from the top of the diagram, as if bound just above it.
+                let top = usize::try_from(*idx)
+                    .ok()
+                    .and_then(|i| binders.len().checked_sub(i + 1))
+                    .map_or(0, |i| 2 * binders[i] + 1);
+                self.vertical.push((column, top, 2 * row + 2));
+                Extent {
+                    width: 1,
+                    height: 1,
+                }
+            }
+            UntypedTerm::Abstraction { body, .. } => {
+                binders.push(row);
+                let body = self.lay_out(body, column, row + 1, binders);
+                binders.pop();
+                self.bars.push((row, column, column + body.width - 1));
+                Extent {
+                    width: body.width,
+                    height: body.height + 1,
+                }
+            }
+            UntypedTerm::Application { function, argument } => {
+                let function = self.lay_out(function, column, row, binders);
+                let argument_column = column + function.width;
+                let argument = self.lay_out(argument, argument_column, row, binders);
+
+                // Both leftmost lines are extended down to a new row below both terms, where the
+                // link joins them, and the function's carries on to the bottom of that row.
+                let link = row + function.height.max(argument.height);
+                self.vertical
+                    .push((column, 2 * (row + function.height), 2 * link + 2));
+                self.vertical
+                    .push((argument_column, 2 * (row + argument.height), 2 * link + 1));
+                self.links.push((link, column, argument_column));
+                Extent {
+                    width: function.width + argument.width,
+                    height: link - row + 1,
+                }
+            }
+        }
+    }
+}
+
+impl UntypedTerm {
+    /// Return the lambda diagram of the `UntypedTerm` as a standalone SVG image.
+    #[must_use]
+    pub fn to_lambda_diagram(&self) -> String {
+        let mut diagram = Diagram::default();
+        let extent = diagram.lay_out(self, 0, 0, &mut Vec::new());
+
+        // Every column is a cell wide, with its line down the middle, and every row a cell high,
+        // with its bar or link across the middle. Bars stop short of the edges of their columns,
+        // so that the bars of neighbouring terms are kept apart.
+        let x = |column: usize| (2 * column + 1) * UNIT;
+        let (width, height) = (2 * extent.width * UNIT, 2 * extent.height * UNIT);
+        let mut svg = String::new();
+        write!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\">\n<g stroke=\"black\" stroke-width=\"2\" \
+             stroke-linecap=\"square\">\n"
+        )
+        .expect("writing to a string should not fail");
+        for (row, first, last) in &diagram.bars {
+            let y = (2 * row + 1) * UNIT;
+            writeln!(
+                svg,
+                "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\"/>",
+                x(*first) - UNIT / 2,
+                x(*last) + UNIT / 2
+            )
+            .expect("writing to a string should not fail");
+        }
+        for (row, first, last) in &diagram.links {
+            let y = (2 * row + 1) * UNIT;
+            writeln!(
+                svg,
+                "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\"/>",
+                x(*first),
+                x(*last)
+            )
+            .expect("writing to a string should not fail");
+        }
+        for (column, top, bottom) in &diagram.vertical {
+            writeln!(
+                svg,
+                "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\"/>",
+                x(*column),
+                top * UNIT,
+                bottom * UNIT
+            )
+            .expect("writing to a string should not fail");
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+}
+
+impl LambdaTerm {
+    /// Return the lambda diagram of the `LambdaTerm`, whose types are not shown, as a standalone
+    /// SVG image.
+    #[must_use]
+    pub fn to_lambda_diagram(&self) -> String {
+        self.erase_types().to_lambda_diagram()
+    }
+}
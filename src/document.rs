@@ -0,0 +1,726 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::environment::{Environment, EnvironmentError, Importer, NoImports};
+use crate::parse::{LambdaTerm, ParseError, Type};
+use crate::surface::{Macro, Program, ScopeError, Span, SurfaceTerm};
+use crate::type_check::{TypeError, TypedTerm};
+
+/// A problem found in a `Document`, attributed to the part of the source responsible for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A program being edited, analysed as a whole once, so that questions about any part of it can
+/// be answered without parsing or checking it again.
+///
+/// Unlike `Environment::load`, analysis does not stop at the first problem: every definition and
+/// macro is checked, and those which are invalid are left out of scope for the rest of the
+/// program. So that the rest of the program can still be given types while it is being edited,
+/// types are also worked out despite errors, as `LambdaTerm::get_partial_type` does, with every
+/// invalid definition given the type it would have had if its errors were fixed where they are.
+#[derive(Debug, Clone)]
+pub struct Document {
+    program: Program,
+    /// The definitions which were found to be valid, each with the index of the definition in
+    /// `program` which produced it.
+    accepted: Vec<(usize, LambdaTerm)>,
+    /// The macros which were found to be valid, each with the index of the macro in `program`
+    /// which produced it.
+    accepted_macros: Vec<(usize, Macro)>,
+    /// The definitions which were found to be invalid, each with the index of the definition in
+    /// `program`, and the type it would have had, if that can be known.
+    failed: Vec<(usize, Option<Type>)>,
+    /// The modules which were imported, each with the index of the import in `program` which
+    /// imported it.
+    imported: Vec<(usize, Environment)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// What kind of thing a `Completion` names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// A variable bound by an enclosing abstraction.
+    Variable,
+    Definition,
+    /// A macro with the given number of parameters.
+    Macro(usize),
+}
+
+/// A name which may be written at some point of a `Document`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub name: String,
+    pub kind: CompletionKind,
+    /// The type of whatever is named, if it is not a macro, and its type can be known.
+    pub ty: Option<Type>,
+}
+
+/// Whatever binds a variable of a `Document`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    /// An abstraction, definition or macro of the document itself, with the given span.
+    Local(Span),
+    /// The definition or macro with the given name of the module imported by the `import`
+    /// directive with the given span, whose path is as it was written in the directive.
+    Imported {
+        span: Span,
+        path: String,
+        name: String,
+    },
+}
+
+/// A part of a program: either one of its definitions, macros or imports, identified by index,
+/// or its term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Definition(usize),
+    Macro(usize),
+    Import(usize),
+    Term,
+}
+
+impl Document {
+    /// Parse and check the program in the given source, which may not import any modules; see
+    /// `with_importer`.
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self::with_importer(source, &mut NoImports)
+    }
+
+    /// Parse and check the program in the given source, bringing the definitions and macros of
+    /// every module it imports, as given by `importer`, into scope where the `import` directive
+    /// appears. A module which cannot be imported is reported, and brings nothing into scope.
+    #[must_use]
+    pub fn with_importer(source: &str, importer: &mut dyn Importer) -> Self {
+        let mut document = Self {
+            program: Program::default(),
+            accepted: Vec::new(),
+            accepted_macros: Vec::new(),
+            failed: Vec::new(),
+            imported: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+        match source.parse::<Program>() {
+            Ok(program) => document.program = program,
+            Err(e) => {
+                document.diagnostics.push(Diagnostic::from_parse_error(&e));
+                return document;
+            }
+        }
+
+        // Definitions, macros and imports are checked in the order in which they were written, so
+        // that each is accepted before anything after it, which may refer to it, is checked.
+        let mut parts: Vec<_> = (0..document.program.definitions.len())
+            .map(Part::Definition)
+            .chain((0..document.program.macros.len()).map(Part::Macro))
+            .chain((0..document.program.imports.len()).map(Part::Import))
+            .collect();
+        parts.sort_by_key(|part| document.start_of(*part));
+        for part in parts {
+            match part {
+                Part::Definition(i) => {
+                    let term = document.program.definitions[i].term.clone();
+                    if let Some(term) = document.check(part, &term) {
+                        document.accepted.push((i, term));
+                    } else {
+                        let ty = document.partial_type_in_scope(part, &term, &[]);
+                        document.failed.push((i, ty));
+                    }
+                }
+                Part::Macro(i) => {
+                    let r#macro = &document.program.macros[i];
+                    match r#macro
+                        .body
+                        .to_core_with_macros(&r#macro.parameters, &|name| {
+                            document.lookup(part, name)
+                        }) {
+                        Ok(body) => document.accepted_macros.push((
+                            i,
+                            Macro {
+                                parameters: r#macro.parameters.len(),
+                                context: 0,
+                                body,
+                            },
+                        )),
+                        Err(e) => document.diagnostics.push(Diagnostic {
+                            span: e.span(),
+                            message: e.to_string(),
+                        }),
+                    }
+                }
+                Part::Import(i) => {
+                    let import = &document.program.imports[i];
+                    match importer.import(&import.path) {
+                        Ok(environment) => document.imported.push((i, environment)),
+                        Err(message) => {
+                            let e = EnvironmentError::Import {
+                                path: import.path.clone(),
+                                message,
+                            };
+                            document.diagnostics.push(Diagnostic {
+                                span: import.span,
+                                message: e.to_string(),
+                            });
+                        }
+                    }
+                }
+                Part::Term => unreachable!("the term is checked last"),
+            }
+        }
+        if let Some(term) = document.program.term.clone() {
+            document.check(Part::Term, &term);
+        }
+        document
+    }
+
+    /// Return every problem found in the program.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Return the innermost subterm at the given byte offset, along with the type it would have
+    /// if each error in it were fixed where it is, or `None` if there is no subterm there, or its
+    /// type cannot be known.
+    #[must_use]
+    pub fn type_at(&self, offset: usize) -> Option<(Span, Type)> {
+        let (part, term) = self.part_at(offset)?;
+        let mut binders = Vec::new();
+        let subterm = innermost(term, offset, &mut binders);
+        let ty = self.partial_type_in_scope(part, subterm, &binders)?;
+        Some((subterm.span(), ty))
+    }
+
+    /// Return the variable and type of every abstraction enclosing the innermost subterm at the
+    /// given byte offset, outermost first, which are the variables in scope there other than
+    /// definitions.
+    #[must_use]
+    pub fn context_at(&self, offset: usize) -> Vec<(String, Type)> {
+        let Some((_, term)) = self.part_at(offset) else {
+            return Vec::new();
+        };
+        let mut binders = Vec::new();
+        innermost(term, offset, &mut binders);
+        binders
+            .into_iter()
+            .map(|(variable, ty)| (variable.to_string(), ty.clone()))
+            .collect()
+    }
+
+    /// Return every name which may be written at the given byte offset, each only once, the
+    /// innermost first: the variables of the abstractions enclosing it, then the definitions and
+    /// macros before the part of the program containing it, whether they are valid or not.
+    #[must_use]
+    pub fn completions_at(&self, offset: usize) -> Vec<Completion> {
+        let mut completions: Vec<Completion> = Vec::new();
+        let mut push = |completion: Completion| {
+            if !completions.iter().any(|c| c.name == completion.name) {
+                completions.push(completion);
+            }
+        };
+
+        let start = if let Some((part, term)) = self.part_at(offset) {
+            let mut binders = Vec::new();
+            innermost(term, offset, &mut binders);
+            for (variable, ty) in binders.into_iter().rev() {
+                push(Completion {
+                    name: variable.to_string(),
+                    kind: CompletionKind::Variable,
+                    ty: Some(ty.clone()),
+                });
+            }
+            self.start_of(part)
+        } else {
+            offset
+        };
+
+        let definitions = self
+            .accepted
+            .iter()
+            .map(|(i, term)| (*i, term.get_type().ok().map(TypedTerm::into_type)));
+        let mut defined: Vec<_> = definitions
+            .chain(self.failed.iter().cloned())
+            .map(|(i, ty)| {
+                let definition = &self.program.definitions[i];
+                (
+                    definition.span.start,
+                    definition.name.as_str(),
+                    CompletionKind::Definition,
+                    ty,
+                )
+            })
+            .chain(self.accepted_macros.iter().map(|(i, r#macro)| {
+                let parameters = r#macro.parameters;
+                let r#macro = &self.program.macros[*i];
+                (
+                    r#macro.span.start,
+                    r#macro.name.as_str(),
+                    CompletionKind::Macro(parameters),
+                    None,
+                )
+            }))
+            .chain(self.imported.iter().flat_map(|(i, environment)| {
+                let start = self.program.imports[*i].span.start;
+                let definitions = environment
+                    .iter()
+                    .filter(|d| environment.get_macro(&d.name).is_none())
+                    .map(move |d| {
+                        let kind = CompletionKind::Definition;
+                        (start, d.name.as_str(), kind, Some(d.ty.clone()))
+                    });
+                let macros = environment.macros().map(move |(name, r#macro)| {
+                    (start, name, CompletionKind::Macro(r#macro.parameters), None)
+                });
+                definitions.chain(macros)
+            }))
+            .filter(|(defined, ..)| *defined < start)
+            .collect();
+        defined.sort_by_key(|(defined, ..)| core::cmp::Reverse(*defined));
+        for (_, name, kind, ty) in defined {
+            push(Completion {
+                name: name.to_string(),
+                kind,
+                ty,
+            });
+        }
+        completions
+    }
+
+    /// Return whatever binds the variable at the given byte offset, which is either an enclosing
+    /// abstraction, a definition or macro, or an import, or `None` if there is no variable there.
+    #[must_use]
+    pub fn definition_at(&self, offset: usize) -> Option<Binding> {
+        let (part, term) = self.part_at(offset)?;
+        let mut binders = Vec::new();
+        let SurfaceTerm::Variable { name, .. } = innermost(term, offset, &mut binders) else {
+            return None;
+        };
+
+        // Walking back down to the variable again, the last abstraction passed binding its name
+        // is the one which binds it.
+        let mut binder = None;
+        let mut term = term;
+        loop {
+            match term {
+                SurfaceTerm::Variable { .. } => break,
+                SurfaceTerm::Abstraction {
+                    variable,
+                    body,
+                    span,
+                    ..
+                } => {
+                    if variable == name {
+                        binder = Some(*span);
+                    }
+                    term = body;
+                }
+                SurfaceTerm::Application {
+                    function, argument, ..
+                } => {
+                    term = if function.span().contains(offset) {
+                        function
+                    } else {
+                        argument
+                    };
+                }
+            }
+        }
+
+        binder
+            .map(Binding::Local)
+            .or_else(|| self.binding(part, name))
+    }
+
+    /// Return whatever binds the given name at the end of the program, where its term would see
+    /// it, which is a definition, a macro, or an import, or `None` if nothing does.
+    #[must_use]
+    pub fn binding_of(&self, name: &str) -> Option<Binding> {
+        self.binding(Part::Term, name)
+    }
+
+    /// Return whichever of the definitions, macros and imports in scope in the given part of the
+    /// program which bind the given name comes last.
+    fn binding(&self, part: Part, name: &str) -> Option<Binding> {
+        let definition = self
+            .in_scope(part)
+            .rev()
+            .map(|(i, _)| &self.program.definitions[*i])
+            .find(|d| d.name == name)
+            .map(|d| Binding::Local(d.span));
+        let r#macro = self
+            .macros_in_scope(part)
+            .rev()
+            .map(|(i, _)| &self.program.macros[*i])
+            .find(|m| m.name == name)
+            .map(|m| Binding::Local(m.span));
+        let import = self
+            .imports_in_scope(part)
+            .rev()
+            .find(|(_, environment)| environment.lookup(name).is_some())
+            .map(|(i, _)| {
+                let import = &self.program.imports[*i];
+                Binding::Imported {
+                    span: import.span,
+                    path: import.path.clone(),
+                    name: name.to_string(),
+                }
+            });
+        definition
+            .into_iter()
+            .chain(r#macro)
+            .chain(import)
+            .max_by_key(|binding| match binding {
+                Binding::Local(span) | Binding::Imported { span, .. } => span.start,
+            })
+    }
+
+    /// Return the part of the program containing the given byte offset, and its term.
+    fn part_at(&self, offset: usize) -> Option<(Part, &SurfaceTerm)> {
+        self.program
+            .definitions
+            .iter()
+            .enumerate()
+            .find(|(_, d)| d.span.contains(offset))
+            .map(|(i, d)| (Part::Definition(i), &d.term))
+            .or_else(|| {
+                self.program
+                    .macros
+                    .iter()
+                    .enumerate()
+                    .find(|(_, m)| m.span.contains(offset))
+                    .map(|(i, m)| (Part::Macro(i), &m.body))
+            })
+            .or_else(|| {
+                self.program
+                    .term
+                    .as_ref()
+                    .filter(|t| t.span().contains(offset))
+                    .map(|t| (Part::Term, t))
+            })
+    }
+
+    /// Return the byte offset at which the given part of the program starts.
+    fn start_of(&self, part: Part) -> usize {
+        match part {
+            Part::Definition(i) => self.program.definitions[i].span.start,
+            Part::Macro(i) => self.program.macros[i].span.start,
+            Part::Import(i) => self.program.imports[i].span.start,
+            Part::Term => self
+                .program
+                .term
+                .as_ref()
+                .map_or(usize::MAX, |t| t.span().start),
+        }
+    }
+
+    /// Return the accepted definitions which are in scope in the given part of the program.
+    fn in_scope(&self, part: Part) -> impl DoubleEndedIterator<Item = &(usize, LambdaTerm)> {
+        let start = self.start_of(part);
+        self.accepted
+            .iter()
+            .filter(move |(i, _)| self.program.definitions[*i].span.start < start)
+    }
+
+    /// Return the accepted macros which are in scope in the given part of the program.
+    fn macros_in_scope(&self, part: Part) -> impl DoubleEndedIterator<Item = &(usize, Macro)> {
+        let start = self.start_of(part);
+        self.accepted_macros
+            .iter()
+            .filter(move |(i, _)| self.program.macros[*i].span.start < start)
+    }
+
+    /// Return the modules imported before the given part of the program.
+    fn imports_in_scope(
+        &self,
+        part: Part,
+    ) -> impl DoubleEndedIterator<Item = &(usize, Environment)> {
+        let start = self.start_of(part);
+        self.imported
+            .iter()
+            .filter(move |(i, _)| self.program.imports[*i].span.start < start)
+    }
+
+    /// Look up the given name in the given part of the program, as whichever of the definitions,
+    /// macros and imports in scope with that name comes last.
+    fn lookup(&self, part: Part, name: &str) -> Option<Macro> {
+        self.lookup_latest(part, name).map(|(_, r#macro)| r#macro)
+    }
+
+    /// Look up the given name just as `lookup` does, returning the byte offset at which whatever
+    /// it refers to starts along with it.
+    fn lookup_latest(&self, part: Part, name: &str) -> Option<(usize, Macro)> {
+        let definition = self
+            .in_scope(part)
+            .rev()
+            .find(|(i, _)| self.program.definitions[*i].name == name)
+            .map(|(i, term)| {
+                let r#macro = Macro {
+                    parameters: 0,
+                    context: 0,
+                    body: term.clone(),
+                };
+                (self.program.definitions[*i].span.start, r#macro)
+            });
+        let r#macro = self
+            .macros_in_scope(part)
+            .rev()
+            .find(|(i, _)| self.program.macros[*i].name == name)
+            .map(|(i, r#macro)| (self.program.macros[*i].span.start, r#macro.clone()));
+        let imported = self
+            .imports_in_scope(part)
+            .rev()
+            .find_map(|(i, environment)| {
+                let r#macro = environment.lookup(name)?;
+                Some((self.program.imports[*i].span.start, r#macro))
+            });
+        definition
+            .into_iter()
+            .chain(r#macro)
+            .chain(imported)
+            .max_by_key(|(start, _)| *start)
+    }
+
+    /// Resolve a subterm of the given part of the program, found under the given binders,
+    /// outermost first, against the definitions in scope there, closing it over its binders.
+    fn resolve(
+        &self,
+        part: Part,
+        subterm: &SurfaceTerm,
+        binders: &[(&str, &Type)],
+    ) -> Result<LambdaTerm, ScopeError> {
+        wrap(subterm, binders).to_core_with_macros(&[], &|name| self.lookup(part, name))
+    }
+
+    /// Return the type of a subterm of the given part of the program, found under the given
+    /// binders, outermost first, or `None` if it refers to a variable which is not in scope.
+    fn type_in_scope(
+        &self,
+        part: Part,
+        subterm: &SurfaceTerm,
+        binders: &[(&str, &Type)],
+    ) -> Option<Result<Type, TypeError>> {
+        let term = self.resolve(part, subterm, binders).ok()?;
+        Some(term.get_type().map(|typed| {
+            // The type of the subterm is what is left of the type of the wrapper once the
+            // argument types of its abstractions are removed.
+            let mut ty = typed.into_type();
+            for _ in binders {
+                let Type::FunctionType(_, return_type) = ty else {
+                    unreachable!("the wrapper should have a function type for every binder");
+                };
+                ty = *return_type;
+            }
+            ty
+        }))
+    }
+
+    /// Return the type which a subterm of the given part of the program, found under the given
+    /// binders, outermost first, would have if each error in it were fixed where it is, or `None`
+    /// if it cannot be known. Names which are neither bound nor defined have the error type.
+    fn partial_type_in_scope(
+        &self,
+        part: Part,
+        subterm: &SurfaceTerm,
+        binders: &[(&str, &Type)],
+    ) -> Option<Type> {
+        // Invalid definitions have no terms to substitute for them, so each stands for a variable
+        // bound outside the subterm instead, of the type it would have had, after a first one
+        // of the error type which stands for every name which is not defined at all.
+        let start = self.start_of(part);
+        let failed: Vec<_> = self
+            .failed
+            .iter()
+            .filter(|(i, _)| self.program.definitions[*i].span.start < start)
+            .collect();
+        let holes: Vec<_> = core::iter::once(None)
+            .chain(failed.iter().map(|(_, ty)| ty.clone()))
+            .collect();
+        let hole = |h: usize| Macro {
+            parameters: 0,
+            context: holes.len(),
+            body: LambdaTerm::Variable {
+                idx: (holes.len() - h - 1) as u64,
+            },
+        };
+        let lookup = |name: &str| {
+            let invalid = failed
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(h, (i, _))| (h, &self.program.definitions[*i]))
+                .find(|(_, d)| d.name == name)
+                .map(|(h, d)| (d.span.start, hole(h + 1)));
+            self.lookup_latest(part, name)
+                .into_iter()
+                .chain(invalid)
+                .max_by_key(|(start, _)| *start)
+                .map_or_else(|| hole(0), |(_, r#macro)| r#macro)
+        };
+
+        let term = wrap(subterm, binders)
+            .to_core_with_macros(&alloc::vec![String::new(); holes.len()], &|name| {
+                Some(lookup(name))
+            })
+            .ok()?;
+        let mut ty = term.get_partial_type(&holes)?;
+        for _ in binders {
+            let Type::FunctionType(_, return_type) = ty else {
+                unreachable!("the wrapper should have a function type for every binder");
+            };
+            ty = *return_type;
+        }
+        Some(ty)
+    }
+
+    /// Check the term of the given part of the program, recording a diagnostic and returning
+    /// `None` if it is invalid, or returning its `LambdaTerm` otherwise.
+    fn check(&mut self, part: Part, term: &SurfaceTerm) -> Option<LambdaTerm> {
+        let lambda_term = match self.resolve(part, term, &[]) {
+            Ok(lambda_term) => lambda_term,
+            Err(e) => {
+                self.diagnostics.push(Diagnostic {
+                    span: e.span(),
+                    message: e.to_string(),
+                });
+                return None;
+            }
+        };
+        if lambda_term.get_type().is_ok() {
+            return Some(lambda_term);
+        }
+
+        // The type checker only reports the terms involved in an error, not where they came
+        // from, so the error is attributed to the innermost ill-typed subterm instead, which is
+        // the first to fail when subterms are checked bottom up.
+        let mut subterms = Vec::new();
+        postorder(term, &mut Vec::new(), &mut subterms);
+        let diagnostic = subterms
+            .into_iter()
+            .find_map(
+                |(subterm, binders)| match self.type_in_scope(part, subterm, &binders) {
+                    Some(Err(e)) => Some(Diagnostic {
+                        span: subterm.span(),
+                        message: describe(subterm, &e),
+                    }),
+                    _ => None,
+                },
+            )
+            .expect("some subterm of an ill-typed term should be ill-typed");
+        self.diagnostics.push(diagnostic);
+        None
+    }
+}
+
+impl Diagnostic {
+    fn from_parse_error(e: &ParseError) -> Self {
+        Self {
+            span: e.span(),
+            message: e.message(),
+        }
+    }
+}
+
+/// Describe the type error found in the given subterm, which is not well-typed although all of
+/// its own subterms are.
+///
+/// The terms in a `TypeError` are written as the type checker saw them, with the enclosing
+/// binders stripped away, so the terms are taken from the source instead, where they have names.
+fn describe(subterm: &SurfaceTerm, e: &TypeError) -> String {
+    match (subterm, e) {
+        (
+            SurfaceTerm::Application {
+                function, argument, ..
+            },
+            TypeError::InvalidApplication {
+                function_type,
+                argument_type,
+                ..
+            },
+        ) => format!(
+            "attempted to apply term ({function}):{function_type} to term \
+             ({argument}):{argument_type}"
+        ),
+        _ => e.to_string(),
+    }
+}
+
+/// Return the given subterm, found under the given binders, outermost first, closed over them by
+/// wrapping it in an abstraction for each of them, so that the result can be type checked in
+/// exactly the scope which the subterm sees.
+fn wrap(subterm: &SurfaceTerm, binders: &[(&str, &Type)]) -> SurfaceTerm {
+    binders
+        .iter()
+        .rev()
+        .fold(subterm.clone(), |body, (variable, argument_type)| {
+            SurfaceTerm::Abstraction {
+                variable: (*variable).to_string(),
+                argument_type: (*argument_type).clone(),
+                body: Box::new(body),
+                span: Span::default(),
+            }
+        })
+}
+
+/// Return the innermost subterm of `term` containing the given byte offset, pushing the variable
+/// and type of every abstraction passed on the way onto `binders`.
+fn innermost<'a>(
+    term: &'a SurfaceTerm,
+    offset: usize,
+    binders: &mut Vec<(&'a str, &'a Type)>,
+) -> &'a SurfaceTerm {
+    let mut term = term;
+    loop {
+        match term {
+            SurfaceTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+                ..
+            } if body.span().contains(offset) => {
+                binders.push((variable, argument_type));
+                term = body;
+            }
+            SurfaceTerm::Application { function, .. } if function.span().contains(offset) => {
+                term = function;
+            }
+            SurfaceTerm::Application { argument, .. } if argument.span().contains(offset) => {
+                term = argument;
+            }
+            _ => return term,
+        }
+    }
+}
+
+/// Push every subterm of `term` onto `subterms` in post-order, each with the binders enclosing
+/// it, outermost first.
+fn postorder<'a>(
+    term: &'a SurfaceTerm,
+    binders: &mut Vec<(&'a str, &'a Type)>,
+    subterms: &mut Vec<(&'a SurfaceTerm, Vec<(&'a str, &'a Type)>)>,
+) {
+    match term {
+        SurfaceTerm::Variable { .. } => {}
+        SurfaceTerm::Abstraction {
+            variable,
+            argument_type,
+            body,
+            ..
+        } => {
+            binders.push((variable, argument_type));
+            postorder(body, binders, subterms);
+            binders.pop();
+        }
+        SurfaceTerm::Application {
+            function, argument, ..
+        } => {
+            postorder(function, binders, subterms);
+            postorder(argument, binders, subterms);
+        }
+    }
+    subterms.push((term, binders.clone()));
+}
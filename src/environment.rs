@@ -0,0 +1,678 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::ptr;
+
+use crate::parse::{LambdaTerm, ParseError, Type};
+use crate::reduce::Equivalence;
+use crate::store::Digest;
+use crate::surface::{
+    Assertion, Item, Macro, Program, ScopeError, Span, SurfaceAssertion, SurfaceDefinition,
+    SurfaceMacro, SurfaceTerm,
+};
+#[cfg(feature = "serde")]
+use crate::table::TermTable;
+use crate::type_check::TypeError;
+
+/// A name standing for a closed, well-typed term.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition {
+    pub name: String,
+    /// The text of the documentation comments preceding the definition, as in
+    /// `SurfaceDefinition::doc`, or the empty string if there are none.
+    pub doc: String,
+    pub term: LambdaTerm,
+    pub ty: Type,
+}
+
+#[derive(Debug)]
+pub enum EnvironmentError {
+    /// The source could not be parsed, or referred to a name which is neither bound nor defined.
+    Parse(ParseError),
+    /// The term given for a definition is not well-typed.
+    IllTyped { name: String, error: TypeError },
+    /// The term being evaluated is not well-typed.
+    Type(TypeError),
+    /// The term given for a definition contains free variables.
+    Open { name: String },
+    /// The module named by an `import` directive could not be loaded, for the given reason.
+    Import { path: String, message: String },
+}
+
+impl Display for EnvironmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => e.fmt(f),
+            Self::IllTyped { name, error } => {
+                write!(f, "definition of {name} is not well-typed: {error}")
+            }
+            Self::Type(error) => write!(f, "term is not well-typed: {error}"),
+            Self::Open { name } => write!(f, "definition of {name} contains free variables"),
+            Self::Import { path, message } => write!(f, "unable to import {path}: {message}"),
+        }
+    }
+}
+
+impl Error for EnvironmentError {}
+
+impl From<ParseError> for EnvironmentError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// The reason an `assert` directive does not hold.
+#[derive(Debug)]
+pub enum AssertionFailure {
+    /// The terms claimed to be equivalent are not, and have the given normal forms.
+    NotEquivalent { left: LambdaTerm, right: LambdaTerm },
+    /// The term claimed to have the type `expected` has the type `actual` instead.
+    WrongType { expected: Type, actual: Type },
+    /// A term in the assertion is not well-typed.
+    IllTyped(TypeError),
+}
+
+impl Display for AssertionFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEquivalent { left, right } => {
+                write!(f, "{left} is not equivalent to {right}")
+            }
+            Self::WrongType { expected, actual } => {
+                write!(f, "term has type {actual}, but {expected} was asserted")
+            }
+            Self::IllTyped(error) => write!(f, "term is not well-typed: {error}"),
+        }
+    }
+}
+
+/// The result of loading a program and checking its assertions.
+#[derive(Debug)]
+pub struct CheckedProgram {
+    /// The term of the program, if it has one, resolved against the environment.
+    pub term: Option<LambdaTerm>,
+    /// The number of assertions which were checked.
+    pub assertions: usize,
+    /// The span and reason of every assertion which does not hold, in order.
+    pub failures: Vec<(Span, AssertionFailure)>,
+}
+
+/// Push every name which `name_of` gives for some subterm of the given term onto `names`, without
+/// looking inside the subterms it gives names for.
+fn referred(
+    term: &LambdaTerm,
+    name_of: &dyn Fn(&LambdaTerm) -> Option<String>,
+    names: &mut Vec<String>,
+) {
+    if let Some(name) = name_of(term) {
+        names.push(name);
+        return;
+    }
+    match term {
+        LambdaTerm::Variable { .. } => {}
+        LambdaTerm::Abstraction { body, .. } => referred(body, name_of, names),
+        LambdaTerm::Application { function, argument } => {
+            referred(function, name_of, names);
+            referred(argument, name_of, names);
+        }
+    }
+}
+
+/// A source of the modules named by `import` directives.
+pub trait Importer {
+    /// Return the environment holding every definition and macro of the module at the given
+    /// path, exactly as it was written in the directive, or a description of why it cannot be
+    /// loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the module does not exist or is invalid.
+    fn import(&mut self, path: &str) -> Result<Environment, String>;
+}
+
+/// The `Importer` used where no modules are available, which refuses every import.
+pub(crate) struct NoImports;
+
+impl Importer for NoImports {
+    fn import(&mut self, _: &str) -> Result<Environment, String> {
+        Err(String::from("modules cannot be imported here"))
+    }
+}
+
+/// A collection of definitions, against which terms may be parsed and evaluated.
+///
+/// Every definition is checked when it is added, so any term produced by the environment is built
+/// only out of closed, well-typed pieces. Definitions are substituted into terms wherever they
+/// are referred to, so a term parsed against an environment does not depend on it afterwards.
+///
+/// Since a definition can only refer to those added before it, and there is no fixpoint
+/// combinator, no definition can be recursive. Every well-typed term of the simply typed lambda
+/// calculus has a normal form, so every definition is total, and none need be checked for
+/// termination: a definition which type checks is a proof of its type.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SharedEnvironment", try_from = "SharedEnvironment")
+)]
+pub struct Environment {
+    definitions: Vec<Definition>,
+    indices: BTreeMap<String, usize>,
+    /// The macros in the environment, each of which hides any definition of the same name made
+    /// before it.
+    macros: BTreeMap<String, Macro>,
+}
+
+/// An `Environment` as it is serialized, with every term in it written in a single `TermTable`,
+/// and referred to by its position there.
+///
+/// Each definition shares the terms of those it refers to, so written out one by one, as trees,
+/// the terms of an environment could be exponentially larger than the program defining them.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SharedEnvironment {
+    terms: TermTable,
+    /// The name, documentation, term and type of each definition.
+    definitions: Vec<(String, String, usize, Type)>,
+    indices: BTreeMap<String, usize>,
+    /// The number of parameters, context and body of each macro.
+    macros: BTreeMap<String, (usize, usize, usize)>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Environment> for SharedEnvironment {
+    fn from(environment: Environment) -> Self {
+        let (terms, positions) = TermTable::new(
+            environment
+                .definitions
+                .iter()
+                .map(|d| &d.term)
+                .chain(environment.macros.values().map(|m| &m.body)),
+        );
+        let (definitions, macros) = positions.split_at(environment.definitions.len());
+        Self {
+            definitions: environment
+                .definitions
+                .into_iter()
+                .zip(definitions)
+                .map(|(d, term)| (d.name, d.doc, *term, d.ty))
+                .collect(),
+            indices: environment.indices,
+            macros: environment
+                .macros
+                .into_iter()
+                .zip(macros)
+                .map(|((name, m), body)| (name, (m.parameters, m.context, *body)))
+                .collect(),
+            terms,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SharedEnvironment> for Environment {
+    type Error = String;
+
+    fn try_from(shared: SharedEnvironment) -> Result<Self, Self::Error> {
+        if let Some(i) = shared
+            .indices
+            .values()
+            .find(|i| **i >= shared.definitions.len())
+        {
+            return Err(format!("there is no definition {i}"));
+        }
+        let positions: Vec<_> = shared
+            .definitions
+            .iter()
+            .map(|(_, _, term, _)| *term)
+            .chain(shared.macros.values().map(|(_, _, body)| *body))
+            .collect();
+        let mut terms = shared
+            .terms
+            .terms(&positions)
+            .map_err(|e| e.to_string())?
+            .into_iter();
+        let definitions = shared
+            .definitions
+            .into_iter()
+            .zip(terms.by_ref())
+            .map(|((name, doc, _, ty), term)| Definition {
+                name,
+                doc,
+                term,
+                ty,
+            })
+            .collect();
+        let macros = shared
+            .macros
+            .into_iter()
+            .zip(terms)
+            .map(|((name, (parameters, context, _)), body)| {
+                let r#macro = Macro {
+                    parameters,
+                    context,
+                    body,
+                };
+                (name, r#macro)
+            })
+            .collect();
+        Ok(Self {
+            definitions,
+            indices: shared.indices,
+            macros,
+        })
+    }
+}
+
+impl Environment {
+    /// Create a new, empty `Environment`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the definition with the given name, if there is one.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Definition> {
+        self.indices.get(name).map(|i| &self.definitions[*i])
+    }
+
+    /// Return the macro with the given name, if there is one.
+    #[must_use]
+    pub fn get_macro(&self, name: &str) -> Option<&Macro> {
+        self.macros.get(name)
+    }
+
+    /// Return an iterator over every definition, in the order in which they were first defined.
+    pub fn iter(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.iter()
+    }
+
+    /// Return an iterator over every macro, along with its name, in alphabetical order.
+    pub fn macros(&self) -> impl Iterator<Item = (&str, &Macro)> {
+        self.macros
+            .iter()
+            .map(|(name, r#macro)| (name.as_str(), r#macro))
+    }
+
+    /// Return a program which, loaded into an empty environment, gives the same definitions and
+    /// macros as this one: every definition which is not hidden by a macro, with its
+    /// documentation, in the order in which they were first defined, then every macro, with its
+    /// parameters named `x1`, `x2`, and so on.
+    ///
+    /// Wherever some subterm of a definition is α-equivalent to the term of an earlier one, the
+    /// name of the last such definition is written instead, so that definitions which refer to
+    /// those before them are written as they were made, rather than with every name expanded.
+    #[must_use]
+    pub fn to_program(&self) -> Program {
+        let mut written = BTreeMap::<Digest, &str>::new();
+        let mut definitions = Vec::new();
+        for definition in self
+            .definitions
+            .iter()
+            .filter(|d| !self.macros.contains_key(&d.name))
+        {
+            let digests = definition.term.digests();
+            let name_of = |term: &LambdaTerm| {
+                let name = written.get(&digests[&ptr::from_ref(term)])?;
+                Some((*name).to_string())
+            };
+            // NOTE: Only abstractions around the names actually referred to need to be renamed
+            // to keep them from being captured, so those names are found first.
+            let mut names = Vec::new();
+            referred(&definition.term, &name_of, &mut names);
+            let term = SurfaceTerm::from_core_referring(&definition.term, &name_of, &names);
+            definitions.push(SurfaceDefinition {
+                doc: definition.doc.clone(),
+                name: definition.name.clone(),
+                term,
+                span: Span::default(),
+            });
+            written.insert(digests[&ptr::from_ref(&definition.term)], &definition.name);
+        }
+        let macros = self
+            .macros
+            .iter()
+            .map(|(name, r#macro)| {
+                let parameters: Vec<_> =
+                    (1..=r#macro.parameters).map(|i| format!("x{i}")).collect();
+                SurfaceMacro {
+                    name: name.clone(),
+                    body: SurfaceTerm::from_core_with(&r#macro.body, &parameters),
+                    parameters,
+                    span: Span::default(),
+                }
+            })
+            .collect();
+        Program {
+            definitions,
+            macros,
+            ..Program::default()
+        }
+    }
+
+    /// Return the number of definitions in the environment.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    /// Return whether the environment contains no definitions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Define `name` to stand for `term`, replacing any existing definition or macro with the
+    /// same name, and return the new definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if `term` contains free variables or is not well-typed.
+    pub fn define(
+        &mut self,
+        name: &str,
+        term: LambdaTerm,
+    ) -> Result<&Definition, EnvironmentError> {
+        self.define_documented(name, "", term)
+    }
+
+    /// Define `name` to stand for `term`, just as `define` does, with the given documentation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if `term` contains free variables or is not well-typed.
+    pub fn define_documented(
+        &mut self,
+        name: &str,
+        doc: &str,
+        term: LambdaTerm,
+    ) -> Result<&Definition, EnvironmentError> {
+        if !term.is_closed() {
+            return Err(EnvironmentError::Open {
+                name: name.to_string(),
+            });
+        }
+        let ty = term.type_of().map_err(|error| EnvironmentError::IllTyped {
+            name: name.to_string(),
+            error,
+        })?;
+
+        Ok(self.insert(Definition {
+            name: name.to_string(),
+            doc: doc.to_string(),
+            term,
+            ty,
+        }))
+    }
+
+    /// Add the given definition, which has already been checked, replacing any existing
+    /// definition or macro with the same name.
+    fn insert(&mut self, definition: Definition) -> &Definition {
+        self.macros.remove(&definition.name);
+        let i = if let Some(i) = self.indices.get(&definition.name) {
+            self.definitions[*i] = definition;
+            *i
+        } else {
+            self.indices
+                .insert(definition.name.clone(), self.definitions.len());
+            self.definitions.push(definition);
+            self.definitions.len() - 1
+        };
+        &self.definitions[i]
+    }
+
+    /// Add every definition and macro of `other` to the environment, replacing any with the same
+    /// names, just as if they had been defined here in the order they were defined there.
+    pub fn import(&mut self, other: &Environment) {
+        for definition in &other.definitions {
+            self.insert(definition.clone());
+        }
+        // NOTE: A macro in `other` only coexists with a definition of the same name if it was
+        // defined after it, so adding every macro after every definition keeps it in front.
+        for (name, r#macro) in &other.macros {
+            self.macros.insert(name.clone(), r#macro.clone());
+        }
+    }
+
+    /// Define `name` as a macro with the given parameters and body, which is resolved against
+    /// the environment as it is now, replacing any existing definition or macro with the same
+    /// name, and return the new macro.
+    ///
+    /// The body need not be well-typed, nor even closed once its parameters are substituted, as
+    /// it is only checked wherever it is expanded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ScopeError` if some variable in the body is neither a parameter, bound, nor
+    /// defined.
+    pub fn define_macro(
+        &mut self,
+        name: &str,
+        parameters: &[String],
+        body: &SurfaceTerm,
+    ) -> Result<&Macro, ScopeError> {
+        let r#macro = Macro {
+            parameters: parameters.len(),
+            context: 0,
+            body: body.to_core_with_macros(parameters, &|name| self.lookup(name))?,
+        };
+        // NOTE: Definitions cannot be removed without disturbing the order of the rest, so a
+        // macro hides a definition of the same name instead, which is looked up only after it.
+        self.macros.insert(name.to_string(), r#macro);
+        Ok(&self.macros[name])
+    }
+
+    /// Look up the given name in the environment, as a macro, or a definition, which is a macro
+    /// with no parameters.
+    pub(crate) fn lookup(&self, name: &str) -> Option<Macro> {
+        self.macros.get(name).cloned().or_else(|| {
+            self.get(name).map(|d| Macro {
+                parameters: 0,
+                context: 0,
+                body: d.term.clone(),
+            })
+        })
+    }
+
+    /// Resolve the given `SurfaceTerm` against the environment, replacing every name which is not
+    /// bound by an abstraction with its definition, and expanding every macro.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ScopeError` if some variable is neither bound nor defined, or some macro is
+    /// applied to too few arguments.
+    pub fn resolve(&self, term: &SurfaceTerm) -> Result<LambdaTerm, ScopeError> {
+        term.to_core_with_macros(&[], &|name| self.lookup(name))
+    }
+
+    /// Parse a term from the given string, resolving it against the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ParseError` if the string is not a valid term, or refers to a name which is
+    /// neither bound nor defined.
+    pub fn parse(&self, string: &str) -> Result<LambdaTerm, ParseError> {
+        let term = string.parse::<SurfaceTerm>()?;
+        self.resolve(&term)
+            .map_err(|e| ParseError::new(e.to_string(), e.span(), string))
+    }
+
+    /// Parse a whole program from the given string, adding each of its definitions to the
+    /// environment in turn, and return its term, if it has one, resolved against the
+    /// environment. Its assertions are not checked; see `load_checked`, and it may not import
+    /// any modules; see `load_with`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if the string is not a valid program or any of its
+    /// definitions are invalid. Definitions preceding the invalid one are still added.
+    pub fn load(&mut self, string: &str) -> Result<Option<LambdaTerm>, EnvironmentError> {
+        self.load_with(string, None, &mut NoImports)
+            .map(|program| program.term)
+    }
+
+    /// Load a whole program from the given string, just as `load` does, but also check each of
+    /// its assertions, up to the given `Equivalence`, against the definitions preceding it.
+    ///
+    /// Assertions which do not hold do not stop the program from being loaded: every one of them
+    /// is reported in the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if the string is not a valid program or any of its
+    /// definitions are invalid, including if an assertion refers to a name which is neither
+    /// bound nor defined where it appears.
+    pub fn load_checked(
+        &mut self,
+        string: &str,
+        equivalence: Equivalence,
+    ) -> Result<CheckedProgram, EnvironmentError> {
+        self.load_with(string, Some(equivalence), &mut NoImports)
+    }
+
+    /// Load a whole program from the given string, checking its assertions if an `Equivalence` is
+    /// given, and adding the definitions and macros of every module it imports, as given by
+    /// `importer`, where the `import` directive appears.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if the string is not a valid program, any of its definitions
+    /// are invalid, or any of the modules it imports cannot be loaded.
+    pub fn load_with(
+        &mut self,
+        string: &str,
+        equivalence: Option<Equivalence>,
+        importer: &mut dyn Importer,
+    ) -> Result<CheckedProgram, EnvironmentError> {
+        let program = string.parse::<Program>()?;
+        let to_parse_error = |e: ScopeError| ParseError::new(e.to_string(), e.span(), string);
+        let mut checked = CheckedProgram {
+            term: None,
+            assertions: 0,
+            failures: Vec::new(),
+        };
+
+        // Each assertion is checked where it appears, so that it sees every name as it was
+        // defined at that point in the program.
+        for item in program.items() {
+            match item {
+                Item::Definition(definition) => {
+                    let term = self.resolve(&definition.term).map_err(to_parse_error)?;
+                    self.define_documented(&definition.name, &definition.doc, term)?;
+                }
+                Item::Macro(r#macro) => {
+                    self.define_macro(&r#macro.name, &r#macro.parameters, &r#macro.body)
+                        .map_err(to_parse_error)?;
+                }
+                Item::Import(import) => {
+                    let module = importer.import(&import.path).map_err(|message| {
+                        EnvironmentError::Import {
+                            path: import.path.clone(),
+                            message,
+                        }
+                    })?;
+                    self.import(&module);
+                }
+                Item::Assertion(assertion) => {
+                    let Some(equivalence) = equivalence else {
+                        continue;
+                    };
+                    checked.assertions += 1;
+                    if let Err(failure) =
+                        self.check(assertion, equivalence).map_err(to_parse_error)?
+                    {
+                        checked.failures.push((assertion.span, failure));
+                    }
+                }
+            }
+        }
+
+        checked.term = program
+            .term
+            .map(|t| self.resolve(&t).map_err(to_parse_error))
+            .transpose()?;
+        Ok(checked)
+    }
+
+    /// Check the given assertion against the environment, up to the given `Equivalence`.
+    fn check(
+        &self,
+        assertion: &SurfaceAssertion,
+        equivalence: Equivalence,
+    ) -> Result<Result<(), AssertionFailure>, ScopeError> {
+        Ok(match &assertion.assertion {
+            Assertion::Equivalent { left, right } => {
+                let (left, right) = (self.resolve(left)?, self.resolve(right)?);
+                if let Err(error) = left.type_of().and_then(|_| right.type_of()) {
+                    Err(AssertionFailure::IllTyped(error))
+                } else if left.is_equivalent(&right, equivalence) {
+                    Ok(())
+                } else {
+                    Err(AssertionFailure::NotEquivalent {
+                        left: left.normal_form(equivalence),
+                        right: right.normal_form(equivalence),
+                    })
+                }
+            }
+            Assertion::HasType { term, ty } => match self.resolve(term)?.type_of() {
+                Ok(actual) if actual == *ty => Ok(()),
+                Ok(actual) => Err(AssertionFailure::WrongType {
+                    expected: ty.clone(),
+                    actual,
+                }),
+                Err(error) => Err(AssertionFailure::IllTyped(error)),
+            },
+        })
+    }
+
+    /// Parse a term from the given string, resolving it against the environment, then type check
+    /// and evaluate it, returning the result along with its type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if the string is not a valid term, or the term is not
+    /// well-typed.
+    pub fn evaluate(&self, string: &str) -> Result<(LambdaTerm, Type), EnvironmentError> {
+        let term = self.parse(string)?;
+        let ty = term.type_of().map_err(EnvironmentError::Type)?;
+        Ok((term.beta_reduce(), ty))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::Environment;
+    use crate::print::DisplayOptions;
+
+    #[test]
+    fn program_refers_to_earlier_definitions() {
+        let source = "/// The identity.\nlet a = \\x:A. x;\nlet b = \\z:A. a (a z);\n\
+                      let c = \\a:A. (\\x:A. x) a;\nlet d = (\\f:A->A. f) c;\n";
+        let mut environment = Environment::new();
+        environment.load(source).unwrap();
+
+        let program = environment.to_program();
+        let written = program.fmt_with(DisplayOptions::default()).to_string();
+        assert_eq!(
+            program.definitions[0].doc,
+            environment.get("a").unwrap().doc
+        );
+        assert!(written.contains("let c = λa1:A. a a1;"), "{written}");
+        assert!(written.contains("let d = (λf:A→A. f) c;"), "{written}");
+
+        let mut reloaded = Environment::new();
+        reloaded.load(&written).unwrap();
+        for definition in environment.iter() {
+            let again = reloaded.get(&definition.name).unwrap();
+            assert_eq!(again.term, definition.term, "{}", definition.name);
+            assert_eq!(again.doc, definition.doc, "{}", definition.name);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Explanations of evaluation, which describe each β-step in terms of the program it came from:
+//! which abstraction was applied, and where it was written, what was substituted for its
+//! variable, and what became of the redex, leaving out the rest of the term.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::environment::Environment;
+use crate::parse::LambdaTerm;
+use crate::profile::{split_tag, untag};
+
+/// A description of a single β-step.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// The name of the variable of the abstraction applied.
+    pub variable: String,
+    /// The name of the definition in which the abstraction was written, or `None` if it was
+    /// written in the term itself.
+    pub definition: Option<String>,
+    /// The name of the definition whose term the argument is, if there is one.
+    pub argument: Option<String>,
+    /// The number of occurrences of the variable which the argument was substituted for.
+    pub uses: usize,
+    /// The redex contracted.
+    pub before: LambdaTerm,
+    /// The term which the redex was contracted to.
+    pub after: LambdaTerm,
+}
+
+impl Environment {
+    /// Apply β-reduction to the given well-typed term one step at a time, following exactly the
+    /// same strategy as `LambdaTerm::beta_reduce`, and return the result along with an
+    /// `Explanation` of every step, in terms of the definitions of the environment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path to a redex found by `next_redex` does not lead to one, which should
+    /// never happen.
+    #[must_use]
+    pub fn explain(&self, term: &LambdaTerm) -> (LambdaTerm, Vec<Explanation>) {
+        let mut term = self.tag(term);
+        let mut explanations = Vec::new();
+        while let Some(path) = term.next_redex() {
+            let mut redex = &term;
+            for _ in &path {
+                let LambdaTerm::Application { function, .. } = redex else {
+                    panic!("the path should only lead through functions");
+                };
+                redex = function;
+            }
+            let LambdaTerm::Application { function, argument } = redex else {
+                panic!("the path should lead to a redex");
+            };
+            let LambdaTerm::Abstraction { variable, body, .. } = function.as_ref() else {
+                panic!("the path should lead to a redex");
+            };
+            let (variable, definition) = split_tag(variable);
+            // NOTE: Terms are compared up to α-equivalence, so the tags make no difference.
+            let named = self.iter().filter(|d| d.term == **argument).last();
+            let after = redex.contract().expect("the path should lead to a redex");
+            explanations.push(Explanation {
+                variable: variable.to_string(),
+                definition: definition.map(ToString::to_string),
+                argument: named.map(|d| d.name.clone()),
+                uses: body.uses_of(0),
+                before: untag(redex),
+                after: untag(&after),
+            });
+            term = term
+                .contract_at(&path)
+                .expect("the path should lead to a redex");
+        }
+        (untag(&term), explanations)
+    }
+}
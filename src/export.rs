@@ -0,0 +1,198 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::{LambdaTerm, Type};
+use crate::pretty::Doc;
+
+/// A proof assistant into whose syntax terms can be exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Assistant {
+    Coq,
+    Agda,
+    Lean,
+}
+
+impl Assistant {
+    fn arrow(self) -> &'static str {
+        match self {
+            Assistant::Coq => "->",
+            Assistant::Agda | Assistant::Lean => "→",
+        }
+    }
+
+    /// Return the text introducing an abstraction binding `variable` of type `argument_type`.
+    fn binder(self, variable: &str, argument_type: &Type) -> String {
+        let argument_type = ExportedType(argument_type, self);
+        match self {
+            Assistant::Coq | Assistant::Lean => format!("fun ({variable} : {argument_type}) =>"),
+            Assistant::Agda => format!("λ ({variable} : {argument_type}) →"),
+        }
+    }
+}
+
+/// A `Type` written in the syntax of an `Assistant`.
+struct ExportedType<'a>(&'a Type, Assistant);
+
+impl Display for ExportedType<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ExportedType(ty, assistant) = *self;
+        match ty {
+            Type::BaseType(name) => name.fmt(f),
+            Type::FunctionType(argument_type, return_type) => {
+                // The arrow associates to the right in every supported assistant, just as it
+                // does in kombi.
+                if let Type::FunctionType(..) = **argument_type {
+                    write!(f, "({})", ExportedType(argument_type, assistant))?;
+                } else {
+                    write!(f, "{}", ExportedType(argument_type, assistant))?;
+                }
+                write!(
+                    f,
+                    " {} {}",
+                    assistant.arrow(),
+                    ExportedType(return_type, assistant)
+                )
+            }
+        }
+    }
+}
+
+/// The number of columns by which broken lines are indented.
+const INDENT: usize = 2;
+
+impl LambdaTerm {
+    /// Build the `Doc` for the `LambdaTerm` in the syntax of the given `Assistant`, naming the
+    /// variable bound by every abstraction after its depth, as `to_canonical_string` does.
+    ///
+    /// Every supported assistant shares kombi's precedence rules, with application binding more
+    /// tightly than abstraction and associating to the left, but Coq does not allow an
+    /// abstraction to be the last argument of an application without parentheses, as kombi does,
+    /// so every compound argument is parenthesized.
+    fn to_exported_doc(&self, assistant: Assistant, depth: u64) -> Doc {
+        let parenthesized = |doc| Doc::concat(vec![Doc::text("("), doc, Doc::text(")")]);
+        match self {
+            LambdaTerm::Variable { idx } => Doc::text(format!("x{}", depth - idx - 1)),
+            LambdaTerm::Abstraction {
+                argument_type,
+                body,
+                ..
+            } => Doc::concat(vec![
+                Doc::text(assistant.binder(&format!("x{depth}"), argument_type)),
+                Doc::concat(vec![Doc::Line, body.to_exported_doc(assistant, depth + 1)])
+                    .nest(INDENT),
+            ])
+            .group(),
+            LambdaTerm::Application { .. } => {
+                let mut arguments = Vec::new();
+                let mut function = self;
+                while let LambdaTerm::Application {
+                    function: f,
+                    argument,
+                } = function
+                {
+                    arguments.push(argument.as_ref());
+                    function = f;
+                }
+
+                let mut docs = Vec::new();
+                for argument in arguments.into_iter().rev() {
+                    let doc = argument.to_exported_doc(assistant, depth);
+                    docs.push(Doc::Line);
+                    docs.push(if let LambdaTerm::Variable { .. } = argument {
+                        doc
+                    } else {
+                        parenthesized(doc)
+                    });
+                }
+
+                let doc = function.to_exported_doc(assistant, depth);
+                Doc::concat(vec![
+                    if let LambdaTerm::Abstraction { .. } = function {
+                        parenthesized(doc)
+                    } else {
+                        doc
+                    },
+                    Doc::concat(docs).nest(INDENT),
+                ])
+                .group()
+            }
+        }
+    }
+
+    /// Return a definition named `name` of the `LambdaTerm` with the given `Type`, written in the
+    /// syntax of the given `Assistant`, and broken across lines to fit within `width` columns
+    /// where possible.
+    ///
+    /// Every base type occurring in the term or its type becomes an implicit parameter of the
+    /// definition, of type `Type` in Coq and Lean and `Set` in Agda. The names of base types, and
+    /// `name`, are written as they are, so must also be valid identifiers in the target assistant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `LambdaTerm` is not closed.
+    #[must_use]
+    pub fn export(
+        &self,
+        ty: &Type,
+        name: &str,
+        assistant: Assistant,
+        width: Option<usize>,
+    ) -> String {
+        assert!(self.is_closed(), "only closed terms can be exported");
+
+        let mut base_types = self.base_types();
+        base_types.extend(ty.base_types());
+        let parameters = base_types
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ty = ExportedType(ty, assistant);
+
+        // Agda does not bring the implicit parameters in a signature into scope in the clause
+        // defining it, so they are bound again there by name.
+        let (signature, header, footer) = match assistant {
+            Assistant::Coq => (
+                None,
+                format!("Definition {name} {{{parameters} : Type}} : {ty} :="),
+                ".",
+            ),
+            Assistant::Agda => {
+                let bound = base_types
+                    .iter()
+                    .map(|name| format!("{{{name}}}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (
+                    Some(format!("{name} : {{{parameters} : Set}} → {ty}\n")),
+                    format!("{name} {bound} ="),
+                    "",
+                )
+            }
+            Assistant::Lean => (
+                None,
+                format!("def {name} {{{parameters} : Type}} : {ty} :="),
+                "",
+            ),
+        };
+
+        // The term is placed on the line after the header when it does not fit on the same one,
+        // which is where a definition would conventionally be broken by hand.
+        let doc = Doc::concat(vec![
+            Doc::text(header),
+            Doc::concat(vec![Doc::Line, self.to_exported_doc(assistant, 0)]).nest(INDENT),
+            Doc::text(footer),
+        ])
+        .group();
+
+        let mut definition = signature.unwrap_or_default();
+        doc.render(width.unwrap_or(usize::MAX), &mut definition)
+            .expect("writing to a string should not fail");
+        definition.push('\n');
+        definition
+    }
+}
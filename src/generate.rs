@@ -0,0 +1,218 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use crate::parse::{LambdaTerm, Type};
+use crate::symbol::Symbol;
+
+/// The number of recursive calls a single `TermGenerator::term_of_type` may make before giving
+/// up. Searching for an inhabitant of a type can backtrack a great deal, so this keeps the
+/// generator from wandering off into the weeds on uninhabited (or barely inhabited) types.
+const FUEL: u64 = 10_000;
+
+/// The number of random types `TermGenerator::term` looks for a term of before giving up.
+const ATTEMPTS: usize = 100;
+
+/// A small, seedable pseudo-random number generator, implementing `SplitMix64`.
+///
+/// This is not remotely suitable for anything which needs real randomness, but it is fast,
+/// reproducible across platforms, and more than good enough for generating test terms.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a new `Rng` from the given seed.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Return the next pseudo-random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return a pseudo-random `usize` in the range `0..bound`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "bound must be positive");
+        // NOTE: The modulo bias here is negligible for the small bounds we deal with, and the
+        // result is strictly less than `bound`, so the cast back to `usize` cannot truncate.
+        #[allow(clippy::cast_possible_truncation)]
+        let n = (self.next_u64() % bound as u64) as usize;
+        n
+    }
+
+    /// Shuffle the given slice in place.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A way in which the generator may attempt to build a term of a given type.
+#[derive(Debug, Clone)]
+enum Move {
+    Variable(u64),
+    Abstraction,
+    Application(Type),
+}
+
+/// A generator of random well-typed `LambdaTerm`s.
+#[derive(Debug, Clone)]
+pub struct TermGenerator {
+    rng: Rng,
+    base_types: Vec<Symbol>,
+    fuel: u64,
+}
+
+impl TermGenerator {
+    /// Create a new `TermGenerator` from the given seed, using the base types `A` and `B`.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self::with_base_types(seed, vec![Symbol::new("A"), Symbol::new("B")])
+    }
+
+    /// Create a new `TermGenerator` from the given seed, drawing base types from the given list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base_types` is empty.
+    #[must_use]
+    pub fn with_base_types(seed: u64, base_types: Vec<Symbol>) -> Self {
+        assert!(!base_types.is_empty(), "at least one base type is required");
+        Self {
+            rng: Rng::new(seed),
+            base_types,
+            fuel: 0,
+        }
+    }
+
+    /// Return a random `Type` containing at most `size` base types.
+    pub fn random_type(&mut self, size: usize) -> Type {
+        if size <= 1 || self.rng.below(3) == 0 {
+            let name = &self.base_types[self.rng.below(self.base_types.len())];
+            Type::BaseType(name.clone())
+        } else {
+            let argument_size = 1 + self.rng.below(size - 1);
+            Type::FunctionType(
+                Box::new(self.random_type(argument_size)),
+                Box::new(self.random_type(size - argument_size)),
+            )
+        }
+    }
+
+    /// Return a random closed `LambdaTerm` of the given type with at most `size` nodes, or `None`
+    /// if no such term could be found.
+    ///
+    /// A `None` does not necessarily mean that no such term exists, only that the generator ran
+    /// out of patience looking for one. Some types, like a lone base type, are simply
+    /// uninhabited.
+    pub fn term_of_type(&mut self, ty: &Type, size: usize) -> Option<LambdaTerm> {
+        self.fuel = FUEL;
+        self.generate(&mut Vec::new(), ty, size)
+    }
+
+    /// Return a random closed `LambdaTerm` with at most `size` nodes, together with its type, or
+    /// `None` if no term could be found for any of `ATTEMPTS` random types.
+    pub fn term(&mut self, size: usize) -> Option<(LambdaTerm, Type)> {
+        // NOTE: A type with a single base type has no closed inhabitants at all, so the types
+        // drawn are always allowed room for an arrow, however small the terms.
+        let type_size = (1 + size / 3).max(2);
+        (0..ATTEMPTS).find_map(|_| {
+            let ty = self.random_type(type_size);
+            self.term_of_type(&ty, size).map(|term| (term, ty))
+        })
+    }
+
+    fn generate(&mut self, ctx: &mut Vec<Type>, ty: &Type, size: usize) -> Option<LambdaTerm> {
+        if size == 0 || self.fuel == 0 {
+            return None;
+        }
+        self.fuel -= 1;
+
+        let mut moves = Vec::new();
+
+        // Any variable of the right type will do.
+        for (i, t) in ctx.iter().rev().enumerate() {
+            if t == ty {
+                moves.push(Move::Variable(i as u64));
+            }
+        }
+
+        // Terms of function type can always be built by abstraction.
+        if size >= 2 {
+            if let Type::FunctionType(..) = ty {
+                moves.push(Move::Abstraction);
+            }
+        }
+
+        // Applications are the interesting case. Applying a variable to enough arguments is the
+        // most direct way to hit the required type, but arbitrary argument types are also
+        // occasionally thrown in, since these are what give rise to β-redexes.
+        if size >= 3 {
+            for t in ctx.iter() {
+                let mut t = t;
+                while let Type::FunctionType(argument_type, return_type) = t {
+                    if return_type.as_ref() == ty {
+                        moves.push(Move::Application(*argument_type.clone()));
+                    }
+                    t = return_type;
+                }
+            }
+            let argument_type = self.random_type(2);
+            moves.push(Move::Application(argument_type));
+        }
+
+        self.rng.shuffle(&mut moves);
+
+        for m in moves {
+            let term = match m {
+                Move::Variable(idx) => Some(LambdaTerm::Variable { idx }),
+                Move::Abstraction => {
+                    let Type::FunctionType(argument_type, return_type) = ty else {
+                        unreachable!()
+                    };
+                    ctx.push(*argument_type.clone());
+                    let body = self.generate(ctx, return_type, size - 1);
+                    ctx.pop();
+                    body.map(|body| LambdaTerm::Abstraction {
+                        variable: format!("x{}", ctx.len()),
+                        argument_type: *argument_type.clone(),
+                        body: Rc::new(body),
+                    })
+                }
+                Move::Application(argument_type) => {
+                    let function_size = 1 + self.rng.below(size - 2);
+                    let function_type =
+                        Type::FunctionType(Box::new(argument_type.clone()), Box::new(ty.clone()));
+                    self.generate(ctx, &function_type, function_size)
+                        .and_then(|function| {
+                            self.generate(ctx, &argument_type, size - 1 - function_size)
+                                .map(|argument| LambdaTerm::Application {
+                                    function: Rc::new(function),
+                                    argument: Rc::new(argument),
+                                })
+                        })
+                }
+            };
+
+            if term.is_some() {
+                return term;
+            }
+        }
+
+        None
+    }
+}
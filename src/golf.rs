@@ -0,0 +1,214 @@
+//! Searching for the smallest term βη-equivalent to a given one, by rewriting it one step at a
+//! time in ways which preserve its meaning.
+//!
+//! Normal forms are not always the smallest terms with their meaning, since reducing a term can
+//! duplicate its subterms, so the search also runs β-reduction backwards, sharing a subterm which
+//! occurs repeatedly by abstracting over it.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parse::LambdaTerm;
+use crate::store::Digest;
+use crate::traverse::Step;
+use crate::type_check::TypedTerm;
+use crate::zipper::TermZipper;
+
+/// Return the term which an η-redex `λx:A. f x`, where `x` does not occur in `f`, contracts to,
+/// or `None` if the term is not one.
+fn eta_contract(term: &LambdaTerm) -> Option<LambdaTerm> {
+    let LambdaTerm::Abstraction { body, .. } = term else {
+        return None;
+    };
+    let LambdaTerm::Application { function, argument } = body.as_ref() else {
+        return None;
+    };
+    (matches!(**argument, LambdaTerm::Variable { idx: 0 }) && function.uses_of(0) == 0)
+        .then(|| function.shift(-1, 0))
+}
+
+/// The path to an occurrence of a subterm within some scope, along with the number of
+/// abstractions between the two.
+type Occurrence = (Vec<Step>, u64);
+
+/// Return a name for a new variable which is not the name of any variable of the given term, so
+/// that it shadows none of them.
+fn fresh_name(term: &LambdaTerm) -> String {
+    let names = term
+        .subterms()
+        .filter_map(|s| match s.term {
+            LambdaTerm::Abstraction { variable, .. } => Some(variable.as_str()),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    // NOTE: There are only so many names taken, so one of one more names than that must be free.
+    (0..=names.len())
+        .map(|i| {
+            if i == 0 {
+                String::from("v")
+            } else {
+                format!("v{i}")
+            }
+        })
+        .find(|name| !names.contains(name.as_str()))
+        .expect("one of the names should not be taken")
+}
+
+/// Return every term which `scope`, whose typed form is `typed`, can be rewritten to by sharing
+/// a subterm which occurs in it more than once, as `(λv:A. ... v ... v ...) s`, wherever doing so
+/// makes it smaller, naming the new variable `variable`.
+///
+/// Only subterms which refer to no variable bound inside `scope` are shared, so that they mean
+/// the same thing outside it.
+///
+/// # Panics
+///
+/// Panics if some path produced by `subterms` does not lead to a subterm, which should never
+/// happen.
+fn share(scope: &LambdaTerm, typed: &TypedTerm, variable: &str) -> Vec<LambdaTerm> {
+    // NOTE: Each group is a subterm as it would be written outside `scope`, along with the path
+    // to and depth of each of its occurrences. Equal subterms cannot contain one another, so the
+    // occurrences in a group never overlap.
+    let mut groups: Vec<(LambdaTerm, Vec<Occurrence>)> = Vec::new();
+    for subterm in scope.subterms().skip(1) {
+        if matches!(subterm.term, LambdaTerm::Variable { .. })
+            || (0..subterm.depth).any(|idx| subterm.term.uses_of(idx) > 0)
+        {
+            continue;
+        }
+        let depth = i64::try_from(subterm.depth).expect("a term should not be so deep");
+        let lifted = subterm.term.shift(-depth, 0);
+        let occurrence = (subterm.path, subterm.depth);
+        match groups.iter_mut().find(|(term, _)| *term == lifted) {
+            Some((_, occurrences)) => occurrences.push(occurrence),
+            None => groups.push((lifted, vec![occurrence])),
+        }
+    }
+
+    let mut shared = Vec::new();
+    for (lifted, occurrences) in groups {
+        // Each occurrence is replaced by a variable, at the cost of a new abstraction, an
+        // application, and one more copy of the subterm.
+        let (count, size) = (occurrences.len(), lifted.size());
+        if count * size <= count + size + 2 {
+            continue;
+        }
+        let argument_type = typed
+            .at_path(&occurrences[0].0)
+            .expect("path to a subterm should be valid")
+            .ty()
+            .clone();
+        let mut body = scope.shift(1, 0);
+        for (path, depth) in &occurrences {
+            let mut zipper =
+                TermZipper::at_path(body, path).expect("path to a subterm should be valid");
+            zipper.replace(LambdaTerm::Variable { idx: *depth });
+            body = zipper.into_term();
+        }
+        shared.push(LambdaTerm::Application {
+            function: Rc::new(LambdaTerm::Abstraction {
+                variable: String::from(variable),
+                argument_type,
+                body: Rc::new(body),
+            }),
+            argument: Rc::new(lifted),
+        });
+    }
+    shared
+}
+
+impl LambdaTerm {
+    /// Return every term which the well-typed `LambdaTerm` can be rewritten to in a single step
+    /// which keeps it βη-equivalent, or nothing if it is not well-typed.
+    ///
+    /// A step either contracts a β-redex, which removes a binder whose variable is never used and
+    /// applies the identities of combinators such as `I` and `K`, contracts an η-redex, or shares
+    /// a subterm which occurs repeatedly within the body of some abstraction, or within the whole
+    /// term, wherever that makes it smaller. Every result is well-typed, with the same type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some path produced by `subterms` does not lead to a subterm, which should never
+    /// happen.
+    #[must_use]
+    pub fn equivalent_rewrites(&self) -> Vec<Self> {
+        let Ok(typed) = self.get_type() else {
+            return Vec::new();
+        };
+        let variable = fresh_name(self);
+        let mut rewrites = Vec::new();
+        for subterm in self.subterms() {
+            let replace = |term| {
+                let mut zipper = TermZipper::at_path(self.clone(), &subterm.path)
+                    .expect("path to a subterm should be valid");
+                zipper.replace(term);
+                zipper.into_term()
+            };
+            if let Some(contracted) = self.contract_at(&subterm.path) {
+                rewrites.push(contracted);
+            }
+            if let Some(contracted) = eta_contract(subterm.term) {
+                rewrites.push(replace(contracted));
+            }
+            if matches!(subterm.path.last(), None | Some(Step::Body)) {
+                let typed = typed
+                    .at_path(&subterm.path)
+                    .expect("path to a subterm should be valid");
+                rewrites.extend(
+                    share(subterm.term, typed, &variable)
+                        .into_iter()
+                        .map(replace),
+                );
+            }
+        }
+        rewrites
+    }
+
+    /// Search for the smallest term βη-equivalent to the well-typed `LambdaTerm`, returning the
+    /// smallest found along with the number of terms explored, which is at most `budget`.
+    ///
+    /// The search starts from the term and its βη-normal form, and repeatedly explores the
+    /// smallest term not yet explored, by way of `equivalent_rewrites`, never considering terms
+    /// larger than both of those it started from. The term returned is no larger than either.
+    #[must_use]
+    pub fn golf(&self, budget: usize) -> (Self, usize) {
+        let normal_form = self.normalize().eta_reduce();
+        let limit = self.size().max(normal_form.size());
+
+        // NOTE: Terms are ordered by size, and then by the order in which they were found, so
+        // that the smallest is explored first and ties go to whichever is closest to the start.
+        let mut found = Vec::new();
+        let mut frontier = BTreeSet::new();
+        let mut seen = BTreeSet::<Digest>::new();
+        for term in [self.clone(), normal_form] {
+            if seen.insert(term.digest()) {
+                frontier.insert((term.size(), found.len()));
+                found.push(term);
+            }
+        }
+
+        let mut best = usize::from(found.len() > 1 && found[1].size() < found[0].size());
+        let mut explored = 0;
+        while explored < budget {
+            let Some((size, i)) = frontier.pop_first() else {
+                break;
+            };
+            explored += 1;
+            if size < found[best].size() {
+                best = i;
+            }
+            for rewrite in found[i].equivalent_rewrites() {
+                let size = rewrite.size();
+                if size <= limit && seen.insert(rewrite.digest()) {
+                    frontier.insert((size, found.len()));
+                    found.push(rewrite);
+                }
+            }
+        }
+        (found.swap_remove(best), explored)
+    }
+}
@@ -0,0 +1,144 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::parse::LambdaTerm;
+use crate::traverse::Step;
+
+/// A β-step from one term of a `ReductionGraph` to another.
+#[derive(Debug, Clone)]
+pub struct ReductionEdge {
+    /// The index of the term in which a redex was contracted.
+    pub from: usize,
+    /// The index of the term resulting from contracting it.
+    pub to: usize,
+    /// The path to the redex which was contracted.
+    pub redex: Vec<Step>,
+}
+
+/// The terms reachable from some `LambdaTerm` by β-reduction, in any order, together with the
+/// β-steps between them, as built by `LambdaTerm::reduction_graph`.
+///
+/// Terms are identified up to α-equivalence, so wherever two different orders of reduction reach
+/// the same term, the graph shows them meeting there.
+#[derive(Debug, Clone)]
+pub struct ReductionGraph {
+    /// Every term in the graph, the original term first, in the order in which they were found.
+    pub terms: Vec<LambdaTerm>,
+    /// Every β-step between terms in the graph.
+    pub edges: Vec<ReductionEdge>,
+    /// Whether exploration was stopped before every reachable term had been found, in which case
+    /// the β-steps leading out of the graph are missing from it.
+    pub truncated: bool,
+}
+
+impl LambdaTerm {
+    /// Return the graph of every term reachable from the `LambdaTerm` by contracting redexes
+    /// anywhere within it, exploring terms breadth first and stopping once `limit` have been
+    /// found.
+    #[must_use]
+    pub fn reduction_graph(&self, limit: usize) -> ReductionGraph {
+        let mut graph = ReductionGraph {
+            terms: Vec::new(),
+            edges: Vec::new(),
+            truncated: false,
+        };
+        let mut indices = BTreeMap::new();
+        let mut queue = VecDeque::new();
+
+        indices.insert(self.to_canonical_string(), 0);
+        graph.terms.push(self.clone());
+        queue.push_back(0);
+
+        while let Some(from) = queue.pop_front() {
+            let term = graph.terms[from].clone();
+            for subterm in term.subterms() {
+                let Some(reduct) = term.contract_at(&subterm.path) else {
+                    continue;
+                };
+                let key = reduct.to_canonical_string();
+                let to = if let Some(to) = indices.get(&key) {
+                    *to
+                } else if graph.terms.len() < limit {
+                    indices.insert(key, graph.terms.len());
+                    graph.terms.push(reduct);
+                    queue.push_back(graph.terms.len() - 1);
+                    graph.terms.len() - 1
+                } else {
+                    graph.truncated = true;
+                    continue;
+                };
+                graph.edges.push(ReductionEdge {
+                    from,
+                    to,
+                    redex: subterm.path,
+                });
+            }
+        }
+        graph
+    }
+}
+
+/// Write the given string as a quoted string in the DOT language.
+fn write_dot_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+impl ReductionGraph {
+    /// Return the graph in the DOT language, for rendering with Graphviz.
+    ///
+    /// Every term is a node labelled with the term itself, and every β-step is an edge labelled
+    /// with the path to its redex, written as a string of `b`, `f`, and `a` for the body of an
+    /// abstraction, function of an application, and argument of an application, or `ε` for the
+    /// whole term. Normal forms are circled twice, and the steps taken by kombi's own lazy
+    /// evaluation are drawn in bold.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph reduction {\n    node [shape=box];\n");
+
+        for (i, term) in self.terms.iter().enumerate() {
+            write!(out, "    {i} [label=").expect("writing to a string should not fail");
+            write_dot_string(&mut out, &term.to_string());
+            if i == 0 {
+                out.push_str(", style=filled, fillcolor=lightgrey");
+            }
+            if term.redex_count() == 0 {
+                out.push_str(", peripheries=2");
+            }
+            out.push_str("];\n");
+        }
+
+        for edge in &self.edges {
+            let redex = if edge.redex.is_empty() {
+                String::from("ε")
+            } else {
+                edge.redex
+                    .iter()
+                    .map(|step| match step {
+                        Step::Body => 'b',
+                        Step::Function => 'f',
+                        Step::Argument => 'a',
+                    })
+                    .collect()
+            };
+            write!(out, "    {} -> {} [label=", edge.from, edge.to)
+                .expect("writing to a string should not fail");
+            write_dot_string(&mut out, &redex);
+            if self.terms[edge.from].next_redex().as_ref() == Some(&edge.redex) {
+                out.push_str(", style=bold");
+            }
+            out.push_str("];\n");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
@@ -0,0 +1,277 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::{LambdaTerm, Type};
+use crate::symbol::Symbol;
+use crate::untyped::UntypedTerm;
+
+#[derive(Debug)]
+pub enum InferenceError {
+    /// The term contains a variable with the given de Bruijn index, relative to the root of the
+    /// term, which is not bound by any of its abstractions.
+    FreeVariable { idx: u64 },
+    /// No assignment of simple types to the variables of the term makes the given application,
+    /// found under `depth` abstractions, well-typed.
+    Untypable {
+        application: UntypedTerm,
+        depth: u64,
+    },
+}
+
+impl Display for InferenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FreeVariable { idx } => {
+                write!(
+                    f,
+                    "term contains the free variable with de Bruijn index {idx}"
+                )
+            }
+            Self::Untypable { application, depth } => {
+                // The variables of the application are named just as they would be in the whole
+                // term, so that it can be found there.
+                write!(f, "the application ")?;
+                application.write(f, *depth, true)?;
+                write!(f, " has no simple type")
+            }
+        }
+    }
+}
+
+impl Error for InferenceError {}
+
+/// A node of the graph of types built up during inference, identified by its position in
+/// `Unifier::nodes`.
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    /// A type about which nothing is yet known.
+    Unknown,
+    /// A type which has been unified with the given node, and is now represented by it.
+    Link(usize),
+    /// The type of functions between the given nodes.
+    Function(usize, usize),
+}
+
+/// A union-find structure over types, into which the constraints on the types of a term are fed
+/// one at a time.
+#[derive(Debug, Default)]
+struct Unifier {
+    nodes: Vec<Node>,
+}
+
+impl Unifier {
+    fn fresh(&mut self) -> usize {
+        self.nodes.push(Node::Unknown);
+        self.nodes.len() - 1
+    }
+
+    fn function(&mut self, argument: usize, result: usize) -> usize {
+        self.nodes.push(Node::Function(argument, result));
+        self.nodes.len() - 1
+    }
+
+    /// Return the node currently representing the given node.
+    fn find(&self, mut node: usize) -> usize {
+        while let Node::Link(next) = self.nodes[node] {
+            node = next;
+        }
+        node
+    }
+
+    /// Return whether the unknown `node` occurs anywhere in the type `ty`.
+    fn occurs(&self, node: usize, ty: usize) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![ty];
+        while let Some(ty) = stack.pop() {
+            let ty = self.find(ty);
+            if ty == node {
+                return true;
+            }
+            if let Node::Function(argument, result) = self.nodes[ty] {
+                if seen.insert(ty) {
+                    stack.push(argument);
+                    stack.push(result);
+                }
+            }
+        }
+        false
+    }
+
+    /// Make the two types equal, returning `false` if they cannot be.
+    fn unify(&mut self, a: usize, b: usize) -> bool {
+        let mut stack = vec![(a, b)];
+        while let Some((a, b)) = stack.pop() {
+            let (a, b) = (self.find(a), self.find(b));
+            if a == b {
+                continue;
+            }
+            match (self.nodes[a], self.nodes[b]) {
+                (Node::Unknown, _) => {
+                    if self.occurs(a, b) {
+                        return false;
+                    }
+                    self.nodes[a] = Node::Link(b);
+                }
+                (_, Node::Unknown) => {
+                    if self.occurs(b, a) {
+                        return false;
+                    }
+                    self.nodes[b] = Node::Link(a);
+                }
+                (Node::Function(a_argument, a_result), Node::Function(b_argument, b_result)) => {
+                    self.nodes[a] = Node::Link(b);
+                    stack.push((a_result, b_result));
+                    stack.push((a_argument, b_argument));
+                }
+                (Node::Link(_), _) | (_, Node::Link(_)) => unreachable!(),
+            }
+        }
+        true
+    }
+
+    /// Return the `Type` which the given node has been found to have, naming every type about
+    /// which nothing is known as a base type, in the order in which they are first met.
+    fn resolve(&self, node: usize, names: &mut BTreeMap<usize, Symbol>) -> Type {
+        let node = self.find(node);
+        match self.nodes[node] {
+            Node::Function(argument, result) => Type::FunctionType(
+                Box::new(self.resolve(argument, names)),
+                Box::new(self.resolve(result, names)),
+            ),
+            Node::Unknown | Node::Link(_) => {
+                let count = names.len();
+                Type::BaseType(
+                    names
+                        .entry(node)
+                        .or_insert_with(|| base_type_name(count))
+                        .clone(),
+                )
+            }
+        }
+    }
+}
+
+/// Return the name given to the base type which is the `n`th to be named by inference, running
+/// through the alphabet as `A`, `B`, ..., `Z`, then again as `A1`, `B1`, and so on.
+fn base_type_name(n: usize) -> Symbol {
+    let letter = char::from(b'A' + u8::try_from(n % 26).expect("remainder should fit in a u8"));
+    if n < 26 {
+        Symbol::new(&format!("{letter}"))
+    } else {
+        Symbol::new(&format!("{letter}{}", n / 26))
+    }
+}
+
+/// A unit of work for inference, analogous to those of the type checker.
+enum Task<'a> {
+    Infer(&'a UntypedTerm),
+    Finish(&'a UntypedTerm),
+}
+
+impl UntypedTerm {
+    /// Return the `LambdaTerm` obtained by annotating every abstraction in the `UntypedTerm` with
+    /// the most general type it can be given, or an `InferenceError` if the term has no simple
+    /// type.
+    ///
+    /// Type variables, which kombi does not have, are replaced by distinct base types, named `A`,
+    /// `B`, and so on in the order in which they appear in the type of the whole term, and then
+    /// in its annotations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InferenceError` if the `UntypedTerm` contains free variables, or cannot be
+    /// given a simple type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some de Bruijn index does not fit in a `usize`.
+    pub fn infer_types(&self) -> Result<LambdaTerm, InferenceError> {
+        let mut unifier = Unifier::default();
+        let mut ctx = Vec::new();
+        let mut binders = Vec::new();
+        let mut tasks = vec![Task::Infer(self)];
+        let mut types = Vec::new();
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Infer(UntypedTerm::Variable { idx }) => {
+                    let i = usize::try_from(*idx).expect("de Bruijn index should fit in a usize");
+                    let Some(i) = ctx.len().checked_sub(i + 1) else {
+                        return Err(InferenceError::FreeVariable {
+                            idx: *idx - ctx.len() as u64,
+                        });
+                    };
+                    types.push(ctx[i]);
+                }
+                Task::Infer(term @ UntypedTerm::Abstraction { body, .. }) => {
+                    let argument = unifier.fresh();
+                    ctx.push(argument);
+                    binders.push(argument);
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Infer(body));
+                }
+                Task::Infer(term @ UntypedTerm::Application { function, argument }) => {
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Infer(argument));
+                    tasks.push(Task::Infer(function));
+                }
+                Task::Finish(UntypedTerm::Abstraction { .. }) => {
+                    let argument = ctx.pop().expect("abstraction should have bound a variable");
+                    let body = types.pop().expect("body should have been inferred");
+                    types.push(unifier.function(argument, body));
+                }
+                Task::Finish(term @ UntypedTerm::Application { .. }) => {
+                    let argument = types.pop().expect("argument should have been inferred");
+                    let function = types.pop().expect("function should have been inferred");
+                    let result = unifier.fresh();
+                    let expected = unifier.function(argument, result);
+                    if !unifier.unify(function, expected) {
+                        return Err(InferenceError::Untypable {
+                            application: term.clone(),
+                            depth: ctx.len() as u64,
+                        });
+                    }
+                    types.push(result);
+                }
+                Task::Finish(UntypedTerm::Variable { .. }) => unreachable!(),
+            }
+        }
+
+        let mut names = BTreeMap::new();
+        unifier.resolve(
+            types.pop().expect("term should have been inferred"),
+            &mut names,
+        );
+        let mut annotations = binders
+            .into_iter()
+            .map(|binder| unifier.resolve(binder, &mut names))
+            .collect::<Vec<_>>()
+            .into_iter();
+        Ok(self.annotate(&mut annotations))
+    }
+
+    /// Rebuild the `UntypedTerm` as a `LambdaTerm`, taking the annotation of each abstraction in
+    /// turn from `annotations`, in pre-order.
+    fn annotate(&self, annotations: &mut impl Iterator<Item = Type>) -> LambdaTerm {
+        match self {
+            UntypedTerm::Variable { idx } => LambdaTerm::Variable { idx: *idx },
+            UntypedTerm::Abstraction { variable, body } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: annotations
+                    .next()
+                    .expect("every abstraction should have an annotation"),
+                body: Rc::new(body.annotate(annotations)),
+            },
+            UntypedTerm::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.annotate(annotations)),
+                argument: Rc::new(argument.annotate(annotations)),
+            },
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Interfaces, which record the names exported by a module and what they are, leaving out their
+//! bodies.
+//!
+//! A module can be checked against the interfaces of the modules it imports, rather than the
+//! modules themselves: every definition they export stands for a variable of its type, so that
+//! none of their terms need be loaded, and every definition of the module is checked only for
+//! its type. Comparing the interface of a module with the one it had before shows whether any
+//! of the modules importing it might no longer check.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::environment::EnvironmentError;
+use crate::parse::{LambdaTerm, ParseError, Type};
+use crate::surface::{Item, Macro, Program, ScopeError, SurfaceTerm};
+
+/// A name exported by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Export {
+    /// A definition of the given type.
+    Definition(Type),
+    /// A macro with the given number of parameters.
+    Macro(usize),
+}
+
+impl Display for Export {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Definition(ty) => write!(f, "a definition of type {ty}"),
+            Self::Macro(1) => write!(f, "a macro with 1 parameter"),
+            Self::Macro(parameters) => write!(f, "a macro with {parameters} parameters"),
+        }
+    }
+}
+
+/// The interface of a module, which is every name it exports, in the order in which they were
+/// first defined.
+///
+/// An interface is written as a definition without a body for each definition, as in
+/// `let id : A → A;`, and a macro without a body for each macro, with each parameter written as
+/// `_`, as in `macro twice _ _;`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    pub exports: Vec<(String, Export)>,
+}
+
+impl Display for Interface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, export) in &self.exports {
+            match export {
+                Export::Definition(ty) => writeln!(f, "let {name} : {ty};")?,
+                Export::Macro(parameters) => {
+                    write!(f, "macro {name}")?;
+                    for _ in 0..*parameters {
+                        write!(f, " _")?;
+                    }
+                    writeln!(f, ";")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A difference between two interfaces of the same module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceChange {
+    /// The name is exported by the new interface, but not the old one.
+    Added { name: String, export: Export },
+    /// The name is exported by the old interface, but not the new one.
+    Removed { name: String, export: Export },
+    /// The name is exported by both interfaces, but is something different in each.
+    Changed {
+        name: String,
+        old: Export,
+        new: Export,
+    },
+}
+
+impl Display for InterfaceChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { name, export } => write!(f, "{name}, {export}, was added"),
+            Self::Removed { name, export } => write!(f, "{name}, {export}, was removed"),
+            Self::Changed { name, old, new } => {
+                write!(f, "{name} was {old}, but is now {new}")
+            }
+        }
+    }
+}
+
+impl Interface {
+    /// Return what the given name is exported as, if it is exported.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Export> {
+        self.exports
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, export)| export)
+    }
+
+    /// Export `name` as the given `Export`, replacing whatever it was exported as before.
+    fn export(&mut self, name: &str, export: Export) {
+        if let Some((_, old)) = self.exports.iter_mut().find(|(n, _)| n == name) {
+            *old = export;
+        } else {
+            self.exports.push((name.to_string(), export));
+        }
+    }
+
+    /// Return every difference between this interface and `new`, a later interface of the same
+    /// module: first those names it removed or changed, in the order of this interface, then
+    /// those it added, in its own order.
+    #[must_use]
+    pub fn changes(&self, new: &Interface) -> Vec<InterfaceChange> {
+        let mut changes = Vec::new();
+        for (name, old) in &self.exports {
+            match new.get(name) {
+                None => changes.push(InterfaceChange::Removed {
+                    name: name.clone(),
+                    export: old.clone(),
+                }),
+                Some(export) if export != old => changes.push(InterfaceChange::Changed {
+                    name: name.clone(),
+                    old: old.clone(),
+                    new: export.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, export) in &new.exports {
+            if self.get(name).is_none() {
+                changes.push(InterfaceChange::Added {
+                    name: name.clone(),
+                    export: export.clone(),
+                });
+            }
+        }
+        changes
+    }
+
+    /// Check the program in the given string against the interfaces of the modules it imports,
+    /// as given by `importer`, and return its own interface. Its assertions and term are not
+    /// checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvironmentError` if the string is not a valid program, any of its definitions
+    /// are invalid, or any of the interfaces it imports cannot be loaded. Since an interface does
+    /// not record the bodies of macros, a macro imported from one cannot be used.
+    pub fn check(
+        string: &str,
+        importer: &mut dyn FnMut(&str) -> Result<Interface, String>,
+    ) -> Result<Interface, EnvironmentError> {
+        let program = string.parse::<Program>()?;
+        let mut checker = Checker::default();
+        for item in program.items() {
+            match item {
+                Item::Definition(definition) => {
+                    let term = checker
+                        .resolve(&definition.term, &[])
+                        .map_err(|e| checker.to_parse_error(&e, string))?;
+                    let ty = term
+                        .get_type_in(&checker.types)
+                        .map_err(|error| EnvironmentError::IllTyped {
+                            name: definition.name.clone(),
+                            error,
+                        })?
+                        .into_type();
+                    checker.define(&definition.name, ty);
+                }
+                Item::Macro(r#macro) => {
+                    let body = checker
+                        .resolve(&r#macro.body, &r#macro.parameters)
+                        .map_err(|e| checker.to_parse_error(&e, string))?;
+                    checker.define_macro(
+                        &r#macro.name,
+                        Macro {
+                            parameters: r#macro.parameters.len(),
+                            context: checker.names.len(),
+                            body,
+                        },
+                    );
+                }
+                Item::Import(import) => {
+                    let interface =
+                        importer(&import.path).map_err(|message| EnvironmentError::Import {
+                            path: import.path.clone(),
+                            message,
+                        })?;
+                    for (name, export) in interface.exports {
+                        match export {
+                            Export::Definition(ty) => checker.define(&name, ty),
+                            Export::Macro(parameters) => {
+                                checker.hide(&name);
+                                checker.interface.export(&name, Export::Macro(parameters));
+                            }
+                        }
+                    }
+                }
+                Item::Assertion(_) => {}
+            }
+        }
+        Ok(checker.interface)
+    }
+}
+
+/// The state of `Interface::check` as it goes through a program.
+#[derive(Default)]
+struct Checker {
+    /// The name of every definition checked or imported so far, each of which stands for a
+    /// variable bound outside the program, the outermost first. Names which have since been
+    /// hidden by another definition are left empty, so that the rest keep their places.
+    names: Vec<String>,
+    /// The type of the variable for each of `names`.
+    types: Vec<Type>,
+    /// The macros defined by the program itself, each with the context it was defined in.
+    macros: BTreeMap<String, Macro>,
+    /// The interface of the program, so far.
+    interface: Interface,
+}
+
+impl Checker {
+    /// Make the given name refer to nothing, ready for it to be defined again.
+    fn hide(&mut self, name: &str) {
+        for hidden in self.names.iter_mut().filter(|n| *n == name) {
+            hidden.clear();
+        }
+        self.macros.remove(name);
+    }
+
+    fn define(&mut self, name: &str, ty: Type) {
+        self.hide(name);
+        self.names.push(name.to_string());
+        self.types.push(ty.clone());
+        self.interface.export(name, Export::Definition(ty));
+    }
+
+    fn define_macro(&mut self, name: &str, r#macro: Macro) {
+        self.hide(name);
+        self.interface
+            .export(name, Export::Macro(r#macro.parameters));
+        self.macros.insert(name.to_string(), r#macro);
+    }
+
+    /// Resolve the given term under abstractions binding each of `parameters`, where every
+    /// definition is the variable it stands for.
+    fn resolve(&self, term: &SurfaceTerm, parameters: &[String]) -> Result<LambdaTerm, ScopeError> {
+        let mut ctx = self.names.clone();
+        ctx.extend_from_slice(parameters);
+        term.to_core_with_macros(&ctx, &|name| {
+            // Since the macro was defined, its context may have grown, which pushes the free
+            // variables of its body further out.
+            let r#macro = self.macros.get(name)?;
+            let amount = i64::try_from(self.names.len() - r#macro.context)
+                .expect("the number of definitions should fit in an i64");
+            Some(Macro {
+                context: self.names.len(),
+                body: r#macro.body.shift(amount, r#macro.parameters as u64),
+                ..r#macro.clone()
+            })
+        })
+    }
+
+    /// Return the given `ScopeError`, found in `string`, as a `ParseError`, explaining why a name
+    /// which the interface exports as a macro cannot be used.
+    fn to_parse_error(&self, e: &ScopeError, string: &str) -> EnvironmentError {
+        let message = match e {
+            ScopeError::UnboundVariable { name, .. }
+                if matches!(self.interface.get(name), Some(Export::Macro(_))) =>
+            {
+                format!("macro {name} is imported from an interface, so cannot be expanded")
+            }
+            e => e.to_string(),
+        };
+        EnvironmentError::Parse(ParseError::new(message, e.span(), string))
+    }
+}
@@ -0,0 +1,46 @@
+//! A typed lambda calculus.
+//!
+//! With default features disabled, the library builds with `no_std`, depending only on `alloc`,
+//! so that it can be embedded anywhere with a heap. The `std` feature restores the standard
+//! library, and the `cli` feature, enabled by default, builds the `kombi` binary on top of it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(clippy::pedantic)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub mod arena;
+pub mod blc;
+pub mod certificate;
+pub mod decode;
+pub mod derivation;
+pub mod diagram;
+pub mod document;
+pub mod environment;
+pub mod explain;
+pub mod export;
+pub mod generate;
+pub mod golf;
+pub mod graph;
+pub mod inference;
+pub mod interface;
+pub mod lint;
+pub mod metrics;
+pub mod parse;
+pub mod prelude;
+pub mod pretty;
+pub mod print;
+pub mod profile;
+pub mod reduce;
+pub mod share;
+pub mod shrink;
+pub mod store;
+pub mod substitution;
+pub mod surface;
+pub mod symbol;
+pub mod table;
+pub mod traverse;
+pub mod type_check;
+pub mod untyped;
+pub mod zipper;
@@ -0,0 +1,467 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::ParseError;
+use crate::surface::{Assertion, Item, Program, Span, SurfaceTerm};
+
+/// How seriously a `Lint` should be taken, from least to most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Severity {
+    /// A matter of style, which does not affect what the program means.
+    Hint,
+    /// Something which is likely to be a mistake.
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Hint => write!(f, "hint"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// What is done about the lints of some kind when they are found, from least to most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The lints are not reported at all.
+    Allow,
+    /// The lints are reported, but the program is used all the same.
+    Warn,
+    /// The lints are reported as errors, which stop the program being used.
+    Deny,
+}
+
+/// A kind of problem which `lint` looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum LintKind {
+    /// An abstraction whose variable is never used in its body. Variables whose names begin with
+    /// `_` are assumed to be unused on purpose.
+    UnusedBinder,
+    /// An abstraction `λx:A. f x`, where `x` does not occur in `f`, which can be replaced by `f`.
+    EtaRedex,
+    /// Parentheses which the term would parse the same without.
+    RedundantParentheses,
+    /// An abstraction or definition reusing the name of a variable or definition already in
+    /// scope, which can then no longer be referred to.
+    ShadowedName,
+}
+
+impl LintKind {
+    /// Every `LintKind`.
+    pub const ALL: &'static [LintKind] = &[
+        LintKind::UnusedBinder,
+        LintKind::EtaRedex,
+        LintKind::RedundantParentheses,
+        LintKind::ShadowedName,
+    ];
+
+    /// Return the name of the `LintKind`, as used on the command line.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            LintKind::UnusedBinder => "unused-binder",
+            LintKind::EtaRedex => "eta-redex",
+            LintKind::RedundantParentheses => "redundant-parentheses",
+            LintKind::ShadowedName => "shadowed-name",
+        }
+    }
+
+    /// Return the `Severity` with which lints of this kind are reported.
+    #[must_use]
+    pub fn severity(self) -> Severity {
+        match self {
+            LintKind::UnusedBinder | LintKind::ShadowedName => Severity::Warning,
+            LintKind::EtaRedex | LintKind::RedundantParentheses => Severity::Hint,
+        }
+    }
+}
+
+/// The `Level` at which each kind of lint is reported.
+///
+/// By default, lints of kinds with the severity of a warning are warned about, while hints, which
+/// are only matters of style, are allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Levels {
+    levels: BTreeMap<LintKind, Level>,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self::new(|kind| match kind.severity() {
+            Severity::Hint => Level::Allow,
+            Severity::Warning => Level::Warn,
+        })
+    }
+}
+
+impl Levels {
+    /// Create `Levels` reporting each kind of lint at the level given by `level`.
+    #[must_use]
+    pub fn new(level: impl Fn(LintKind) -> Level) -> Self {
+        Self {
+            levels: LintKind::ALL
+                .iter()
+                .map(|&kind| (kind, level(kind)))
+                .collect(),
+        }
+    }
+
+    /// Return the `Level` at which lints of the given kind are reported.
+    #[must_use]
+    pub fn get(&self, kind: LintKind) -> Level {
+        self.levels[&kind]
+    }
+
+    /// Report lints of the given kind at the given `Level`.
+    pub fn set(&mut self, kind: LintKind, level: Level) {
+        self.levels.insert(kind, level);
+    }
+
+    /// Report every kind of lint which would be warned about as an error instead.
+    pub fn deny_warnings(&mut self) {
+        for level in self.levels.values_mut() {
+            if *level == Level::Warn {
+                *level = Level::Deny;
+            }
+        }
+    }
+}
+
+/// A problem found by `lint`, attributed to the part of the source responsible for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    pub span: Span,
+    pub message: String,
+}
+
+/// What is known about the place in a program at which a pass is checking a subterm.
+struct Context<'a> {
+    source: &'a str,
+    /// The variables bound by the abstractions enclosing the subterm, outermost first.
+    binders: &'a [&'a str],
+    /// The names of the definitions and macros preceding the subterm.
+    definitions: &'a [&'a str],
+    /// The names of the macros preceding the subterm, which must be applied to as many arguments
+    /// as they have parameters, so cannot take part in η-reduction.
+    macros: &'a [&'a str],
+    /// The number of pairs of parentheses written around the subterm.
+    parentheses: usize,
+    /// Whether the subterm needs a pair of parentheses where it is.
+    needs_parentheses: bool,
+}
+
+/// A single check, which `lint` runs on every subterm of a program in turn.
+trait Pass {
+    fn check(&self, term: &SurfaceTerm, context: &Context<'_>, lints: &mut Vec<Lint>);
+}
+
+struct UnusedBinder;
+struct EtaRedex;
+struct RedundantParentheses;
+struct ShadowedName;
+
+/// Every pass run by `lint`.
+const PASSES: &[&dyn Pass] = &[
+    &UnusedBinder,
+    &EtaRedex,
+    &RedundantParentheses,
+    &ShadowedName,
+];
+
+impl Pass for UnusedBinder {
+    fn check(&self, term: &SurfaceTerm, _: &Context<'_>, lints: &mut Vec<Lint>) {
+        if let SurfaceTerm::Abstraction {
+            variable,
+            body,
+            span,
+            ..
+        } = term
+        {
+            if !variable.starts_with('_') && !occurs_free(body, variable) {
+                lints.push(Lint {
+                    kind: LintKind::UnusedBinder,
+                    span: *span,
+                    message: format!("variable {variable} is never used"),
+                });
+            }
+        }
+    }
+}
+
+impl Pass for EtaRedex {
+    fn check(&self, term: &SurfaceTerm, context: &Context<'_>, lints: &mut Vec<Lint>) {
+        let SurfaceTerm::Abstraction {
+            variable,
+            body,
+            span,
+            ..
+        } = term
+        else {
+            return;
+        };
+        let SurfaceTerm::Application {
+            function, argument, ..
+        } = body.as_ref()
+        else {
+            return;
+        };
+        let mut head = function.as_ref();
+        while let SurfaceTerm::Application { function, .. } = head {
+            head = function;
+        }
+        let expands = matches!(
+            head,
+            SurfaceTerm::Variable { name, .. }
+                if context.macros.contains(&name.as_str())
+                    && !context.binders.contains(&name.as_str())
+        );
+        if matches!(argument.as_ref(), SurfaceTerm::Variable { name, .. } if name == variable)
+            && !occurs_free(function, variable)
+            && !expands
+        {
+            lints.push(Lint {
+                kind: LintKind::EtaRedex,
+                span: *span,
+                message: format!("abstraction can be replaced by {function}"),
+            });
+        }
+    }
+}
+
+impl Pass for RedundantParentheses {
+    fn check(&self, term: &SurfaceTerm, context: &Context<'_>, lints: &mut Vec<Lint>) {
+        let needed = usize::from(context.needs_parentheses);
+        if context.parentheses <= needed {
+            return;
+        }
+
+        // The redundant parentheses are the outermost ones, since any which are needed must be
+        // the innermost, directly around the subterm.
+        let mut span = term.span();
+        for _ in 0..context.parentheses {
+            span = parenthesized_span(context.source, span)
+                .expect("subterm should have as many parentheses as were counted");
+        }
+        let count = context.parentheses - needed;
+        lints.push(Lint {
+            kind: LintKind::RedundantParentheses,
+            span,
+            message: if count == 1 {
+                String::from("parentheses are unnecessary")
+            } else {
+                format!("{count} pairs of parentheses are unnecessary")
+            },
+        });
+    }
+}
+
+impl Pass for ShadowedName {
+    fn check(&self, term: &SurfaceTerm, context: &Context<'_>, lints: &mut Vec<Lint>) {
+        let SurfaceTerm::Abstraction { variable, span, .. } = term else {
+            return;
+        };
+        let shadowed = if context.binders.contains(&variable.as_str()) {
+            "an enclosing variable"
+        } else if context.definitions.contains(&variable.as_str()) {
+            "a definition"
+        } else {
+            return;
+        };
+        lints.push(Lint {
+            kind: LintKind::ShadowedName,
+            span: *span,
+            message: format!("variable {variable} shadows {shadowed} of the same name"),
+        });
+    }
+}
+
+/// Return whether the variable `name` occurs free in `term`.
+fn occurs_free(term: &SurfaceTerm, name: &str) -> bool {
+    match term {
+        SurfaceTerm::Variable { name: n, .. } => n == name,
+        SurfaceTerm::Abstraction { variable, body, .. } => {
+            variable != name && occurs_free(body, name)
+        }
+        SurfaceTerm::Application {
+            function, argument, ..
+        } => occurs_free(function, name) || occurs_free(argument, name),
+    }
+}
+
+/// Return the `Span` of the given `Span` of `source` together with a pair of parentheses written
+/// directly around it, if there is one.
+fn parenthesized_span(source: &str, span: Span) -> Option<Span> {
+    let is_whitespace = |c: char| matches!(c, '\x09'..='\x0d' | ' ');
+    let before = source[..span.start].trim_end_matches(is_whitespace);
+    let after = source[span.end..].trim_start_matches(is_whitespace);
+    (before.ends_with('(') && after.starts_with(')')).then(|| Span {
+        start: before.len() - 1,
+        end: source.len() - after.len() + 1,
+    })
+}
+
+/// Run every pass on `term` and each of its subterms, found in the given context, where
+/// `rightmost` is whether the term extends to the right end of the enclosing term, and
+/// `argument` is whether it is the argument of an application.
+fn walk<'a>(
+    term: &'a SurfaceTerm,
+    source: &str,
+    binders: &mut Vec<&'a str>,
+    (definitions, macros): (&[&str], &[&str]),
+    (rightmost, argument): (bool, bool),
+    lints: &mut Vec<Lint>,
+) {
+    let mut parentheses = 0;
+    let mut span = term.span();
+    while let Some(outer) = parenthesized_span(source, span) {
+        parentheses += 1;
+        span = outer;
+    }
+    let context = Context {
+        source,
+        binders,
+        definitions,
+        macros,
+        parentheses,
+        needs_parentheses: match term {
+            SurfaceTerm::Variable { .. } => false,
+            SurfaceTerm::Abstraction { .. } => !rightmost,
+            SurfaceTerm::Application { .. } => argument,
+        },
+    };
+    for pass in PASSES {
+        pass.check(term, &context, lints);
+    }
+
+    // Within parentheses, a term extends to the closing parenthesis, so is rightmost.
+    let rightmost = rightmost || parentheses > 0;
+    match term {
+        SurfaceTerm::Variable { .. } => {}
+        SurfaceTerm::Abstraction { variable, body, .. } => {
+            binders.push(variable);
+            walk(
+                body,
+                source,
+                binders,
+                (definitions, macros),
+                (true, false),
+                lints,
+            );
+            binders.pop();
+        }
+        SurfaceTerm::Application {
+            function, argument, ..
+        } => {
+            walk(
+                function,
+                source,
+                binders,
+                (definitions, macros),
+                (false, false),
+                lints,
+            );
+            walk(
+                argument,
+                source,
+                binders,
+                (definitions, macros),
+                (rightmost, true),
+                lints,
+            );
+        }
+    }
+}
+
+/// Parse the program in the given source and check it for problems which, while not making it
+/// invalid, suggest that it could be written better, returning them in the order in which they
+/// appear in the source.
+///
+/// # Errors
+///
+/// Returns a `ParseError` if the source is not a valid program. Variables which are not bound are
+/// not reported, since they are left for scope checking to find.
+pub fn lint(source: &str) -> Result<Vec<Lint>, ParseError> {
+    let program = source.parse::<Program>()?;
+    let mut lints = Vec::new();
+    let mut definitions = Vec::new();
+    let mut macros = Vec::new();
+
+    for item in program.items() {
+        match item {
+            // NOTE: Imported modules are linted on their own, and their names are not known here.
+            Item::Import(_) => {}
+            Item::Definition(definition) => {
+                walk(
+                    &definition.term,
+                    source,
+                    &mut Vec::new(),
+                    (&definitions, &macros),
+                    (true, false),
+                    &mut lints,
+                );
+                if definitions.contains(&definition.name.as_str()) {
+                    lints.push(Lint {
+                        kind: LintKind::ShadowedName,
+                        span: definition.span,
+                        message: format!(
+                            "definition of {} shadows a definition of the same name",
+                            definition.name
+                        ),
+                    });
+                }
+                definitions.push(&definition.name);
+                macros.retain(|name| *name != definition.name);
+            }
+            Item::Assertion(assertion) => {
+                let terms = match &assertion.assertion {
+                    Assertion::Equivalent { left, right } => vec![left, right],
+                    Assertion::HasType { term, .. } => vec![term],
+                };
+                for term in terms {
+                    walk(
+                        term,
+                        source,
+                        &mut Vec::new(),
+                        (&definitions, &macros),
+                        (true, false),
+                        &mut lints,
+                    );
+                }
+            }
+            Item::Macro(r#macro) => {
+                walk(
+                    &r#macro.body,
+                    source,
+                    &mut r#macro.parameters.iter().map(String::as_str).collect(),
+                    (&definitions, &macros),
+                    (true, false),
+                    &mut lints,
+                );
+                definitions.push(&r#macro.name);
+                macros.push(&r#macro.name);
+            }
+        }
+    }
+    if let Some(term) = &program.term {
+        walk(
+            term,
+            source,
+            &mut Vec::new(),
+            (&definitions, &macros),
+            (true, false),
+            &mut lints,
+        );
+    }
+
+    lints.sort_by_key(|lint| (lint.span.start, lint.span.end, lint.kind));
+    Ok(lints)
+}
@@ -0,0 +1,244 @@
+//! Loading the modules named by `import` directives from disk, along with the `.kombic` files in
+//! which they are cached between runs.
+//!
+//! A module is loaded into an empty environment, whatever imports it, and its assertions are not
+//! checked, since they are checked whenever the module is run itself. Once loaded, its
+//! environment is written next to it, in a file with the extension `.kombic`, along with a hash of
+//! its contents and of the contents of every module it imports, directly or not. As long as none
+//! of those have changed, later runs read the environment back rather than parsing and checking
+//! the module again.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use kombi::environment::{Environment, Importer};
+use kombi::store::Digest;
+
+/// The extension of the files in which modules are cached.
+const EXTENSION: &str = "kombic";
+
+/// Return the hash of the given string, which, unlike the hashers of the standard library, is
+/// the same on every run and every platform, and is collision-resistant, so that a module which
+/// has changed is never taken for its cached self.
+fn hash(string: &str) -> Digest {
+    Digest::of(string.as_bytes())
+}
+
+/// Return the file named by the given path of an `import` directive in the file at `importing`,
+/// relative to which it is resolved.
+pub fn resolve(importing: &Path, path: &str) -> Result<PathBuf, String> {
+    importing
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(path)
+        .canonicalize()
+        .map_err(|e| e.to_string())
+}
+
+/// Return the description of the cycle of imports made by importing `path` from the last of
+/// `importing`, each of which imports the next, if it is one of them.
+pub fn cycle<'a>(importing: impl Iterator<Item = &'a Path>, path: &Path) -> Option<String> {
+    let mut cycle: Vec<_> = importing
+        .skip_while(|p| *p != path)
+        .map(|p| p.display().to_string())
+        .collect();
+    if cycle.is_empty() {
+        return None;
+    }
+    cycle.push(path.display().to_string());
+    Some(format!(
+        "it imports itself, by way of {}",
+        cycle.join(" -> ")
+    ))
+}
+
+/// Return whether every definition in the environment is closed and has the type recorded for it,
+/// and the body of every macro refers to no variable beyond its parameters and context, as they
+/// are in any environment loaded from source.
+fn is_sound(environment: &Environment) -> bool {
+    environment.iter().all(|definition| {
+        definition.term.is_closed()
+            && definition
+                .term
+                .type_of()
+                .is_ok_and(|ty| ty == definition.ty)
+    }) && environment.macros().all(|(_, r#macro)| {
+        r#macro.body.free_indices().iter().all(|idx| {
+            usize::try_from(*idx).is_ok_and(|idx| idx < r#macro.parameters + r#macro.context)
+        })
+    })
+}
+
+/// A module which has been loaded, as it is written to its cache.
+#[derive(Clone, Serialize, Deserialize)]
+struct Module {
+    /// The version of kombi which wrote the cache, since another may check terms differently.
+    version: String,
+    /// The hash of the contents of the module.
+    hash: Digest,
+    /// The hash of the contents of every module imported by this one, directly or not.
+    dependencies: BTreeMap<PathBuf, Digest>,
+    environment: Environment,
+}
+
+/// A module which is in the middle of being loaded.
+struct Frame {
+    path: PathBuf,
+    dependencies: BTreeMap<PathBuf, Digest>,
+}
+
+/// An `Importer` which reads modules from disk, resolving their paths relative to the file which
+/// imports them.
+pub struct Loader {
+    /// Whether modules are read from and written to their caches.
+    cache: bool,
+    /// The files being loaded, outermost first, each of which is importing the next.
+    stack: Vec<Frame>,
+    /// Every module loaded so far, so that each is only loaded once, however often it is imported.
+    loaded: HashMap<PathBuf, Module>,
+}
+
+impl Loader {
+    /// Create a `Loader` for the imports of the file at the given path.
+    pub fn new(path: &Path, cache: bool) -> Self {
+        Self {
+            cache,
+            stack: vec![Frame {
+                path: path.canonicalize().unwrap_or_else(|_| path.to_path_buf()),
+                dependencies: BTreeMap::new(),
+            }],
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Return the cached module at the given path, if its cache exists and is up to date.
+    ///
+    /// Whatever wrote the cache, its environment is checked as it would be if the module were
+    /// loaded from source, so that a corrupted cache is loaded again rather than trusted.
+    fn cached(path: &Path, hash: Digest) -> Option<Module> {
+        let cache = read_to_string(path.with_extension(EXTENSION)).ok()?;
+        // NOTE: As when reading terms from JSON, the recursion limit would reject deep terms.
+        let mut deserializer = serde_json::Deserializer::from_str(&cache);
+        deserializer.disable_recursion_limit();
+        let module = Module::deserialize(&mut deserializer).ok()?;
+        let fresh = module.version == env!("CARGO_PKG_VERSION")
+            && module.hash == hash
+            && module.dependencies.iter().all(|(dependency, hash)| {
+                read_to_string(dependency).is_ok_and(|source| self::hash(&source) == *hash)
+            });
+        (fresh && is_sound(&module.environment)).then_some(module)
+    }
+
+    /// Parse and check the module at the given path, whose contents are `source`.
+    fn load(&mut self, path: &Path, source: &str) -> Result<Module, String> {
+        self.stack.push(Frame {
+            path: path.to_path_buf(),
+            dependencies: BTreeMap::new(),
+        });
+        let mut environment = Environment::new();
+        let result = environment.load_with(source, None, self);
+        let frame = self
+            .stack
+            .pop()
+            .expect("the module should still be on the stack");
+        result.map_err(|e| format!("in file {}: {e}", path.display()))?;
+        Ok(Module {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            hash: hash(source),
+            dependencies: frame.dependencies,
+            environment,
+        })
+    }
+}
+
+impl Importer for Loader {
+    fn import(&mut self, path: &str) -> Result<Environment, String> {
+        let importing = &self
+            .stack
+            .last()
+            .expect("some file should be importing")
+            .path;
+        let path = resolve(importing, path)?;
+        if let Some(cycle) = cycle(self.stack.iter().map(|frame| frame.path.as_path()), &path) {
+            return Err(cycle);
+        }
+
+        let module = if let Some(module) = self.loaded.get(&path) {
+            module.clone()
+        } else {
+            let source = read_to_string(&path).map_err(|e| e.to_string())?;
+            let cached = if self.cache {
+                Self::cached(&path, hash(&source))
+            } else {
+                None
+            };
+            let module = if let Some(module) = cached {
+                module
+            } else {
+                let module = self.load(&path, &source)?;
+                // NOTE: The cache only saves time, so a module whose cache cannot be written,
+                // say because its directory is read-only, is simply loaded again next time.
+                if self.cache {
+                    let json = serde_json::to_string(&module)
+                        .expect("environments should be serializable");
+                    let _ = write(path.with_extension(EXTENSION), json);
+                }
+                module
+            };
+            self.loaded.insert(path.clone(), module.clone());
+            module
+        };
+
+        let frame = self
+            .stack
+            .last_mut()
+            .expect("some file should be importing");
+        frame.dependencies.insert(path, module.hash);
+        frame.dependencies.extend(module.dependencies);
+        Ok(module.environment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+    use std::path::Path;
+
+    use kombi::environment::Environment;
+
+    use super::{Loader, EXTENSION};
+
+    /// Load the program in the file at the given path, returning its environment.
+    fn load(path: &Path) -> Environment {
+        let mut environment = Environment::new();
+        let source = read_to_string(path).unwrap();
+        environment
+            .load_with(&source, None, &mut Loader::new(path, true))
+            .unwrap();
+        environment
+    }
+
+    #[test]
+    fn corrupted_caches_are_loaded_again() {
+        let directory = std::env::temp_dir().join(format!("kombi-loader-{}", std::process::id()));
+        create_dir_all(&directory).unwrap();
+        let (module, main) = (directory.join("lib.kombi"), directory.join("main.kombi"));
+        write(&module, "let I = \\x:A. x;\n").unwrap();
+        write(&main, "import \"lib.kombi\";\nlet J = I;\n").unwrap();
+        let expected = load(&main).get("J").unwrap().ty.clone();
+
+        // NOTE: The only variable in the module is the `x` of `I`, which is then left unbound.
+        let cache = module.with_extension(EXTENSION);
+        let json = read_to_string(&cache).unwrap();
+        let corrupted = json.replace("{\"Variable\":{\"idx\":0}}", "{\"Variable\":{\"idx\":5}}");
+        assert_ne!(corrupted, json);
+        write(&cache, corrupted).unwrap();
+
+        assert_eq!(load(&main).get("J").unwrap().ty, expected);
+        assert_eq!(read_to_string(&cache).unwrap(), json);
+        remove_dir_all(&directory).unwrap();
+    }
+}
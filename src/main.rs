@@ -1,75 +1,747 @@
 #![warn(clippy::pedantic)]
 
-mod parse;
-mod reduce;
-mod type_check;
-
-use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use kombi::arena::TermArena;
+use kombi::decode::{DecodeError, Encoding, Value};
+use kombi::derivation::ProofStyle;
+use kombi::environment::Environment;
+use kombi::explain::Explanation;
+use kombi::export::Assistant;
+use kombi::lint::Levels;
+use kombi::parse::{LambdaTerm, Type};
+use kombi::print::DisplayOptions;
+use kombi::profile::{Entry, Profile};
+use kombi::reduce::Equivalence;
+use kombi::untyped::UntypedTerm;
+
+use loader::Loader;
 
-use crate::parse::LambdaTerm;
+mod commands;
+mod loader;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about=None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a term between kombi and other formats
+    Convert(commands::convert::ConvertArgs),
+    /// Check that every way of evaluating terms agrees on random well-typed terms
+    Difftest(commands::difftest::DifftestArgs),
+    /// Write documentation for the definitions in programs
+    Doc(commands::doc::DocArgs),
+    /// Export the evaluation of a term for viewing elsewhere
+    Export(commands::export::ExportArgs),
+    /// Rewrite programs in a canonical style
+    Fmt(commands::fmt::FmtArgs),
+    /// Generate random well-typed terms
+    Gen(commands::generate::GenArgs),
+    /// Grade submissions by comparing them to a reference solution
+    Grade(commands::grade::GradeArgs),
+    /// Generate a syntax definition for editors from kombi's grammar
+    Grammar(commands::grammar::GrammarArgs),
+    /// Print the hashes which identify the terms of definitions, whatever they are named
+    Hash(commands::hash::HashArgs),
+    /// Write or check the interfaces of modules, which record the names and types they export
+    Interface(commands::interface::InterfaceArgs),
+    /// Check a program for likely mistakes and matters of style
+    Lint(commands::lint::LintArgs),
+    /// Run a language server, speaking the Language Server Protocol over stdin and stdout
+    Lsp,
+    /// Search for the smallest term βη-equivalent to a term
+    Min(commands::min::MinArgs),
+    /// Evaluate terms interactively, building up definitions over the course of a session
+    Repl(commands::repl::ReplArgs),
+    /// Check that random well-typed terms satisfy the metatheory of the calculus
+    Selftest(commands::selftest::SelftestArgs),
+    /// Print the type of the term at a position in a program, and the variables in scope there
+    Type(commands::type_at::TypeArgs),
+    /// Check a certificate of the evaluation of a term by replaying the redexes it records
+    Verify(commands::verify::VerifyArgs),
+}
+
+/// A format other than kombi's own syntax in which the evaluated term can be printed.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// A definition in Coq
+    Coq,
+    /// A definition in Agda
+    Agda,
+    /// A definition in Lean
+    Lean,
+    /// A lambda diagram, as drawn by John Tromp, in SVG
+    LambdaDiagram,
+}
+
+/// A format in which the graph of reductions of a term can be printed.
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+    /// Graphviz's DOT language
+    Dot,
+}
+
+/// A format in which the profile of an evaluation can be printed.
+#[derive(Clone, Copy, ValueEnum)]
+enum ProfileFormat {
+    /// A table of the abstractions contracted, the most expensive first
+    Report,
+    /// One line of folded stacks for each abstraction, weighted by the number of β-steps
+    /// contracting it, as read by flame graph tools
+    Folded,
+}
+
+/// The arguments used when no subcommand is given, in which case the term in <FILE> is evaluated.
+#[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct RunArgs {
     /// File containing the term to be evaluated
-    file: PathBuf,
+    #[arg(required = true)]
+    file: Option<PathBuf>,
     /// Evaluate the application of the term contained in <FILE> to the term contained in <ARG>
     #[arg(short, long)]
     arg: Option<PathBuf>,
 
+    /// Apply the term contained in <FILE> to the term contained in the given file, as its
+    /// parameter named <NAME>, which is the variable of one of the abstractions it begins with.
+    /// May be given more than once, for different parameters, in any order, and those not given
+    /// are left as parameters of the term evaluated
+    #[arg(long, value_name = "NAME=FILE", value_parser = parse_binding, conflicts_with = "arg")]
+    bind: Vec<(String, PathBuf)>,
+
+    /// Format of <FILE>, <ARG> and the files given to --bind. Types are inferred for untyped
+    /// formats, and terms which have no type are evaluated untyped, to normal form
+    #[arg(long, value_enum, default_value_t = commands::Format::Kombi)]
+    from: commands::Format,
+
+    /// Load the definitions of the bundled module with the given name, such as `combinators`,
+    /// before reading <FILE> and <ARG>. May be given more than once
+    #[arg(short, long = "prelude", value_name = "MODULE")]
+    prelude: Vec<String>,
+
+    /// Neither read nor write the `.kombic` files in which the modules imported by <FILE> and
+    /// <ARG> are cached
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Equivalence up to which the `assert t1 = t2;` directives in <FILE> and <ARG> are checked
+    #[arg(long, value_enum, default_value_t = Equivalence::Beta)]
+    equivalence: Equivalence,
+
+    /// Maximum number of β-steps taken when evaluating an untyped term, or of terms explored by
+    /// --dump-reduction
+    #[arg(long, default_value_t = 10_000)]
+    fuel: usize,
+
+    /// Rather than evaluating the term, print the graph of every term it can be reduced to, by any
+    /// order of reduction, in the given format
+    #[arg(long, value_name = "FORMAT", value_enum)]
+    dump_reduction: Option<DumpFormat>,
+
+    /// Rather than evaluating the term, print the derivation of its type as a LaTeX proof tree,
+    /// typeset with the given package
+    #[arg(
+        long,
+        value_name = "PACKAGE",
+        value_enum,
+        conflicts_with = "dump_reduction"
+    )]
+    derivation: Option<ProofStyle>,
+
+    /// Rather than printing the evaluated term, print the value it encodes in the given encoding,
+    /// which is `nat` for Church numerals, `bool` for Church booleans, `list` followed by the
+    /// encoding of the elements for Church-encoded lists, as in `list nat`, or `pair` followed by
+    /// the encodings of both values for Church-encoded pairs, as in `pair nat (list bool)`
+    #[arg(
+        long,
+        value_name = "ENCODING",
+        conflicts_with_all = ["dump_reduction", "derivation", "format"]
+    )]
+    decode: Option<Encoding>,
+
     /// Print evaluated term in debug format
     #[arg(short, long)]
     debug: bool,
+
+    /// Print terms and types using ASCII symbols rather than Unicode
+    #[arg(long)]
+    ascii: bool,
+
+    /// Print variables as de Bruijn indices rather than names
+    #[arg(long)]
+    indices: bool,
+
+    /// Parenthesize every compound subterm and subtype
+    #[arg(long)]
+    parenthesize: bool,
+
+    /// Omit the type annotations on abstractions
+    #[arg(long)]
+    omit_types: bool,
+
+    /// Print the evaluated term in the given format: either as a definition in the syntax of a
+    /// proof assistant, named after <FILE>, or as a diagram of its binding structure
+    #[arg(
+        short,
+        long,
+        value_enum,
+        conflicts_with_all = ["debug", "ascii", "indices", "parenthesize", "omit_types"]
+    )]
+    format: Option<OutputFormat>,
+
+    /// Break the evaluated term across lines to fit within <WIDTH> columns
+    #[arg(short, long)]
+    width: Option<usize>,
+
+    /// Type check and evaluate the term in an arena rather than as a tree of individually
+    /// allocated nodes
+    #[arg(long)]
+    arena: bool,
+
+    /// Reduce each argument which is certain to be used before substituting it, rather than at
+    /// every use. The result is β-equivalent to that of lazy evaluation, but may differ from it
+    #[arg(long, conflicts_with = "arena")]
+    strict: bool,
+
+    /// Write each closed subterm which occurs more than once only once before evaluation, bound
+    /// to a variable. Combined with --strict, each is then reduced only once, wherever it is used
+    #[arg(long)]
+    share: bool,
+
+    /// Remove every binding whose variable is never used, as in (λx:A. t) u where t does not use
+    /// x, from the term before evaluation and from its result
+    #[arg(long)]
+    prune: bool,
+
+    /// Print to stderr how many β-steps contracted each abstraction, and how large the arguments
+    /// they substituted were, attributed to the definitions in which the abstractions were written
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "report",
+        conflicts_with_all = ["arena", "strict"]
+    )]
+    profile: Option<ProfileFormat>,
+
+    /// Print to stderr a description of every β-step taken in evaluating the term, naming the
+    /// abstraction applied and what is substituted for its variable, and showing the redex before
+    /// and after it is contracted
+    #[arg(long, conflicts_with_all = ["arena", "strict", "profile"])]
+    explain: bool,
+
+    /// Write to the given file a certificate recording every redex contracted in evaluating the
+    /// term, which `kombi verify` checks the result against
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["strict", "share", "prune", "profile"])]
+    certificate: Option<PathBuf>,
+
+    #[command(flatten)]
+    levels: commands::LevelArgs,
+
+    /// Print size statistics for the term before and after evaluation to stderr
+    #[arg(short, long)]
+    stats: bool,
+}
+
+/// Parse a binding of a parameter to the term in a file, written as `NAME=FILE`.
+fn parse_binding(binding: &str) -> Result<(String, PathBuf), String> {
+    match binding.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((String::from(name), PathBuf::from(path)))
+        }
+        _ => Err(String::from("expected NAME=FILE")),
+    }
+}
+
+/// Print the size statistics of a term to stderr, prefixed by the given label.
+fn print_stats(label: &str, lambda_term: &LambdaTerm) {
+    eprintln!(
+        "{label}: size {}, depth {}, {} redexes, {} binders",
+        lambda_term.size(),
+        lambda_term.depth(),
+        lambda_term.redex_count(),
+        lambda_term.binder_count()
+    );
+}
+
+/// Return the given term with its dead bindings pruned and its common subterms shared, as
+/// requested by the given arguments, printing the size statistics after each pass if requested,
+/// or print the error and exit if it is not well-typed.
+fn prepare_or_exit(cli: &RunArgs, mut lambda_term: LambdaTerm) -> LambdaTerm {
+    if cli.prune {
+        lambda_term = lambda_term.prune();
+        if cli.stats {
+            print_stats("pruned", &lambda_term);
+        }
+    }
+    if cli.share {
+        lambda_term = lambda_term.share().unwrap_or_else(|e| {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        });
+        if cli.stats {
+            print_stats("shared", &lambda_term);
+        }
+    }
+    lambda_term
+}
+
+/// Load the program in the given string, read from the file at `path`, into a copy of the given
+/// `Environment` and return its term, printing the error and exiting if the program is invalid or
+/// has no term. The modules it imports are cached if `cache` is set.
+///
+/// The program's assertions are checked up to the given `Equivalence`, and if any of them fail,
+/// each is reported and the process exits. A program which has assertions, but no term, is taken
+/// to be a file of tests, so once they all hold, the process exits successfully.
+fn load_or_exit(
+    string: &str,
+    path: &Path,
+    environment: &Environment,
+    equivalence: Equivalence,
+    cache: bool,
+) -> LambdaTerm {
+    let program = environment
+        .clone()
+        .load_with(string, Some(equivalence), &mut Loader::new(path, cache))
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            exit(1);
+        });
+    for (span, failure) in &program.failures {
+        let (line, column) = commands::line_and_column(string, span.start);
+        eprintln!(
+            "{}:{line}:{column}: assertion failed: {failure}",
+            path.display()
+        );
+    }
+    if !program.failures.is_empty() {
+        eprintln!(
+            "{} of {} assertions in file {} failed",
+            program.failures.len(),
+            program.assertions,
+            path.display()
+        );
+        exit(1);
+    }
+
+    match program.term {
+        Some(lambda_term) => lambda_term,
+        None if program.assertions > 0 => {
+            println!(
+                "All {} assertions in file {} hold",
+                program.assertions,
+                path.display()
+            );
+            exit(0);
+        }
+        None => {
+            eprintln!(
+                "File {} does not contain a term to evaluate",
+                path.display()
+            );
+            exit(1);
+        }
+    }
+}
+
+/// Return the name under which the term in the given file is exported, which is the stem of the
+/// file name if that is a valid identifier, and `term` otherwise.
+fn definition_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| {
+            let mut chars = stem.chars();
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .unwrap_or("term")
+        .to_string()
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    // Read a lambda term from the file supplied by the user.
-    let lambda_term = read_to_string(&cli.file).map_or_else(
-        |e| {
-            eprintln!("Unable to open file {}: {}", cli.file.display(), e);
+    match cli.command {
+        Some(Command::Convert(args)) => commands::convert::run(&args),
+        Some(Command::Difftest(args)) => commands::difftest::run(&args),
+        Some(Command::Doc(args)) => commands::doc::run(&args),
+        Some(Command::Export(args)) => commands::export::run(&args),
+        Some(Command::Fmt(args)) => commands::fmt::run(&args),
+        Some(Command::Gen(args)) => commands::generate::run(&args),
+        Some(Command::Grade(args)) => commands::grade::run(&args),
+        Some(Command::Grammar(args)) => commands::grammar::run(&args),
+        Some(Command::Hash(args)) => commands::hash::run(&args),
+        Some(Command::Interface(args)) => commands::interface::run(&args),
+        Some(Command::Lint(args)) => commands::lint::run(&args),
+        Some(Command::Lsp) => commands::lsp::run(),
+        Some(Command::Min(args)) => commands::min::run(&args),
+        Some(Command::Repl(args)) => commands::repl::run(&args),
+        Some(Command::Selftest(args)) => commands::selftest::run(&args),
+        Some(Command::Type(args)) => commands::type_at::run(&args),
+        Some(Command::Verify(args)) => commands::verify::run(&args),
+        None => run(&cli.run),
+    }
+}
+
+/// Evaluate the term in the file supplied by the user, as requested by the given arguments.
+fn run(cli: &RunArgs) {
+    // NOTE: Clap ensures that a file is given whenever there is no subcommand.
+    let file = cli.file.as_deref().expect("a file should be given");
+
+    // Read a lambda term from the file supplied by the user, and if an argument was supplied,
+    // apply the term to it.
+    let prelude = commands::Prelude::load_or_exit(&cli.prelude);
+    let levels = cli.levels.levels(Levels::default());
+    let read_or_exit = |path| {
+        if cli.from == commands::Format::Kombi {
+            commands::lint_or_exit(path, &levels);
+        }
+        commands::read_or_exit(path, cli.from, &prelude, cli.equivalence, !cli.no_cache)
+    };
+    let input = read_or_exit(file);
+    let input = match &cli.arg {
+        Some(path) => input.apply(read_or_exit(path)),
+        None => input,
+    };
+    let input = if cli.bind.is_empty() {
+        input
+    } else {
+        let bindings = cli
+            .bind
+            .iter()
+            .map(|(name, path)| (name.clone(), read_or_exit(path)))
+            .collect();
+        input.bind(bindings).unwrap_or_else(|e| {
+            eprintln!(
+                "Unable to bind the arguments of the term in {}: {e}",
+                file.display()
+            );
             exit(1);
-        },
-        |s| LambdaTerm::from_str(&s),
-    );
+        })
+    };
 
-    // If an argument was supplied, apply it to the required term.
-    let lambda_term = if let Some(path) = cli.arg {
-        let arg = read_to_string(&path).map_or_else(
-            |e| {
-                eprintln!("Unable to open file {}: {}", path.display(), e);
+    // Untyped input is given the most general types it can have, and only where there are none
+    // is it evaluated untyped instead.
+    let lambda_term = match input.into_typed() {
+        Ok(lambda_term) => lambda_term,
+        Err((term, e)) => {
+            eprintln!("Term {term} cannot be given a type, so it is evaluated untyped: {e}");
+            run_untyped(cli, &term);
+            return;
+        }
+    };
+
+    if cli.stats {
+        print_stats("input", &lambda_term);
+    }
+
+    if let Some(format) = cli.dump_reduction {
+        if let Err(e) = lambda_term.type_of() {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        }
+        let graph = lambda_term.reduction_graph(cli.fuel);
+        if graph.truncated {
+            eprintln!(
+                "Stopped exploring after {} terms, so the graph is incomplete",
+                cli.fuel
+            );
+        }
+        match format {
+            DumpFormat::Dot => print!("{}", graph.to_dot()),
+        }
+        return;
+    }
+
+    if let Some(style) = cli.derivation {
+        match lambda_term.to_latex_derivation(style) {
+            Ok(derivation) => print!("{derivation}"),
+            Err(e) => {
+                eprintln!("Term {lambda_term} is not well-typed: {e}");
                 exit(1);
-            },
-            |s| LambdaTerm::from_str(&s),
-        );
-        LambdaTerm::Application {
-            function: Box::new(lambda_term),
-            argument: Box::new(arg),
+            }
         }
+        return;
+    }
+
+    if let Some(encoding) = &cli.decode {
+        if let Err(e) = lambda_term.type_of() {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        }
+        print_decoded(lambda_term.decode(encoding), &lambda_term, encoding);
+        return;
+    }
+
+    let lambda_term = prepare_or_exit(cli, lambda_term);
+
+    let (lambda_term, lambda_term_type) = evaluate_or_exit(cli, &prelude, &lambda_term);
+    if cli.stats {
+        print_stats("output", &lambda_term);
+    }
+
+    print_result(cli, file, &lambda_term, &lambda_term_type);
+}
+
+/// Type check and compute the β-reduction of the given term, either directly or in an arena, as
+/// requested by the given arguments, returning the result, with its dead bindings pruned if
+/// requested, along with its type, or print the error and exit if it is not well-typed.
+fn evaluate_or_exit(
+    cli: &RunArgs,
+    prelude: &commands::Prelude,
+    lambda_term: &LambdaTerm,
+) -> (LambdaTerm, Type) {
+    let (reduced, reduced_type) = if let Some(format) = cli.profile {
+        let lambda_term_type = lambda_term.type_of().unwrap_or_else(|e| {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        });
+        let (reduced, profile) = source_environment(cli, prelude).profile(lambda_term);
+        print_profile(&profile, format);
+        (reduced, lambda_term_type)
+    } else if cli.explain {
+        let lambda_term_type = lambda_term.type_of().unwrap_or_else(|e| {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        });
+        let (reduced, explanations) = source_environment(cli, prelude).explain(lambda_term);
+        print_explanations(cli, &explanations);
+        (reduced, lambda_term_type)
+    } else if cli.arena {
+        let mut arena = TermArena::new();
+        let id = arena.insert(lambda_term);
+        let lambda_term_type = arena.type_of(id).unwrap_or_else(|e| {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        });
+        let reduced = arena.beta_reduce(id);
+        (arena.term(reduced), arena.ty(lambda_term_type))
     } else {
-        lambda_term
+        let lambda_term_type = lambda_term.type_of().unwrap_or_else(|e| {
+            eprintln!("Term {lambda_term} is not well-typed: {e}");
+            exit(1);
+        });
+        let reduced = if cli.strict {
+            lambda_term.beta_reduce_strict()
+        } else {
+            lambda_term.beta_reduce()
+        };
+        (reduced, lambda_term_type)
     };
+    if let Some(path) = &cli.certificate {
+        write_certificate_or_exit(path, lambda_term, &reduced);
+    }
+    if cli.prune {
+        (reduced.prune(), reduced_type)
+    } else {
+        (reduced, reduced_type)
+    }
+}
 
-    let lambda_term_type = lambda_term.get_type().unwrap_or_else(|e| {
-        eprintln!("Term {lambda_term} is not well-typed: {e}");
+/// Write a certificate of the evaluation of the given term to the given file, printing the error
+/// and exiting if it cannot be written, or if replaying the redexes it records does not reach
+/// the result which was evaluated.
+fn write_certificate_or_exit(path: &Path, lambda_term: &LambdaTerm, reduced: &LambdaTerm) {
+    let certificate = lambda_term.certify();
+    if certificate.result != *reduced {
+        eprintln!(
+            "The certificate reduces the term to {}, rather than to the result {reduced}",
+            certificate.result
+        );
         exit(1);
-    });
+    }
+    if let Err(e) = std::fs::write(path, certificate.to_string()) {
+        eprintln!("Unable to write file {}: {e}", path.display());
+        exit(1);
+    }
+}
+
+/// Return the environment in which the term in the file supplied by the user is defined, in terms
+/// of whose definitions its evaluation is profiled and explained.
+fn source_environment(cli: &RunArgs, prelude: &commands::Prelude) -> Environment {
+    let mut environment = prelude.environment().clone();
+    let Some(file) = cli.file.as_deref() else {
+        return environment;
+    };
+    if cli.from == commands::Format::Kombi {
+        if let Ok(source) = std::fs::read_to_string(file) {
+            // NOTE: The file has already been loaded once, so it is known to be valid.
+            let mut loader = Loader::new(file, !cli.no_cache);
+            let _ = environment.load_with(&source, None, &mut loader);
+        }
+    }
+    environment
+}
+
+/// Print the given explanations of the steps of an evaluation to stderr, writing terms as
+/// requested by the given arguments.
+fn print_explanations(cli: &RunArgs, explanations: &[Explanation]) {
+    let options = DisplayOptions {
+        ascii: cli.ascii,
+        indices: cli.indices,
+        parenthesize: cli.parenthesize,
+        omit_types: cli.omit_types,
+        width: cli.width,
+    };
+    let lambda = if cli.ascii { "\\" } else { "λ" };
+    let arrow = if cli.ascii { "->" } else { "⟶" };
+    for (i, explanation) in explanations.iter().enumerate() {
+        let variable = &explanation.variable;
+        let abstraction = match &explanation.definition {
+            Some(definition) => format!("the abstraction {lambda}{variable} of {definition}"),
+            None => format!("the abstraction {lambda}{variable}"),
+        };
+        let argument = match &explanation.argument {
+            Some(name) => name.clone(),
+            None => String::from("its argument"),
+        };
+        let substitution = match explanation.uses {
+            0 => format!("which is discarded, since {variable} is never used"),
+            1 => format!("which replaces the one use of {variable}"),
+            uses => format!("which replaces each of the {uses} uses of {variable}"),
+        };
+        eprintln!(
+            "Step {}: apply {abstraction} to {argument}, {substitution}.",
+            i + 1
+        );
+        eprintln!("    {}", explanation.before.fmt_with(options));
+        eprintln!("  {arrow} {}", explanation.after.fmt_with(options));
+    }
+}
+
+/// Print the given profile to stderr, in the given format.
+fn print_profile(profile: &Profile, format: ProfileFormat) {
+    let origin = |entry: &Entry| match &entry.definition {
+        Some(definition) => definition.clone(),
+        None => String::from("the term"),
+    };
+    match format {
+        ProfileFormat::Report => {
+            let total = profile.total();
+            eprintln!(
+                "{} β-steps, substituting {} nodes",
+                total.steps, total.substituted
+            );
+            eprintln!("{:>10} {:>12}  abstraction", "steps", "substituted");
+            for entry in &profile.entries {
+                eprintln!(
+                    "{:>10} {:>12}  λ{} in {}",
+                    entry.cost.steps,
+                    entry.cost.substituted,
+                    entry.variable,
+                    origin(entry)
+                );
+            }
+        }
+        ProfileFormat::Folded => {
+            for entry in &profile.entries {
+                // NOTE: Stacks are separated by semicolons and weighted after a space, so neither
+                // may appear in the name of a frame.
+                let origin = origin(entry).replace([';', ' '], "_");
+                eprintln!("{origin};λ{} {}", entry.variable, entry.cost.steps);
+            }
+        }
+    }
+}
+
+/// Evaluate the untyped term, as requested by the given arguments, or report the first of them
+/// which only applies to typed terms and exit.
+fn run_untyped(cli: &RunArgs, term: &UntypedTerm) {
+    // NOTE: Untyped terms are always written in ASCII, so --ascii needs no handling here.
+    let typed_only = [
+        ("--indices", cli.indices),
+        ("--width", cli.width.is_some()),
+        ("--stats", cli.stats),
+    ];
+    if let Some((flag, _)) = typed_only.iter().find(|(_, given)| *given) {
+        eprintln!("Term {term} is evaluated untyped, so {flag} cannot be used");
+        exit(1);
+    }
+    let Some(normal_form) = term.normalize(cli.fuel) else {
+        eprintln!(
+            "Term {term} does not reach a normal form within {} steps",
+            cli.fuel
+        );
+        exit(1);
+    };
+    if let Some(encoding) = &cli.decode {
+        print_decoded(normal_form.decode(encoding), &normal_form, encoding);
+    } else if cli.format == Some(OutputFormat::LambdaDiagram) {
+        print!("{}", normal_form.to_lambda_diagram());
+    } else if cli.debug {
+        println!("{normal_form:?}");
+    } else {
+        println!("{normal_form}");
+    }
+}
 
-    // Compute the β-reduction of the lambda term.
-    let lambda_term = lambda_term.beta_reduce();
+/// Print the value decoded from the given term, or if there is none, report why the term does not
+/// encode a value in the given encoding and exit.
+fn print_decoded(value: Result<Value, DecodeError>, term: &impl Display, encoding: &Encoding) {
+    let value = value.unwrap_or_else(|e| {
+        eprintln!("Term {term} does not encode a value as {encoding}: {e}");
+        exit(1);
+    });
+    println!("{value}");
+}
 
+/// Print the β-reduced lambda term and its type, as requested by the given arguments.
+fn print_result(cli: &RunArgs, file: &Path, lambda_term: &LambdaTerm, lambda_term_type: &Type) {
     // Print the β-reduced lambda term. In debug mode, this will print the term in its derived
     // debug format to simplify debugging. When not in debug mode, variables will have their de
-    // Bruijn indices replaced with human-readable names. The output format will always be parsable
-    // as a valid lambda term, so computations can be chained together.
+    // Bruijn indices replaced with human-readable names. Unless it is written with de Bruijn
+    // indices or without its type annotations, the term between the parentheses always parses
+    // back to the same term, as the tests in `print` check, so computations can be chained
+    // together.
     if cli.debug {
         println!("({lambda_term:?}):{lambda_term_type:?}");
+    } else if let Some(format) = cli.format {
+        let assistant = match format {
+            OutputFormat::Coq => Assistant::Coq,
+            OutputFormat::Agda => Assistant::Agda,
+            OutputFormat::Lean => Assistant::Lean,
+            OutputFormat::LambdaDiagram => {
+                print!("{}", lambda_term.to_lambda_diagram());
+                return;
+            }
+        };
+        print!(
+            "{}",
+            lambda_term.export(
+                lambda_term_type,
+                &definition_name(file),
+                assistant,
+                cli.width
+            )
+        );
     } else {
-        println!("({lambda_term}):{lambda_term_type}");
+        let options = DisplayOptions {
+            ascii: cli.ascii,
+            indices: cli.indices,
+            parenthesize: cli.parenthesize,
+            omit_types: cli.omit_types,
+            width: cli.width,
+        };
+        println!(
+            "({}):{}",
+            lambda_term.fmt_with(options),
+            lambda_term_type.fmt_with(options)
+        );
     }
 }
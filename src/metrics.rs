@@ -0,0 +1,36 @@
+use crate::parse::LambdaTerm;
+
+impl LambdaTerm {
+    /// Return the number of nodes in the `LambdaTerm`.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.subterms().count()
+    }
+
+    /// Return the number of nodes on the longest path from the root of the `LambdaTerm` to one
+    /// of its variables.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.subterms().map(|s| s.path.len() + 1).max().unwrap_or(0)
+    }
+
+    /// Return the number of β-redexes in the `LambdaTerm`, i.e. the number of applications whose
+    /// function is an abstraction.
+    #[must_use]
+    pub fn redex_count(&self) -> usize {
+        self.subterms()
+            .filter(|s| {
+                matches!(s.term, LambdaTerm::Application { function, .. }
+                    if matches!(**function, LambdaTerm::Abstraction { .. }))
+            })
+            .count()
+    }
+
+    /// Return the number of abstractions in the `LambdaTerm`.
+    #[must_use]
+    pub fn binder_count(&self) -> usize {
+        self.subterms()
+            .filter(|s| matches!(s.term, LambdaTerm::Abstraction { .. }))
+            .count()
+    }
+}
@@ -1,32 +1,46 @@
-use std::collections::HashMap;
-use std::fmt::{Display, Formatter, Result};
-use std::process::exit;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
 
-use pest::error::{Error, ErrorVariant};
+use pest::error::{Error, ErrorVariant, InputLocation};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::interface::{Export, Interface};
+use crate::surface::{
+    Assertion, Program, ScopeError, Span, SurfaceAssertion, SurfaceDefinition, SurfaceImport,
+    SurfaceMacro, SurfaceTerm,
+};
+use crate::symbol::Symbol;
+use crate::untyped::UntypedTerm;
+
 #[derive(Parser)]
 #[grammar = "kombi.pest"]
 pub struct KombiParser;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
-    BaseType(String),
+    BaseType(Symbol),
     FunctionType(Box<Type>, Box<Type>),
 }
 
 impl Type {
     fn from_pair(pair: Pair<Rule>) -> Self {
         match pair.as_rule() {
-            Rule::base_type => Type::BaseType(pair.as_str().to_string()),
+            Rule::base_type => Type::BaseType(Symbol::new(pair.as_str())),
             Rule::function_type => {
+                // The arrow associates to the right, so each successive argument type is applied
+                // to everything which comes after it. A single type is not a function type at
+                // all, and is returned unchanged.
                 let mut pairs = pair.into_inner();
-                let return_type = Box::new(Self::from_pair(pairs.next_back().unwrap()));
-                let argument_type = Box::new(Self::from_pair(pairs.next_back().unwrap()));
-                
-                pairs.rfold(Type::FunctionType(argument_type, return_type), |a, p| {
+                let return_type = Self::from_pair(pairs.next_back().unwrap());
+
+                pairs.rfold(return_type, |a, p| {
                     Type::FunctionType(Box::new(Self::from_pair(p)), Box::new(a))
                 })
             }
@@ -35,118 +49,452 @@ impl Type {
     }
 }
 
-impl Display for Type {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match self {
-            Type::BaseType(name) => name.fmt(f),
-            Type::FunctionType(argument_type, return_type) => match **argument_type {
-                Type::BaseType(_) => write!(f, "{argument_type}→{return_type}"),
-                Type::FunctionType(..) => write!(f, "({argument_type})→{return_type}"),
+/// An error encountered while parsing a string, which can be displayed with reference to the
+/// offending part of the string.
+#[derive(Debug)]
+pub struct ParseError(Box<Error<Rule>>);
+
+impl ParseError {
+    /// Create a new `ParseError` with the given message, pointing at the given `Span` of
+    /// `source`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Span` does not lie within `source`.
+    #[must_use]
+    pub fn new(message: String, span: Span, source: &str) -> Self {
+        let span = pest::Span::new(source, span.start, span.end).expect("span should be valid");
+        Self(Box::new(Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            span,
+        )))
+    }
+
+    /// Return the `Span` of source at which the error was found, which is empty if the error is
+    /// at a single position.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self.0.location {
+            InputLocation::Pos(position) => Span {
+                start: position,
+                end: position,
             },
+            InputLocation::Span((start, end)) => Span { start, end },
         }
     }
+
+    /// Return the message describing the error, without reference to the source.
+    #[must_use]
+    pub fn message(&self) -> String {
+        self.0.variant.message().to_string()
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
+impl core::error::Error for ParseError {}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A representation of an arbitrary expression in the lambda calculus.
+///
+/// Variables are referred to by de Bruijn index. Abstractions remember the name which their
+/// variable was given in the source, but this is only a hint for printing, and has no bearing on
+/// the meaning of the term.
+///
+/// Subterms are reference-counted, so cloning a term, or any part of one, is cheap.
 pub enum LambdaTerm {
     Variable {
         idx: u64,
     },
     Abstraction {
+        variable: String,
         argument_type: Type,
-        body: Box<LambdaTerm>,
+        body: Rc<LambdaTerm>,
     },
     Application {
-        function: Box<LambdaTerm>,
-        argument: Box<LambdaTerm>,
+        function: Rc<LambdaTerm>,
+        argument: Rc<LambdaTerm>,
     },
 }
 
-impl LambdaTerm {
+/// Two `LambdaTerm`s are equal exactly when they are α-equivalent, that is, when they differ at
+/// most in the names given to their variables.
+impl PartialEq for LambdaTerm {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LambdaTerm::Variable { idx: i }, LambdaTerm::Variable { idx: j }) => i == j,
+            (
+                LambdaTerm::Abstraction {
+                    argument_type: s,
+                    body: t,
+                    ..
+                },
+                LambdaTerm::Abstraction {
+                    argument_type: u,
+                    body: v,
+                    ..
+                },
+            ) => s == u && t == v,
+            (
+                LambdaTerm::Application {
+                    function: s,
+                    argument: t,
+                },
+                LambdaTerm::Application {
+                    function: u,
+                    argument: v,
+                },
+            ) => s == u && t == v,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LambdaTerm {}
+
+impl FromStr for LambdaTerm {
+    type Err = ParseError;
+
     /// Create a new `LambdaTerm` from the given string, according to our grammar.
-    pub fn from_str(string: &str) -> Self {
-        let parsed = KombiParser::parse(Rule::program, string)
-            .unwrap_or_else(|e| {
-                eprintln!("{e}");
-                exit(1);
-            })
+    ///
+    /// The string may begin with any number of definitions, which are substituted into the term
+    /// wherever they are referred to, but must end with a term.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let program = string.parse::<Program>()?;
+        let to_parse_error = |e: ScopeError| ParseError::new(e.to_string(), e.span(), string);
+
+        let mut definitions = Vec::<(&str, LambdaTerm)>::new();
+        for definition in &program.definitions {
+            let term = definition
+                .term
+                .to_core_with(&|name| lookup(&definitions, name))
+                .map_err(to_parse_error)?;
+            definitions.push((&definition.name, term));
+        }
+
+        program
+            .term
+            .ok_or_else(|| {
+                ParseError::new(
+                    "expected a term".to_string(),
+                    Span {
+                        start: string.len(),
+                        end: string.len(),
+                    },
+                    string,
+                )
+            })?
+            .to_core_with(&|name| lookup(&definitions, name))
+            .map_err(to_parse_error)
+    }
+}
+
+/// Return the most recent definition with the given name.
+fn lookup(definitions: &[(&str, LambdaTerm)], name: &str) -> Option<LambdaTerm> {
+    definitions
+        .iter()
+        .rev()
+        .find(|(n, _)| *n == name)
+        .map(|(_, t)| t.clone())
+}
+
+impl FromStr for Type {
+    type Err = ParseError;
+
+    /// Create a new `Type` from the given string, according to our grammar.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let parsed = KombiParser::parse(Rule::type_expression, string)
+            .map_err(|e| ParseError(Box::new(e)))?
             .next()
             .unwrap();
-        LambdaTerm::from_pair(parsed, HashMap::new())
+        Ok(Type::from_pair(parsed))
     }
+}
 
-    fn from_pair(pair: Pair<Rule>, mut ctx: HashMap<String, u64>) -> Self {
-        match pair.as_rule() {
-            Rule::variable => {
-                let idx = *ctx.get(pair.as_str()).unwrap_or_else(|| {
-                    let e = Error::new_from_span(
-                        ErrorVariant::<()>::CustomError {
-                            message: format!("variable {} is not bound", pair.as_str()),
-                        },
-                        pair.as_span(),
-                    );
-                    eprintln!("{e}");
-                    exit(1);
-                });
-                LambdaTerm::Variable { idx }
-            }
-            Rule::abstraction => {
-                let mut pairs = pair.into_inner();
-                let variable = pairs.next().unwrap();
-                let argument_type = Type::from_pair(pairs.next().unwrap());
-                let body = pairs.next().unwrap();
+impl FromStr for SurfaceTerm {
+    type Err = ParseError;
 
-                // Update the context.
-                for v in ctx.values_mut() {
-                    *v += 1;
-                }
-                ctx.insert(variable.as_str().to_string(), 0);
+    /// Create a new `SurfaceTerm` from the given string, according to our grammar.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let parsed = KombiParser::parse(Rule::expression, string)
+            .map_err(|e| ParseError(Box::new(e)))?
+            .next()
+            .unwrap();
+        Ok(surface_term_from_pair(parsed))
+    }
+}
 
-                // Parse the body in the updated context.
-                LambdaTerm::Abstraction {
-                    argument_type,
-                    body: Box::new(LambdaTerm::from_pair(body, ctx)),
+impl FromStr for Program {
+    type Err = ParseError;
+
+    /// Create a new `Program` from the given string, according to our grammar.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let pairs =
+            KombiParser::parse(Rule::program, string).map_err(|e| ParseError(Box::new(e)))?;
+
+        let mut program = Program::default();
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::definition => {
+                    let span = span_of(&pair);
+                    let mut pairs = pair.into_inner().peekable();
+
+                    // A single space after the `///` of a comment is taken to separate it from
+                    // the text, as is conventional, rather than being part of it.
+                    let mut lines = Vec::new();
+                    while let Some(comment) = pairs.next_if(|p| p.as_rule() == Rule::doc_comment) {
+                        let text = &comment.as_str()["///".len()..];
+                        lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+                    }
+                    let doc = lines.join("\n");
+
+                    let name = pairs.next().unwrap().as_str().to_string();
+                    let term = surface_term_from_pair(pairs.next().unwrap());
+                    program.definitions.push(SurfaceDefinition {
+                        doc,
+                        name,
+                        term,
+                        span,
+                    });
+                }
+                Rule::assertion => {
+                    let span = span_of(&pair);
+                    let mut pairs = pair.into_inner();
+                    let term = surface_term_from_pair(pairs.next().unwrap());
+                    let claim = pairs.next().unwrap();
+                    let assertion = if claim.as_rule() == Rule::function_type {
+                        Assertion::HasType {
+                            term,
+                            ty: Type::from_pair(claim),
+                        }
+                    } else {
+                        Assertion::Equivalent {
+                            left: term,
+                            right: surface_term_from_pair(claim),
+                        }
+                    };
+                    program
+                        .assertions
+                        .push(SurfaceAssertion { assertion, span });
                 }
+                Rule::import => {
+                    let span = span_of(&pair);
+                    let path = pair.into_inner().next().unwrap().as_str().to_string();
+                    program.imports.push(SurfaceImport { path, span });
+                }
+                Rule::macro_definition => {
+                    let span = span_of(&pair);
+                    let mut pairs: Vec<_> = pair.into_inner().collect();
+                    let body = surface_term_from_pair(pairs.pop().unwrap());
+                    let mut names = pairs.into_iter().map(|p| p.as_str().to_string());
+                    program.macros.push(SurfaceMacro {
+                        name: names.next().unwrap(),
+                        parameters: names.collect(),
+                        body,
+                        span,
+                    });
+                }
+                Rule::EOI => {}
+                _ => program.term = Some(surface_term_from_pair(pair)),
             }
-            Rule::application => {
-                let mut pairs = pair.into_inner();
-                let function = Box::new(LambdaTerm::from_pair(pairs.next().unwrap(), ctx.clone()));
-                let argument = Box::new(LambdaTerm::from_pair(pairs.next().unwrap(), ctx.clone()));
+        }
+        Ok(program)
+    }
+}
 
-                pairs.fold(LambdaTerm::Application { function, argument }, |a, p| {
-                    LambdaTerm::Application {
-                        function: Box::new(a),
-                        argument: Box::new(LambdaTerm::from_pair(p, ctx.clone())),
-                    }
-                })
+impl FromStr for Interface {
+    type Err = ParseError;
+
+    /// Create a new `Interface` from the given string, as written by its `Display`
+    /// implementation.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let pairs =
+            KombiParser::parse(Rule::interface, string).map_err(|e| ParseError(Box::new(e)))?;
+
+        let mut interface = Interface::default();
+        for pair in pairs {
+            let rule = pair.as_rule();
+            let mut pairs = pair.into_inner();
+            match rule {
+                Rule::interface_definition => {
+                    let name = pairs.next().unwrap().as_str().to_string();
+                    let ty = Type::from_pair(pairs.next().unwrap());
+                    interface.exports.push((name, Export::Definition(ty)));
+                }
+                Rule::interface_macro => {
+                    let name = pairs.next().unwrap().as_str().to_string();
+                    interface.exports.push((name, Export::Macro(pairs.count())));
+                }
+                _ => {}
             }
-            _ => unreachable!(),
         }
+        Ok(interface)
+    }
+}
+
+impl FromStr for UntypedTerm {
+    type Err = ParseError;
+
+    /// Create a new `UntypedTerm` from the given string, written in the conventional notation of
+    /// the untyped lambda calculus, as in `\f x. f (f x)`.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        parse_untyped(string, &|_| None)
     }
 }
 
-impl Display for LambdaTerm {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match self {
-            LambdaTerm::Variable { idx } => {
-                write!(f, "{idx}")
+impl UntypedTerm {
+    /// Create a new `UntypedTerm` from the given string, just as `from_str` does, except that
+    /// variables which are not bound by any abstraction are looked up with `definitions`, and
+    /// replaced by the closed term it returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ParseError` if the string is not a valid term, or some variable is neither
+    /// bound nor defined.
+    pub fn parse_with(
+        string: &str,
+        definitions: &impl Fn(&str) -> Option<UntypedTerm>,
+    ) -> Result<Self, ParseError> {
+        parse_untyped(string, definitions)
+    }
+}
+
+fn parse_untyped(
+    string: &str,
+    definitions: &impl Fn(&str) -> Option<UntypedTerm>,
+) -> Result<UntypedTerm, ParseError> {
+    let parsed = KombiParser::parse(Rule::untyped_expression, string)
+        .map_err(|e| ParseError(Box::new(e)))?
+        .next()
+        .unwrap();
+    untyped_term_from_pair(parsed, &mut Vec::new(), definitions)
+        .map_err(|e| ParseError::new(e.to_string(), e.span(), string))
+}
+
+fn untyped_term_from_pair<'a>(
+    pair: Pair<'a, Rule>,
+    ctx: &mut Vec<&'a str>,
+    definitions: &impl Fn(&str) -> Option<UntypedTerm>,
+) -> Result<UntypedTerm, ScopeError> {
+    match pair.as_rule() {
+        Rule::variable => {
+            let name = pair.as_str();
+            match ctx.iter().rposition(|v| *v == name) {
+                Some(position) => Ok(UntypedTerm::Variable {
+                    idx: (ctx.len() - position - 1) as u64,
+                }),
+                None => definitions(name).ok_or_else(|| ScopeError::UnboundVariable {
+                    name: name.to_string(),
+                    span: span_of(&pair),
+                }),
             }
-            LambdaTerm::Abstraction {
+        }
+        Rule::untyped_abstraction => {
+            // Binding several variables at once is shorthand for nesting abstractions, one for
+            // each of them, so the body is parsed with all of them in scope.
+            let mut pairs = pair.into_inner();
+            let body = pairs.next_back().unwrap();
+            let variables: Vec<&str> = pairs.map(|p| p.as_str()).collect();
+
+            ctx.extend(&variables);
+            let body = untyped_term_from_pair(body, ctx, definitions);
+            ctx.truncate(ctx.len() - variables.len());
+
+            variables
+                .into_iter()
+                .rev()
+                .try_fold(body?, |body, variable| {
+                    Ok(UntypedTerm::Abstraction {
+                        variable: variable.to_string(),
+                        body: Box::new(body),
+                    })
+                })
+        }
+        Rule::untyped_application => {
+            let mut pairs = pair.into_inner();
+            let function = untyped_term_from_pair(pairs.next().unwrap(), ctx, definitions)?;
+            pairs.try_fold(function, |function, p| {
+                Ok(UntypedTerm::Application {
+                    function: Box::new(function),
+                    argument: Box::new(untyped_term_from_pair(p, ctx, definitions)?),
+                })
+            })
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn span_of(pair: &Pair<Rule>) -> Span {
+    Span {
+        start: pair.as_span().start(),
+        end: pair.as_span().end(),
+    }
+}
+
+/// Return the offset in `input` at which the atom of an application containing the term `pair`
+/// ends, given the offset at which the previous atom ended, so that the closing parentheses of a
+/// parenthesized atom are included in it, just as its opening parentheses are.
+fn atom_end(input: &str, previous_end: usize, pair: &Pair<Rule>) -> usize {
+    let is_whitespace = |c: char| matches!(c, '\x09'..='\x0d' | ' ');
+    let opened = input[previous_end..pair.as_span().start()]
+        .matches('(')
+        .count();
+    let mut end = pair.as_span().end();
+    for _ in 0..opened {
+        end += input[end..].find(|c| !is_whitespace(c)).unwrap() + 1;
+    }
+    end
+}
+
+fn surface_term_from_pair(pair: Pair<Rule>) -> SurfaceTerm {
+    let span = span_of(&pair);
+    match pair.as_rule() {
+        Rule::variable => SurfaceTerm::Variable {
+            name: pair.as_str().to_string(),
+            span,
+        },
+        Rule::abstraction => {
+            let mut pairs = pair.into_inner();
+            let variable = pairs.next().unwrap().as_str().to_string();
+            let argument_type = Type::from_pair(pairs.next().unwrap());
+            let body = Box::new(surface_term_from_pair(pairs.next().unwrap()));
+
+            SurfaceTerm::Abstraction {
+                variable,
                 argument_type,
                 body,
-            } => {
-                write!(f, "λ:{argument_type} {body}")
+                span,
             }
-            LambdaTerm::Application { function, argument } => {
-                if let LambdaTerm::Abstraction { .. } = **function {
-                    write!(f, "({function}) {argument}")
-                } else if let LambdaTerm::Application { .. } = **argument {
-                    write!(f, "{function} ({argument})")
-                } else {
-                    write!(f, "{function} {argument}")
+        }
+        Rule::application => {
+            let input = pair.get_input();
+            let mut end = span.start;
+            let mut pairs = pair.into_inner();
+            let function = pairs.next().unwrap();
+            end = atom_end(input, end, &function);
+            let function = surface_term_from_pair(function);
+
+            // Application associates to the left, so each successive argument is applied to
+            // everything which came before it. A single term is not an application at all, and
+            // is returned unchanged.
+            pairs.fold(function, |a, p| {
+                end = atom_end(input, end, &p);
+                let argument = surface_term_from_pair(p);
+                SurfaceTerm::Application {
+                    function: Box::new(a),
+                    argument: Box::new(argument),
+                    span: Span {
+                        start: span.start,
+                        end,
+                    },
                 }
-            }
+            })
         }
+        _ => unreachable!(),
     }
 }
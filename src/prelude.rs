@@ -0,0 +1,59 @@
+//! Modules of standard definitions which are bundled with kombi, so that they can be loaded into
+//! an `Environment` by name rather than written out again in every program.
+//!
+//! The type system has no polymorphism, so each definition is given a single type, written in
+//! terms of the base types `A`, `B` and `C`, wherever the definition is typeable at all. Terms
+//! which have no simple type, such as the fixed point combinator, are provided separately, for
+//! use with untyped terms, alongside the type erasures of the rest.
+
+/// A bundled module of definitions.
+#[derive(Debug, Clone, Copy)]
+pub struct Module {
+    pub name: &'static str,
+    /// One line describing what the module defines.
+    pub description: &'static str,
+    /// The definitions of the module, as a program in kombi's own syntax.
+    pub source: &'static str,
+    /// The definitions of the module which have no simple type, each as a name and a term in the
+    /// conventional notation of the untyped lambda calculus.
+    pub untyped: &'static [(&'static str, &'static str)],
+}
+
+/// Every bundled module.
+pub const MODULES: &[Module] = &[
+    Module {
+        name: "combinators",
+        description: "The combinators S, K, I, B, C and W, and, for untyped terms, Y",
+        source: include_str!("prelude/combinators.kombi"),
+        untyped: &[("Y", "\\f. (\\x. f (x x)) (\\x. f (x x))")],
+    },
+    Module {
+        name: "church",
+        description: "Church numerals, with their successor, sum and product, and, for untyped \
+                      terms, exponentiation and predecessor",
+        source: include_str!("prelude/church.kombi"),
+        untyped: &[
+            ("exp", "\\m n. n m"),
+            ("pred", "\\n f x. n (\\g h. h (g f)) (\\u. x) (\\u. u)"),
+        ],
+    },
+    Module {
+        name: "lists",
+        description: "Church-encoded lists, with their fold, map and append",
+        source: include_str!("prelude/lists.kombi"),
+        untyped: &[],
+    },
+    Module {
+        name: "data",
+        description: "Encodings of pairs, optional values and values of either of two types, \
+                      with the usual functions on each",
+        source: include_str!("prelude/data.kombi"),
+        untyped: &[],
+    },
+];
+
+/// Return the bundled module with the given name, if there is one.
+#[must_use]
+pub fn module(name: &str) -> Option<&'static Module> {
+    MODULES.iter().find(|module| module.name == name)
+}
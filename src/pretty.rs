@@ -0,0 +1,151 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// A document, describing text together with the places where it may be broken across lines,
+/// in the style of Wadler's "A prettier printer".
+///
+/// Documents are built out of text, which is never broken, and `Doc::Line`s, which are written as
+/// a single space if the `Doc::Group` enclosing them fits on the current line, and as a newline
+/// otherwise. Every line in a group is laid out the same way, but nested groups are laid out
+/// independently, so the outermost groups are broken first.
+#[derive(Debug, Clone)]
+pub enum Doc {
+    /// The empty document.
+    Nil,
+    /// Text containing no newlines.
+    Text(Cow<'static, str>),
+    /// A space, or a newline followed by the current indentation.
+    Line,
+    /// Documents written one after another.
+    Concat(Vec<Doc>),
+    /// A document in which newlines are followed by the given number of additional spaces.
+    Nest(usize, Box<Doc>),
+    /// A document whose lines are all written as spaces if it fits within the line width, and all
+    /// written as newlines otherwise.
+    Group(Box<Doc>),
+}
+
+/// How the `Doc::Line`s in a part of a document are being written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+impl Doc {
+    /// Return a document consisting of the given text, which must not contain newlines.
+    pub fn text(text: impl Into<Cow<'static, str>>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// Return the given documents written one after another.
+    #[must_use]
+    pub fn concat(docs: Vec<Doc>) -> Self {
+        Self::Concat(docs)
+    }
+
+    /// Return this document with the given amount of additional indentation after newlines.
+    #[must_use]
+    pub fn nest(self, indent: usize) -> Self {
+        Self::Nest(indent, Box::new(self))
+    }
+
+    /// Return this document as a group, which is written flat if it fits.
+    #[must_use]
+    pub fn group(self) -> Self {
+        Self::Group(Box::new(self))
+    }
+
+    /// Lay the document out within the given line width, writing it to `out`.
+    ///
+    /// Text which cannot be broken is allowed to run past the width, so every document can be
+    /// laid out. A width of `usize::MAX` never breaks any line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails.
+    pub fn render(&self, width: usize, out: &mut impl Write) -> fmt::Result {
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil => {}
+                Doc::Text(text) => {
+                    out.write_str(text)?;
+                    column += text.chars().count();
+                }
+                Doc::Line => match mode {
+                    Mode::Flat => {
+                        out.write_char(' ')?;
+                        column += 1;
+                    }
+                    Mode::Break => {
+                        out.write_char('\n')?;
+                        for _ in 0..indent {
+                            out.write_char(' ')?;
+                        }
+                        column = indent;
+                    }
+                },
+                Doc::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|d| (indent, mode, d)));
+                }
+                Doc::Nest(extra, doc) => stack.push((indent + extra, mode, doc)),
+                Doc::Group(doc) => {
+                    let mode = if mode == Mode::Flat
+                        || width == usize::MAX
+                        || fits(width.saturating_sub(column), doc, &stack)
+                    {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, doc));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return whether `doc`, written flat, followed by everything in `rest` up to its next newline,
+/// fits within `remaining` columns.
+fn fits(remaining: usize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut remaining = remaining;
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+
+    loop {
+        let Some((mode, doc)) = stack
+            .pop()
+            .or_else(|| rest.next().map(|(_, m, d)| (*m, *d)))
+        else {
+            return true;
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => {
+                let length = text.chars().count();
+                if length > remaining {
+                    return false;
+                }
+                remaining -= length;
+            }
+            Doc::Line => match mode {
+                Mode::Flat => {
+                    if remaining == 0 {
+                        return false;
+                    }
+                    remaining -= 1;
+                }
+                Mode::Break => return true,
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (mode, d))),
+            Doc::Nest(_, doc) | Doc::Group(doc) => stack.push((mode, doc)),
+        }
+    }
+}
@@ -0,0 +1,525 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::{LambdaTerm, Type};
+use crate::pretty::Doc;
+use crate::surface::{Assertion, Item, Program, Span, SurfaceTerm};
+
+/// Options controlling how terms and types are written out.
+///
+/// The default options produce the same output as the `Display` implementations: Unicode
+/// symbols, named variables, as few parentheses as possible, and type annotations on every
+/// abstraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DisplayOptions {
+    /// Write `\` and `->` rather than `λ` and `→`.
+    pub ascii: bool,
+    /// Write variables as de Bruijn indices rather than names. This has no effect on
+    /// `SurfaceTerm`s, whose variables have not yet been resolved to indices.
+    pub indices: bool,
+    /// Parenthesize every compound subterm and subtype, rather than only where necessary.
+    pub parenthesize: bool,
+    /// Omit the type annotations on abstractions.
+    pub omit_types: bool,
+    /// Break terms across lines to keep them within this many columns, where possible. This has
+    /// no effect on types, which are never broken.
+    pub width: Option<usize>,
+}
+
+impl DisplayOptions {
+    /// The options used by the canonical printers, `LambdaTerm::to_canonical_string` and
+    /// `Type::to_canonical_string`.
+    const CANONICAL: Self = Self {
+        ascii: true,
+        indices: false,
+        parenthesize: false,
+        omit_types: false,
+        width: None,
+    };
+
+    fn lambda(self) -> &'static str {
+        if self.ascii {
+            "\\"
+        } else {
+            "λ"
+        }
+    }
+
+    fn arrow(self) -> &'static str {
+        if self.ascii {
+            "->"
+        } else {
+            "→"
+        }
+    }
+}
+
+/// The number of columns by which the body of an abstraction, or the arguments of an
+/// application, are indented when they are broken onto their own lines.
+const INDENT: usize = 2;
+
+/// Wrap the given `Doc` in parentheses.
+fn parenthesized(doc: Doc) -> Doc {
+    Doc::concat(vec![Doc::text("("), doc, Doc::text(")")])
+}
+
+/// A term or type paired with the `DisplayOptions` with which it should be displayed, created by
+/// one of the `fmt_with` methods.
+pub struct WithOptions<'a, T: ?Sized> {
+    value: &'a T,
+    options: DisplayOptions,
+}
+
+impl Type {
+    /// Return a wrapper which displays the `Type` according to the given options.
+    #[must_use]
+    pub fn fmt_with(&self, options: DisplayOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+
+    /// Return the canonical representation of the `Type` as a string, written in ASCII, with as
+    /// few parentheses as possible.
+    #[must_use]
+    pub fn to_canonical_string(&self) -> String {
+        self.fmt_with(DisplayOptions::CANONICAL).to_string()
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, options: DisplayOptions, top: bool) -> fmt::Result {
+        match self {
+            Type::BaseType(name) => name.fmt(f),
+            Type::FunctionType(argument_type, return_type) => {
+                let parenthesize = options.parenthesize && !top;
+                if parenthesize {
+                    write!(f, "(")?;
+                }
+
+                // The arrow associates to the right, so only function types on the left of an
+                // arrow need parentheses.
+                if let Type::FunctionType(..) = **argument_type {
+                    if options.parenthesize {
+                        argument_type.write(f, options, false)?;
+                    } else {
+                        write!(f, "(")?;
+                        argument_type.write(f, options, true)?;
+                        write!(f, ")")?;
+                    }
+                } else {
+                    argument_type.write(f, options, false)?;
+                }
+                write!(f, "{}", options.arrow())?;
+                return_type.write(f, options, false)?;
+
+                if parenthesize {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for WithOptions<'_, Type> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.value.write(f, self.options, true)
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(DisplayOptions::default()).fmt(f)
+    }
+}
+
+impl SurfaceTerm {
+    /// Return a wrapper which displays the `SurfaceTerm` according to the given options.
+    #[must_use]
+    pub fn fmt_with(&self, options: DisplayOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+
+    /// Build the `Doc` for the `SurfaceTerm`, parenthesizing abstractions unless they extend to
+    /// the right end of the enclosing term, where they are unambiguous.
+    ///
+    /// When the `Doc` does not fit on one line, it is broken after the binder of an abstraction,
+    /// and between the arguments of an application, indenting what follows.
+    fn to_doc(&self, options: DisplayOptions, top: bool, rightmost: bool) -> Doc {
+        match self {
+            SurfaceTerm::Variable { name, .. } => Doc::text(name.clone()),
+            SurfaceTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+                ..
+            } => {
+                let parenthesize = !rightmost || (options.parenthesize && !top);
+
+                let mut binder = format!("{}{variable}", options.lambda());
+                if !options.omit_types {
+                    binder = format!("{binder}:{}", argument_type.fmt_with(options));
+                }
+                binder.push('.');
+                let doc = Doc::concat(vec![
+                    Doc::text(binder),
+                    Doc::concat(vec![Doc::Line, body.to_doc(options, false, true)]).nest(INDENT),
+                ])
+                .group();
+
+                if parenthesize {
+                    parenthesized(doc)
+                } else {
+                    doc
+                }
+            }
+            SurfaceTerm::Application { .. } => {
+                let parenthesize = options.parenthesize && !top;
+
+                // If the application is parenthesized, then its argument extends to the closing
+                // parenthesis, and so is rightmost regardless of where the application is.
+                let rightmost = rightmost || parenthesize;
+
+                // Unless every application is parenthesized, a function which is itself an
+                // application never needs parentheses, so the whole spine of arguments can be
+                // laid out together, rather than nesting deeper with every argument.
+                let mut arguments = Vec::new();
+                let mut function = self;
+                while let SurfaceTerm::Application {
+                    function: f,
+                    argument,
+                    ..
+                } = function
+                {
+                    arguments.push(argument.as_ref());
+                    function = f;
+                    if options.parenthesize {
+                        break;
+                    }
+                }
+
+                let count = arguments.len();
+                let mut docs = Vec::new();
+                for (i, argument) in arguments.into_iter().rev().enumerate() {
+                    // Only the last argument can extend to the right end of the application.
+                    let rightmost = rightmost && i + 1 == count;
+                    let doc = if let SurfaceTerm::Application { .. } = argument {
+                        if options.parenthesize {
+                            argument.to_doc(options, false, true)
+                        } else {
+                            parenthesized(argument.to_doc(options, true, true))
+                        }
+                    } else {
+                        argument.to_doc(options, false, rightmost)
+                    };
+                    docs.push(Doc::Line);
+                    docs.push(doc);
+                }
+
+                let doc = Doc::concat(vec![
+                    function.to_doc(options, false, false),
+                    Doc::concat(docs).nest(INDENT),
+                ])
+                .group();
+
+                if parenthesize {
+                    parenthesized(doc)
+                } else {
+                    doc
+                }
+            }
+        }
+    }
+}
+
+impl Display for WithOptions<'_, SurfaceTerm> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.value
+            .to_doc(self.options, true, true)
+            .render(self.options.width.unwrap_or(usize::MAX), f)
+    }
+}
+
+impl Display for SurfaceTerm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(DisplayOptions::default()).fmt(f)
+    }
+}
+
+impl Program {
+    /// Return a wrapper which displays the `Program` according to the given options, as source
+    /// which parses back to the same program.
+    ///
+    /// Every item is written on its own line, after any documentation comments, as is the term,
+    /// which is separated from the items by a blank line. Definitions and macros which do not fit
+    /// within the width are broken after the `=`, indenting the term.
+    #[must_use]
+    pub fn fmt_with(&self, options: DisplayOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl WithOptions<'_, Program> {
+    /// Write the given assertion on its own line, breaking it after the `=` or `:` if it does
+    /// not fit within the width, and indenting what follows.
+    fn fmt_assertion(&self, assertion: &Assertion, f: &mut Formatter<'_>) -> fmt::Result {
+        let (term, claim) = match assertion {
+            Assertion::Equivalent { left, right } => (
+                left,
+                Doc::concat(vec![
+                    Doc::text(" ="),
+                    Doc::concat(vec![Doc::Line, right.to_doc(self.options, true, true)])
+                        .nest(INDENT),
+                ]),
+            ),
+            Assertion::HasType { term, ty } => (
+                term,
+                Doc::concat(vec![
+                    Doc::text(" :"),
+                    Doc::concat(vec![
+                        Doc::Line,
+                        Doc::text(ty.fmt_with(self.options).to_string()),
+                    ])
+                    .nest(INDENT),
+                ]),
+            ),
+        };
+        Doc::concat(vec![
+            Doc::text("assert "),
+            term.to_doc(self.options, true, true),
+            claim,
+            Doc::text(";"),
+        ])
+        .group()
+        .render(self.options.width.unwrap_or(usize::MAX), f)?;
+        writeln!(f)
+    }
+}
+
+impl Display for WithOptions<'_, Program> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.options.width.unwrap_or(usize::MAX);
+        let items = self.value.items();
+        for item in &items {
+            let (head, term) = match item {
+                Item::Import(import) => {
+                    writeln!(f, "import \"{}\";", import.path)?;
+                    continue;
+                }
+                Item::Assertion(assertion) => {
+                    self.fmt_assertion(&assertion.assertion, f)?;
+                    continue;
+                }
+                Item::Definition(definition) => {
+                    for line in definition.doc.lines() {
+                        if line.is_empty() {
+                            writeln!(f, "///")?;
+                        } else {
+                            writeln!(f, "/// {line}")?;
+                        }
+                    }
+                    (format!("let {} =", definition.name), &definition.term)
+                }
+                Item::Macro(r#macro) => {
+                    let mut head = format!("macro {}", r#macro.name);
+                    for parameter in &r#macro.parameters {
+                        head.push(' ');
+                        head.push_str(parameter);
+                    }
+                    head.push_str(" =");
+                    (head, &r#macro.body)
+                }
+            };
+            Doc::concat(vec![
+                Doc::text(head),
+                Doc::concat(vec![Doc::Line, term.to_doc(self.options, true, true)]).nest(INDENT),
+                Doc::text(";"),
+            ])
+            .group()
+            .render(width, f)?;
+            writeln!(f)?;
+        }
+        if let Some(term) = &self.value.term {
+            if !items.is_empty() {
+                writeln!(f)?;
+            }
+            term.to_doc(self.options, true, true).render(width, f)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl LambdaTerm {
+    /// Return a wrapper which displays the `LambdaTerm` according to the given options.
+    #[must_use]
+    pub fn fmt_with(&self, options: DisplayOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+
+    /// Produce a `SurfaceTerm` in which every variable is named by its de Bruijn index and every
+    /// abstraction binds the empty name, so that it displays in de Bruijn notation.
+    fn to_indexed_surface(&self) -> SurfaceTerm {
+        match self {
+            LambdaTerm::Variable { idx } => SurfaceTerm::Variable {
+                name: idx.to_string(),
+                span: Span::default(),
+            },
+            LambdaTerm::Abstraction {
+                argument_type,
+                body,
+                ..
+            } => SurfaceTerm::Abstraction {
+                variable: String::new(),
+                argument_type: argument_type.clone(),
+                body: Box::new(body.to_indexed_surface()),
+                span: Span::default(),
+            },
+            LambdaTerm::Application { function, argument } => SurfaceTerm::Application {
+                function: Box::new(function.to_indexed_surface()),
+                argument: Box::new(argument.to_indexed_surface()),
+                span: Span::default(),
+            },
+        }
+    }
+
+    /// Produce a `SurfaceTerm` in which the variable bound by every abstraction is named after the
+    /// number of abstractions enclosing it, as in `x0`, `x1`, and so on, ignoring the names
+    /// recorded in the `LambdaTerm`.
+    fn to_canonical_surface(&self, depth: u64) -> SurfaceTerm {
+        match self {
+            LambdaTerm::Variable { idx } => SurfaceTerm::Variable {
+                name: if *idx < depth {
+                    format!("x{}", depth - idx - 1)
+                } else {
+                    format!("_{}", idx - depth)
+                },
+                span: Span::default(),
+            },
+            LambdaTerm::Abstraction {
+                argument_type,
+                body,
+                ..
+            } => SurfaceTerm::Abstraction {
+                variable: format!("x{depth}"),
+                argument_type: argument_type.clone(),
+                body: Box::new(body.to_canonical_surface(depth + 1)),
+                span: Span::default(),
+            },
+            LambdaTerm::Application { function, argument } => SurfaceTerm::Application {
+                function: Box::new(function.to_canonical_surface(depth)),
+                argument: Box::new(argument.to_canonical_surface(depth)),
+                span: Span::default(),
+            },
+        }
+    }
+
+    /// Return the canonical representation of the `LambdaTerm` as a string, which should be
+    /// preferred over `Display` wherever the output is to be read back by a program.
+    ///
+    /// The canonical representation is written in ASCII, with every variable named after its
+    /// binder's depth, so two terms have the same canonical representation exactly when they are
+    /// α-equivalent. For every closed term whose base types have valid names, parsing the
+    /// canonical representation produces the same term again, up to α-equivalence.
+    #[must_use]
+    pub fn to_canonical_string(&self) -> String {
+        self.to_canonical_surface(0)
+            .fmt_with(DisplayOptions::CANONICAL)
+            .to_string()
+    }
+}
+
+impl Display for WithOptions<'_, LambdaTerm> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let surface = if self.options.indices {
+            self.value.to_indexed_surface()
+        } else {
+            SurfaceTerm::from_core(self.value)
+        };
+        surface.fmt_with(self.options).fmt(f)
+    }
+}
+
+impl Display for LambdaTerm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(DisplayOptions::default()).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::DisplayOptions;
+    use crate::generate::TermGenerator;
+    use crate::parse::{LambdaTerm, Type};
+
+    /// The seeds of the generators from which the terms printed are drawn, fixed so that a
+    /// failure can be reproduced.
+    const SEEDS: core::ops::Range<u64> = 0..200;
+
+    /// Return a term drawn from the generator with the given seed, along with its type.
+    fn generated(seed: u64) -> (LambdaTerm, Type) {
+        let size = 2 + usize::try_from(seed % 30).expect("a size should fit in a usize");
+        TermGenerator::new(seed)
+            .term(size)
+            .expect("a term of every size should be found")
+    }
+
+    #[test]
+    fn canonical_term_round_trips() {
+        for seed in SEEDS {
+            let (term, ty) = generated(seed);
+            let printed = term.to_canonical_string();
+            let parsed = printed.parse::<LambdaTerm>().unwrap();
+            assert_eq!(parsed, term, "seed {seed}: {printed}");
+            let printed = ty.to_canonical_string();
+            assert_eq!(
+                printed.parse::<Type>().unwrap(),
+                ty,
+                "seed {seed}: {printed}"
+            );
+        }
+    }
+
+    #[test]
+    fn displayed_term_round_trips() {
+        let options = [
+            DisplayOptions::default(),
+            DisplayOptions {
+                ascii: true,
+                parenthesize: true,
+                ..DisplayOptions::default()
+            },
+            DisplayOptions {
+                width: Some(20),
+                ..DisplayOptions::default()
+            },
+        ];
+        for seed in SEEDS {
+            let (term, ty) = generated(seed);
+            for options in options {
+                let printed = term.fmt_with(options).to_string();
+                let parsed = printed.parse::<LambdaTerm>().unwrap();
+                assert_eq!(parsed, term, "seed {seed}, {options:?}: {printed}");
+                let printed = ty.fmt_with(options).to_string();
+                let parsed = printed.parse::<Type>().unwrap();
+                assert_eq!(parsed, ty, "seed {seed}, {options:?}: {printed}");
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Profiling evaluation, by attributing the work done by each β-step to the abstraction which it
+//! contracts, and so to the definition in which that abstraction was written.
+//!
+//! Definitions are substituted into terms wherever they are referred to, so a term does not
+//! remember which of its parts came from which definition. Instead, each closed subterm which is
+//! the term of some definition is taken to have come from it, and everything else from the term
+//! itself.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::environment::Environment;
+use crate::parse::LambdaTerm;
+
+/// What separates the name of the variable of an abstraction from the name of the definition it
+/// came from, while evaluation is being profiled. It cannot appear in the name of either.
+const TAG: char = '\0';
+
+/// The work attributed to some abstraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cost {
+    /// The number of β-steps contracting the abstraction.
+    pub steps: usize,
+    /// The total size of the arguments substituted for its variable, counted once for every
+    /// occurrence of the variable they were substituted for.
+    pub substituted: usize,
+}
+
+/// The work attributed to one abstraction by a `Profile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The name of the definition in which the abstraction was written, or `None` if it was
+    /// written in the term itself.
+    pub definition: Option<String>,
+    /// The name of the variable of the abstraction.
+    pub variable: String,
+    pub cost: Cost,
+}
+
+/// The work done in evaluating a term, broken down by the abstractions contracted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// An entry for every abstraction which was contracted at least once, the most expensive
+    /// first. Abstractions with the same variable in the same definition share an entry.
+    pub entries: Vec<Entry>,
+}
+
+impl Profile {
+    /// Return the total work done.
+    #[must_use]
+    pub fn total(&self) -> Cost {
+        self.entries
+            .iter()
+            .fold(Cost::default(), |total, entry| Cost {
+                steps: total.steps + entry.cost.steps,
+                substituted: total.substituted + entry.cost.substituted,
+            })
+    }
+}
+
+/// The definitions of an environment, as they are tagged, grouped by size.
+type Tagged<'a> = BTreeMap<usize, Vec<(&'a LambdaTerm, LambdaTerm)>>;
+
+/// Return the given term with the variable of each of its abstractions tagged with `owner`,
+/// except in the closed subterms which are the terms of the definitions in `tagged`, which are
+/// replaced by the definitions as they were tagged, along with the size of the term and one more
+/// than the largest de Bruijn index free in it, or zero if it is closed.
+fn tag(term: &LambdaTerm, owner: &str, tagged: &Tagged<'_>) -> (LambdaTerm, usize, u64) {
+    let (result, size, free) = match term {
+        LambdaTerm::Variable { idx } => (term.clone(), 1, idx + 1),
+        LambdaTerm::Abstraction {
+            variable,
+            argument_type,
+            body,
+        } => {
+            let (body, size, free) = tag(body, owner, tagged);
+            let abstraction = LambdaTerm::Abstraction {
+                variable: format!("{variable}{TAG}{owner}"),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body),
+            };
+            (abstraction, size + 1, free.saturating_sub(1))
+        }
+        LambdaTerm::Application { function, argument } => {
+            let (function, function_size, function_free) = tag(function, owner, tagged);
+            let (argument, argument_size, argument_free) = tag(argument, owner, tagged);
+            let application = LambdaTerm::Application {
+                function: Rc::new(function),
+                argument: Rc::new(argument),
+            };
+            (
+                application,
+                function_size + argument_size + 1,
+                function_free.max(argument_free),
+            )
+        }
+    };
+    // NOTE: Later definitions come last, so take precedence over any earlier ones which happen to
+    // have the same term.
+    let definition = tagged
+        .get(&size)
+        .filter(|_| free == 0)
+        .and_then(|candidates| candidates.iter().rev().find(|(t, _)| *t == term));
+    match definition {
+        Some((_, definition)) => (definition.clone(), size, free),
+        None => (result, size, free),
+    }
+}
+
+/// Return the given term with the tags removed from the variables of its abstractions.
+pub(crate) fn untag(term: &LambdaTerm) -> LambdaTerm {
+    match term {
+        LambdaTerm::Variable { .. } => term.clone(),
+        LambdaTerm::Abstraction {
+            variable,
+            argument_type,
+            body,
+        } => LambdaTerm::Abstraction {
+            variable: variable
+                .split_once(TAG)
+                .map_or(variable.as_str(), |(variable, _)| variable)
+                .to_string(),
+            argument_type: argument_type.clone(),
+            body: Rc::new(untag(body)),
+        },
+        LambdaTerm::Application { function, argument } => LambdaTerm::Application {
+            function: Rc::new(untag(function)),
+            argument: Rc::new(untag(argument)),
+        },
+    }
+}
+
+/// Return the name of the variable of an abstraction in a tagged term, along with the name of
+/// the definition in which the abstraction was written, or `None` if it was written in the term
+/// itself.
+pub(crate) fn split_tag(variable: &str) -> (&str, Option<&str>) {
+    let (variable, definition) = variable.split_once(TAG).unwrap_or((variable, ""));
+    (variable, Some(definition).filter(|d| !d.is_empty()))
+}
+
+impl Environment {
+    /// Return the given term with the variable of each of its abstractions tagged with the name
+    /// of the definition of the environment in which it was written, as far as can be told.
+    pub(crate) fn tag(&self, term: &LambdaTerm) -> LambdaTerm {
+        let mut tagged = Tagged::new();
+        for definition in self.iter() {
+            let (term, size, _) = tag(&definition.term, &definition.name, &tagged);
+            tagged
+                .entry(size)
+                .or_default()
+                .push((&definition.term, term));
+        }
+        tag(term, "", &tagged).0
+    }
+
+    /// Apply β-reduction to the given well-typed term one step at a time, following exactly the
+    /// same strategy as `LambdaTerm::beta_reduce`, and return the result along with a `Profile`
+    /// attributing each step to the definition of the environment from which the abstraction it
+    /// contracts came.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path to a redex found by `next_redex` does not lead to one, which should
+    /// never happen.
+    #[must_use]
+    pub fn profile(&self, term: &LambdaTerm) -> (LambdaTerm, Profile) {
+        let mut term = self.tag(term);
+        let mut costs = BTreeMap::<String, Cost>::new();
+        while let Some(path) = term.next_redex() {
+            let mut redex = &term;
+            for _ in &path {
+                let LambdaTerm::Application { function, .. } = redex else {
+                    panic!("the path should only lead through functions");
+                };
+                redex = function;
+            }
+            let LambdaTerm::Application { function, argument } = redex else {
+                panic!("the path should lead to a redex");
+            };
+            let LambdaTerm::Abstraction { variable, body, .. } = function.as_ref() else {
+                panic!("the path should lead to a redex");
+            };
+            let cost = costs.entry(variable.clone()).or_default();
+            cost.steps += 1;
+            cost.substituted += argument.size() * body.uses_of(0);
+            term = term
+                .contract_at(&path)
+                .expect("the path should lead to a redex");
+        }
+
+        let mut entries: Vec<_> = costs
+            .into_iter()
+            .map(|(tagged, cost)| {
+                let (variable, definition) = split_tag(&tagged);
+                Entry {
+                    definition: definition.map(ToString::to_string),
+                    variable: variable.to_string(),
+                    cost,
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| core::cmp::Reverse(entry.cost));
+        (untag(&term), Profile { entries })
+    }
+}
@@ -1,32 +1,40 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
 use crate::parse::LambdaTerm;
+use crate::traverse::Step;
+use crate::zipper::TermZipper;
 
-impl LambdaTerm {
-    /// Replace every instance of the variable with de Bruijn index `replacement_idx` in the
-    /// current scope with a `LambdaTerm`.
-    fn replace_idx(&self, new: Self, replacement_idx: u64) -> Self {
-        match self {
-            LambdaTerm::Variable { idx } => {
-                if idx == &replacement_idx {
-                    new
-                } else {
-                    self.clone()
-                }
-            }
-            LambdaTerm::Abstraction {
-                argument_type,
-                body,
-            } => LambdaTerm::Abstraction {
-                argument_type: argument_type.clone(),
-                body: Box::new(body.replace_idx(new, replacement_idx + 1)),
-            },
-            LambdaTerm::Application { function, argument } => LambdaTerm::Application {
-                function: Box::new(function.replace_idx(new.clone(), replacement_idx)),
-                argument: Box::new(argument.replace_idx(new, replacement_idx)),
-            },
-        }
+/// A hook which is invoked at every step of a reduction performed by `LambdaTerm::reduce_with`.
+pub trait ReductionObserver {
+    /// Called after each β-step with the path to the redex which was contracted and the whole term
+    /// resulting from contracting it. Returning `ControlFlow::Break` stops the reduction.
+    fn step(&mut self, redex: &[Step], term: &LambdaTerm) -> ControlFlow<()>;
+}
+
+impl<F: FnMut(&[Step], &LambdaTerm) -> ControlFlow<()>> ReductionObserver for F {
+    fn step(&mut self, redex: &[Step], term: &LambdaTerm) -> ControlFlow<()> {
+        self(redex, term)
     }
+}
 
+/// A notion of when two terms are considered to be the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Equivalence {
+    /// Terms are equivalent if they differ only in the names of their bound variables.
+    Alpha,
+    /// Terms are equivalent if they have the same β-normal form.
+    #[default]
+    Beta,
+    /// Terms are equivalent if they have the same βη-normal form.
+    BetaEta,
+}
+
+impl LambdaTerm {
     /// Apply β-reduction to a given expression in the lambda calculus.
+    #[must_use]
     pub fn beta_reduce(&self) -> Self {
         match self {
             LambdaTerm::Application { function, argument } => {
@@ -36,9 +44,7 @@ impl LambdaTerm {
                 // β-reduced itself prior to substitution. β-reduction is then applied
                 // post-substitution.
                 match function.beta_reduce() {
-                    LambdaTerm::Abstraction { body, .. } => {
-                        body.replace_idx(*argument.clone(), 0).beta_reduce()
-                    }
+                    LambdaTerm::Abstraction { body, .. } => body.open(argument).beta_reduce(),
                     _ => {
                         // NOTE: This would only be reachable when β-reducing terms which contain
                         // free variables, which are not allowed in our grammar.
@@ -49,4 +55,215 @@ impl LambdaTerm {
             _ => self.clone(),
         }
     }
+
+    /// Apply β-reduction as `beta_reduce` does, except that an argument which the body of the
+    /// function it is applied to demands, as found by `demanded`, is reduced before it is
+    /// substituted, rather than again at each of its uses.
+    ///
+    /// This does the same work as `beta_reduce` at most, and often far less where arguments are
+    /// used many times, but the arguments substituted into the result are left reduced, so while
+    /// the result is β-equivalent to that of `beta_reduce`, and is also an abstraction, it need
+    /// not be the same term.
+    #[must_use]
+    pub fn beta_reduce_strict(&self) -> Self {
+        match self {
+            LambdaTerm::Application { function, argument } => match function.beta_reduce_strict() {
+                LambdaTerm::Abstraction { body, .. } if body.demanded().contains(&0) => body
+                    .open(&argument.beta_reduce_strict())
+                    .beta_reduce_strict(),
+                LambdaTerm::Abstraction { body, .. } => body.open(argument).beta_reduce_strict(),
+                _ => {
+                    // NOTE: As in `beta_reduce`, this would only be reachable when β-reducing
+                    // terms which contain free variables.
+                    unreachable!()
+                }
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Contract every β-redex in the `LambdaTerm` whose abstraction never uses its variable, as
+    /// in `(λx:A. t) u` where `t` does not refer to `x`, dropping the argument unreduced.
+    ///
+    /// Such a binding does nothing but make the term larger, and since contracting it can only
+    /// remove redexes, the result is β-equivalent to the `LambdaTerm` and has the same type.
+    #[must_use]
+    pub fn prune(&self) -> Self {
+        match self {
+            LambdaTerm::Variable { .. } => self.clone(),
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.prune()),
+            },
+            LambdaTerm::Application { function, argument } => match function.prune() {
+                // NOTE: Pruning the body first means that a binding whose only uses were in dead
+                // bindings of its own is found to be dead too.
+                LambdaTerm::Abstraction { body, .. } if body.uses_of(0) == 0 => body.shift(-1, 0),
+                function => LambdaTerm::Application {
+                    function: Rc::new(function),
+                    argument: Rc::new(argument.prune()),
+                },
+            },
+        }
+    }
+
+    /// If the `LambdaTerm` is a β-redex, return the result of contracting it.
+    #[must_use]
+    pub fn contract(&self) -> Option<Self> {
+        match self {
+            LambdaTerm::Application { function, argument } => match function.as_ref() {
+                LambdaTerm::Abstraction { body, .. } => Some(body.open(argument)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Return the path to the redex which `beta_reduce` would contract next, or `None` if the
+    /// `LambdaTerm` is already fully reduced.
+    ///
+    /// Since evaluation is lazy, this is always the redex at the head of the term, found by
+    /// following functions down from the root.
+    #[must_use]
+    pub fn next_redex(&self) -> Option<Vec<Step>> {
+        let mut path = Vec::new();
+        let mut term = self;
+        while let LambdaTerm::Application { function, .. } = term {
+            if let LambdaTerm::Abstraction { .. } = **function {
+                return Some(path);
+            }
+            path.push(Step::Function);
+            term = function;
+        }
+        None
+    }
+
+    /// Contract the redex at the end of the given path, returning the resulting term, or `None`
+    /// if there is no redex there.
+    #[must_use]
+    pub fn contract_at(&self, path: &[Step]) -> Option<Self> {
+        let mut zipper = TermZipper::at_path(self.clone(), path)?;
+        let contracted = zipper.focus().contract()?;
+        zipper.replace(contracted);
+        Some(zipper.into_term())
+    }
+
+    /// Perform a single step of β-reduction, returning the path to the redex contracted and the
+    /// resulting term, or `None` if the `LambdaTerm` is already fully reduced.
+    #[must_use]
+    pub fn step(&self) -> Option<(Vec<Step>, Self)> {
+        let path = self.next_redex()?;
+        let term = self.contract_at(&path)?;
+        Some((path, term))
+    }
+
+    /// Apply β-reduction one step at a time, following exactly the same strategy as
+    /// `beta_reduce`, and invoking `observer` after every step.
+    ///
+    /// The result is the term reached when there are no more redexes to contract, or when the
+    /// observer stops the reduction, whichever comes first.
+    #[must_use]
+    pub fn reduce_with(&self, observer: &mut impl ReductionObserver) -> Self {
+        let mut term = self.clone();
+        while let Some((path, next)) = term.step() {
+            term = next;
+            if observer.step(&path, &term).is_break() {
+                break;
+            }
+        }
+        term
+    }
+
+    /// Return the β-normal form of the `LambdaTerm`, in which, unlike in the result of
+    /// `beta_reduce`, there are no redexes left anywhere, even under abstractions.
+    ///
+    /// Every well-typed term has a β-normal form, which this always finds, but it may be very much
+    /// larger than the term itself.
+    #[must_use]
+    pub fn normalize(&self) -> Self {
+        match self {
+            LambdaTerm::Variable { .. } => self.clone(),
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.normalize()),
+            },
+            LambdaTerm::Application { function, argument } => match function.normalize() {
+                LambdaTerm::Abstraction { body, .. } => body.open(argument).normalize(),
+                function => LambdaTerm::Application {
+                    function: Rc::new(function),
+                    argument: Rc::new(argument.normalize()),
+                },
+            },
+        }
+    }
+
+    /// Contract every η-redex in the `LambdaTerm`, replacing each abstraction `λx:A. f x`, where
+    /// `x` does not occur in `f`, by `f`.
+    ///
+    /// Contracting an η-redex never creates a β-redex in a term which has none, so the result of
+    /// applying this to a β-normal form is its βη-normal form.
+    #[must_use]
+    pub fn eta_reduce(&self) -> Self {
+        match self {
+            LambdaTerm::Variable { .. } => self.clone(),
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => {
+                let body = body.eta_reduce();
+                if let LambdaTerm::Application { function, argument } = &body {
+                    if matches!(**argument, LambdaTerm::Variable { idx: 0 })
+                        && function.uses_of(0) == 0
+                    {
+                        return function.shift(-1, 0);
+                    }
+                }
+                LambdaTerm::Abstraction {
+                    variable: variable.clone(),
+                    argument_type: argument_type.clone(),
+                    body: Rc::new(body),
+                }
+            }
+            LambdaTerm::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.eta_reduce()),
+                argument: Rc::new(argument.eta_reduce()),
+            },
+        }
+    }
+
+    /// Return whether the `LambdaTerm` and `other`, which must both be well-typed, are
+    /// βη-equivalent, that is, whether they have the same βη-normal form.
+    #[must_use]
+    pub fn is_beta_eta_equivalent(&self, other: &Self) -> bool {
+        self.normalize().eta_reduce() == other.normalize().eta_reduce()
+    }
+
+    /// Return the normal form of the well-typed `LambdaTerm` under the given `Equivalence`, which
+    /// is α-equivalent to the normal form of every term equivalent to it.
+    #[must_use]
+    pub fn normal_form(&self, equivalence: Equivalence) -> Self {
+        match equivalence {
+            Equivalence::Alpha => self.clone(),
+            Equivalence::Beta => self.normalize(),
+            Equivalence::BetaEta => self.normalize().eta_reduce(),
+        }
+    }
+
+    /// Return whether the `LambdaTerm` and `other`, which must both be well-typed, are equivalent
+    /// under the given `Equivalence`.
+    #[must_use]
+    pub fn is_equivalent(&self, other: &Self, equivalence: Equivalence) -> bool {
+        self.normal_form(equivalence) == other.normal_form(equivalence)
+    }
 }
@@ -0,0 +1,207 @@
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::type_check::TypeError;
+
+/// The name given to the variables bound by `LambdaTerm::share`.
+const SHARED: &str = "shared";
+
+/// A node of a term, with its children replaced by the numbers of their classes in a `Partition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Variable(u64),
+    Abstraction(usize, usize),
+    Application(usize, usize),
+}
+
+/// What is known about one class of α-equivalent subterms.
+#[derive(Debug, Clone, Copy)]
+struct Class {
+    /// The number of times a member of the class occurs in the term.
+    count: usize,
+    size: usize,
+    /// One more than the largest de Bruijn index occurring free in the members of the class, or
+    /// zero if they are closed.
+    free: u64,
+}
+
+/// The subterms of a term, partitioned into classes of α-equivalent ones.
+#[derive(Default)]
+struct Partition {
+    ids: BTreeMap<Key, usize>,
+    classes: Vec<Class>,
+    /// The types of the abstractions in the term, each of which is referred to by its position.
+    types: Vec<Type>,
+}
+
+impl Partition {
+    /// Return the key of the given node, whose children are members of the given classes.
+    fn key(&mut self, term: &LambdaTerm, children: (usize, usize)) -> Key {
+        match term {
+            LambdaTerm::Variable { idx } => Key::Variable(*idx),
+            LambdaTerm::Abstraction { argument_type, .. } => {
+                let ty = self.types.iter().position(|ty| ty == argument_type);
+                let ty = ty.unwrap_or_else(|| {
+                    self.types.push(argument_type.clone());
+                    self.types.len() - 1
+                });
+                Key::Abstraction(ty, children.0)
+            }
+            LambdaTerm::Application { .. } => Key::Application(children.0, children.1),
+        }
+    }
+
+    /// Return the class of the given node, whose children are members of the given classes,
+    /// counting it as one more occurrence.
+    fn insert(&mut self, term: &LambdaTerm, children: (usize, usize)) -> usize {
+        let key = self.key(term, children);
+        if let Some(&id) = self.ids.get(&key) {
+            self.classes[id].count += 1;
+            return id;
+        }
+        let class = match key {
+            Key::Variable(idx) => Class {
+                count: 1,
+                size: 1,
+                free: idx + 1,
+            },
+            Key::Abstraction(_, body) => Class {
+                count: 1,
+                size: self.classes[body].size + 1,
+                free: self.classes[body].free.saturating_sub(1),
+            },
+            Key::Application(function, argument) => Class {
+                count: 1,
+                size: self.classes[function].size + self.classes[argument].size + 1,
+                free: self.classes[function].free.max(self.classes[argument].free),
+            },
+        };
+        self.classes.push(class);
+        self.ids.insert(key, self.classes.len() - 1);
+        self.classes.len() - 1
+    }
+
+    /// Sort every subterm of the given term into its class, returning the class of the term.
+    fn classify(&mut self, term: &LambdaTerm) -> usize {
+        let children = match term {
+            LambdaTerm::Variable { .. } => (0, 0),
+            LambdaTerm::Abstraction { body, .. } => (self.classify(body), 0),
+            LambdaTerm::Application { function, argument } => {
+                (self.classify(function), self.classify(argument))
+            }
+        };
+        self.insert(term, children)
+    }
+
+    /// Return the class of the closed subterms which are most worth sharing, if sharing any of
+    /// them would make the term smaller.
+    fn most_shared(&self) -> Option<usize> {
+        // NOTE: Replacing every occurrence with a variable saves all but one node of each, but
+        // binding the subterm costs an abstraction and an application, as well as the subterm
+        // itself.
+        let saving = |class: &Class| (class.count * (class.size - 1)).checked_sub(class.size + 2);
+        self.classes
+            .iter()
+            .enumerate()
+            .filter(|(_, class)| class.free == 0 && class.count > 1)
+            .filter_map(|(id, class)| Some((saving(class).filter(|s| *s > 0)?, class.size, id)))
+            .max()
+            .map(|(.., id)| id)
+    }
+
+    /// Return the given term, which lies under `depth` abstractions, as the body of an
+    /// abstraction around the whole term, with every member of the given class replaced by the
+    /// variable it binds, along with the class of the term itself. The first member replaced is
+    /// kept in `shared`.
+    fn replace(
+        &mut self,
+        term: &LambdaTerm,
+        class: usize,
+        depth: u64,
+        shared: &mut Option<LambdaTerm>,
+    ) -> (LambdaTerm, usize) {
+        let (replaced, children) = match term {
+            // The free variables of the term are now bound outside one more abstraction.
+            LambdaTerm::Variable { idx } if *idx >= depth => {
+                (LambdaTerm::Variable { idx: idx + 1 }, (0, 0))
+            }
+            LambdaTerm::Variable { .. } => (term.clone(), (0, 0)),
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => {
+                let (body, id) = self.replace(body, class, depth + 1, shared);
+                let abstraction = LambdaTerm::Abstraction {
+                    variable: variable.clone(),
+                    argument_type: argument_type.clone(),
+                    body: Rc::new(body),
+                };
+                (abstraction, (id, 0))
+            }
+            LambdaTerm::Application { function, argument } => {
+                let (function, f) = self.replace(function, class, depth, shared);
+                let (argument, a) = self.replace(argument, class, depth, shared);
+                let application = LambdaTerm::Application {
+                    function: Rc::new(function),
+                    argument: Rc::new(argument),
+                };
+                (application, (f, a))
+            }
+        };
+        // NOTE: Every subterm of the term has already been classified, so this looks up the class
+        // of the subterm as it was before anything was replaced in it.
+        let key = self.key(term, children);
+        let id = self.ids[&key];
+        if id == class {
+            shared.get_or_insert_with(|| term.clone());
+            (LambdaTerm::Variable { idx: depth }, id)
+        } else {
+            (replaced, id)
+        }
+    }
+}
+
+impl LambdaTerm {
+    /// Return a term β-equivalent to the `LambdaTerm` in which every closed subterm which occurs
+    /// more than once, and is large enough that doing so makes the term smaller, is written only
+    /// once, bound to a variable by a redex around the whole term.
+    ///
+    /// Since `beta_reduce` substitutes arguments without reducing them, this saves no work on
+    /// its own, but it does under `beta_reduce_strict`, which reduces each shared subterm which is
+    /// demanded only once, rather than once for every occurrence.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if some closed subterm which would be shared is not well-typed,
+    /// since its variable could then not be given a type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subterm chosen to be shared is not found in the term, which should never
+    /// happen.
+    pub fn share(&self) -> Result<Self, TypeError> {
+        let mut term = self.clone();
+        loop {
+            let mut classes = Partition::default();
+            classes.classify(&term);
+            let Some(class) = classes.most_shared() else {
+                return Ok(term);
+            };
+            let mut shared = None;
+            let (body, _) = classes.replace(&term, class, 0, &mut shared);
+            let shared = shared.expect("the shared subterm should occur in the term");
+            term = LambdaTerm::Application {
+                function: Rc::new(LambdaTerm::Abstraction {
+                    variable: String::from(SHARED),
+                    argument_type: shared.type_of()?,
+                    body: Rc::new(body),
+                }),
+                argument: Rc::new(shared),
+            };
+        }
+    }
+}
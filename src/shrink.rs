@@ -0,0 +1,58 @@
+use alloc::vec::Vec;
+
+use crate::parse::LambdaTerm;
+use crate::zipper::TermZipper;
+
+impl LambdaTerm {
+    /// Return every term which can be obtained from the `LambdaTerm` by one simplifying change,
+    /// smallest first.
+    ///
+    /// A simplifying change either contracts a single β-redex, or replaces some subterm with a
+    /// closed term occurring inside it. Every candidate is strictly smaller than the original,
+    /// and closed if the original is, but need not be well-typed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some path produced by `subterms` does not lead to a subterm, which should never
+    /// happen.
+    #[must_use]
+    pub fn shrink(&self) -> Vec<Self> {
+        let size = self.size();
+        let mut candidates = Vec::new();
+
+        for subterm in self.subterms() {
+            if let Some(contracted) = self.contract_at(&subterm.path) {
+                candidates.push(contracted);
+            }
+
+            // Closed terms mean the same thing wherever they are, so can be moved anywhere
+            // without adjusting their indices.
+            for inner in subterm.term.subterms().skip(1) {
+                if inner.term.is_closed() {
+                    let mut zipper = TermZipper::at_path(self.clone(), &subterm.path)
+                        .expect("path to a subterm should be valid");
+                    zipper.replace(inner.term.clone());
+                    candidates.push(zipper.into_term());
+                }
+            }
+        }
+
+        candidates.retain(|c| c.size() < size);
+        candidates.sort_by_key(LambdaTerm::size);
+        candidates
+    }
+
+    /// Return a term, as small as could be found, which is `interesting`, by repeatedly replacing
+    /// the `LambdaTerm` with the first of its `shrink` candidates which is still interesting.
+    ///
+    /// The `LambdaTerm` itself is assumed to be interesting, and is returned unchanged if none of
+    /// its candidates are.
+    #[must_use]
+    pub fn minimize(&self, mut interesting: impl FnMut(&LambdaTerm) -> bool) -> Self {
+        let mut term = self.clone();
+        while let Some(smaller) = term.shrink().into_iter().find(|c| interesting(c)) {
+            term = smaller;
+        }
+        term
+    }
+}
@@ -0,0 +1,523 @@
+//! A content-addressed store of definitions, in which each term is identified by its `Digest`,
+//! a hash of its structure which ignores the names given to its variables.
+//!
+//! Since variables are referred to by de Bruijn index and every name in a definition has been
+//! resolved to the term it stands for, two definitions have the same digest exactly when they
+//! define the same term, whatever they are called and whatever the names they refer to are
+//! called. This lets the same term be recognized when it appears in several files under
+//! different names, and a definition be checked for having changed without comparing its text.
+
+use alloc::collections::BTreeMap;
+#[cfg(feature = "serde")]
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "serde")]
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::ptr;
+use core::str::FromStr;
+
+use crate::environment::Definition;
+use crate::parse::{LambdaTerm, Type};
+#[cfg(feature = "serde")]
+use crate::table::TermTable;
+
+/// The SHA-256 round constants.
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// The initial SHA-256 hash value.
+const INITIAL_HASH: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+/// Return the SHA-256 hash of the given bytes.
+///
+/// # Panics
+///
+/// Panics if the message is longer than 2⁶¹ bytes, which should never happen.
+// NOTE: The working variables are named as they are in the standard, FIPS 180-4.
+#[allow(clippy::many_single_char_names)]
+fn sha256(message: &[u8]) -> [u8; 32] {
+    // NOTE: The message is padded with a single set bit, then zeroes, and then its length in
+    // bits, to a whole number of 64-byte blocks.
+    let length = u64::try_from(message.len())
+        .ok()
+        .and_then(|length| length.checked_mul(8))
+        .expect("a message should not be so long");
+    let mut padded = Vec::with_capacity(message.len() + 72);
+    padded.extend_from_slice(message);
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&length.to_be_bytes());
+
+    let mut hash = INITIAL_HASH;
+    for block in padded.chunks_exact(64) {
+        let mut schedule = [0u32; 64];
+        for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = schedule[i - 15].rotate_right(7)
+                ^ schedule[i - 15].rotate_right(18)
+                ^ (schedule[i - 15] >> 3);
+            let s1 = schedule[i - 2].rotate_right(17)
+                ^ schedule[i - 2].rotate_right(19)
+                ^ (schedule[i - 2] >> 10);
+            schedule[i] = schedule[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = hash;
+        for (constant, word) in ROUND_CONSTANTS.iter().zip(schedule) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*constant)
+                .wrapping_add(word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (word, added) in hash.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(hash) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// A SHA-256 hash of a term or type, which is the same for α-equivalent terms, or of any other
+/// bytes.
+///
+/// A digest is written as 64 hexadecimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// Return the `Digest` of the given bytes, which is simply their SHA-256 hash.
+    #[must_use]
+    pub fn of(bytes: &[u8]) -> Self {
+        Self(sha256(bytes))
+    }
+}
+
+/// The string could not be parsed as a `Digest`, since it is not 64 hexadecimal digits.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DigestError;
+
+impl Display for DigestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a digest must be 64 hexadecimal digits")
+    }
+}
+
+impl Error for DigestError {}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl FromStr for Digest {
+    type Err = DigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DigestError);
+        }
+        let mut digest = [0; 32];
+        for (byte, digits) in digest.iter_mut().zip(s.as_bytes().chunks_exact(2)) {
+            let digits = core::str::from_utf8(digits).map_err(|_| DigestError)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| DigestError)?;
+        }
+        Ok(Self(digest))
+    }
+}
+
+// NOTE: Digests are written as strings, rather than as numbers, so that they can be the keys of a
+// JSON object, and are not mangled by readers which take every number to be a double.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Digest {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Digest {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Hash a node with the given tag whose contents are the given bytes, which are each either a
+/// leaf or the digest of a child.
+fn node(tag: u8, contents: &[&[u8]]) -> Digest {
+    let mut message = vec![tag];
+    for content in contents {
+        message.extend_from_slice(content);
+    }
+    Digest(sha256(&message))
+}
+
+impl Type {
+    /// Return the `Digest` of the `Type`.
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        match self {
+            Type::BaseType(name) => node(b'T', &[name.as_bytes()]),
+            Type::FunctionType(argument_type, return_type) => {
+                node(b'>', &[&argument_type.digest().0, &return_type.digest().0])
+            }
+        }
+    }
+}
+
+impl LambdaTerm {
+    /// Return the `Digest` of the `LambdaTerm`, which depends on its structure, its de Bruijn
+    /// indices and its type annotations, but not on the names given to its variables.
+    ///
+    /// A subterm shared between several parts of the term, as the definitions of a program are, is
+    /// only hashed once.
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        self.digests()[&ptr::from_ref(self)]
+    }
+
+    /// Return the `Digest` of every subterm of the `LambdaTerm`, including itself, by address.
+    pub(crate) fn digests(&self) -> BTreeMap<*const LambdaTerm, Digest> {
+        // NOTE: Each subterm is hashed from the digests of its children, as in a Merkle tree, so
+        // that the digest of a shared subterm can be kept by address and used wherever it
+        // appears. Each subterm is taken off the stack twice, first to visit its children and
+        // then, once they have been, itself.
+        let mut digests = BTreeMap::<*const LambdaTerm, Digest>::new();
+        let mut stack = vec![(self, false)];
+        while let Some((term, finished)) = stack.pop() {
+            let key = ptr::from_ref(term);
+            if digests.contains_key(&key) {
+                continue;
+            }
+            let child = |child: &LambdaTerm| digests[&ptr::from_ref(child)].0;
+            match (term, finished) {
+                (LambdaTerm::Variable { idx }, _) => {
+                    digests.insert(key, node(b'v', &[&idx.to_le_bytes()]));
+                }
+                (LambdaTerm::Abstraction { body, .. }, false) => {
+                    stack.extend([(term, true), (body.as_ref(), false)]);
+                }
+                (LambdaTerm::Application { function, argument }, false) => {
+                    stack.extend([(term, true), (function, false), (argument, false)]);
+                }
+                (
+                    LambdaTerm::Abstraction {
+                        argument_type,
+                        body,
+                        ..
+                    },
+                    true,
+                ) => {
+                    let digest = node(b'l', &[&argument_type.digest().0, &child(body)]);
+                    digests.insert(key, digest);
+                }
+                (LambdaTerm::Application { function, argument }, true) => {
+                    let digest = node(b'a', &[&child(function), &child(argument)]);
+                    digests.insert(key, digest);
+                }
+            }
+        }
+        digests
+    }
+}
+
+/// A term kept in a `Store`, along with its type.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoredTerm {
+    pub term: LambdaTerm,
+    pub ty: Type,
+}
+
+/// What became of a definition inserted into a `Store`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Insertion {
+    /// The digest of the term defined.
+    pub digest: Digest,
+    /// The digest which the name referred to before, if it referred to any.
+    pub previous: Option<Digest>,
+    /// Whether the term was not already in the store, under any name.
+    pub new: bool,
+}
+
+impl Insertion {
+    /// Return whether the name referred to some other term before.
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        self.previous
+            .is_some_and(|previous| previous != self.digest)
+    }
+}
+
+/// A collection of terms, each kept once under its `Digest`, together with the names which refer
+/// to them.
+///
+/// A name refers to the term it was last inserted with, but every term ever inserted is kept, so
+/// that a definition which is changed back is recognized as the term it was before.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SharedStore", try_from = "SharedStore")
+)]
+pub struct Store {
+    terms: BTreeMap<Digest, StoredTerm>,
+    names: BTreeMap<String, Digest>,
+}
+
+/// A `Store` as it is serialized, with every term in it written in a single `TermTable`, so that
+/// the terms of definitions which refer to one another are not each written out in full.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SharedStore {
+    table: TermTable,
+    /// The position in the table and the type of the term with each digest.
+    terms: BTreeMap<Digest, (usize, Type)>,
+    names: BTreeMap<String, Digest>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Store> for SharedStore {
+    fn from(store: Store) -> Self {
+        let (table, positions) = TermTable::new(store.terms.values().map(|t| &t.term));
+        Self {
+            terms: store
+                .terms
+                .into_iter()
+                .zip(positions)
+                .map(|((digest, stored), position)| (digest, (position, stored.ty)))
+                .collect(),
+            names: store.names,
+            table,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SharedStore> for Store {
+    type Error = String;
+
+    fn try_from(shared: SharedStore) -> Result<Self, Self::Error> {
+        if let Some(digest) = shared
+            .names
+            .values()
+            .find(|digest| !shared.terms.contains_key(digest))
+        {
+            return Err(format!("there is no term with digest {digest}"));
+        }
+        let positions: Vec<_> = shared
+            .terms
+            .values()
+            .map(|(position, _)| *position)
+            .collect();
+        let terms = shared.table.terms(&positions).map_err(|e| e.to_string())?;
+        Ok(Self {
+            terms: shared
+                .terms
+                .into_iter()
+                .zip(terms)
+                .map(|((digest, (_, ty)), term)| (digest, StoredTerm { term, ty }))
+                .collect(),
+            names: shared.names,
+        })
+    }
+}
+
+impl Store {
+    /// Create an empty `Store`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert the term of the given definition, and make the given name refer to it, returning
+    /// what became of it.
+    pub fn insert(&mut self, name: &str, definition: &Definition) -> Insertion {
+        let digest = definition.term.digest();
+        let new = !self.terms.contains_key(&digest);
+        if new {
+            self.terms.insert(
+                digest,
+                StoredTerm {
+                    term: definition.term.clone(),
+                    ty: definition.ty.clone(),
+                },
+            );
+        }
+        let previous = self.names.insert(String::from(name), digest);
+        Insertion {
+            digest,
+            previous,
+            new,
+        }
+    }
+
+    /// Return the term with the given digest, if it is in the store.
+    #[must_use]
+    pub fn get(&self, digest: Digest) -> Option<&StoredTerm> {
+        self.terms.get(&digest)
+    }
+
+    /// Return the digest of the term which the given name refers to, if it refers to any.
+    #[must_use]
+    pub fn resolve(&self, name: &str) -> Option<Digest> {
+        self.names.get(name).copied()
+    }
+
+    /// Return an iterator over the names which refer to the term with the given digest, in
+    /// order.
+    pub fn names_of(&self, digest: Digest) -> impl Iterator<Item = &str> {
+        self.names
+            .iter()
+            .filter(move |(_, d)| **d == digest)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Return an iterator over every term in the store, along with its digest, in order of
+    /// digest.
+    pub fn iter(&self) -> impl Iterator<Item = (Digest, &StoredTerm)> {
+        self.terms.iter().map(|(digest, term)| (*digest, term))
+    }
+
+    /// Return the number of terms in the store.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Return whether the store contains no terms.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::Digest;
+
+    #[test]
+    fn sha256_matches_known_answers() {
+        // NOTE: These are the examples of FIPS 180-2, the last of which is two blocks long.
+        let known = [
+            (
+                "",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (
+                "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopq\
+                 klmnopqrlmnopqrsmnopqrstnopqrstu",
+                "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+            ),
+        ];
+        for (message, digest) in known {
+            assert_eq!(Digest::of(message.as_bytes()).to_string(), digest);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::rc::Rc;
+
+use crate::parse::LambdaTerm;
+
+impl LambdaTerm {
+    /// Shift every free variable in the `LambdaTerm` with de Bruijn index at least `cutoff` by
+    /// `amount`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if shifting would make some de Bruijn index negative.
+    #[must_use]
+    pub fn shift(&self, amount: i64, cutoff: u64) -> Self {
+        match self {
+            LambdaTerm::Variable { idx } => {
+                if *idx >= cutoff {
+                    LambdaTerm::Variable {
+                        idx: idx
+                            .checked_add_signed(amount)
+                            .expect("shifting should not make a de Bruijn index negative"),
+                    }
+                } else {
+                    self.clone()
+                }
+            }
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.shift(amount, cutoff + 1)),
+            },
+            LambdaTerm::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.shift(amount, cutoff)),
+                argument: Rc::new(argument.shift(amount, cutoff)),
+            },
+        }
+    }
+
+    /// Replace every occurrence of the free variable with de Bruijn index `idx` with
+    /// `replacement`.
+    ///
+    /// The free variables of `replacement` are shifted as it passes under abstractions, so they
+    /// are never captured.
+    #[must_use]
+    pub fn substitute(&self, idx: u64, replacement: &LambdaTerm) -> Self {
+        self.substitute_at_depth(idx, replacement, 0)
+    }
+
+    fn substitute_at_depth(&self, idx: u64, replacement: &LambdaTerm, depth: u64) -> Self {
+        match self {
+            LambdaTerm::Variable { idx: i } => {
+                if *i == idx + depth {
+                    // NOTE: Shifting is skipped at depth zero, where it would be a no-op anyway,
+                    // since this is by far the most common case when reducing closed terms.
+                    if depth == 0 {
+                        replacement.clone()
+                    } else {
+                        replacement
+                            .shift(i64::try_from(depth).expect("depth should fit in an i64"), 0)
+                    }
+                } else {
+                    self.clone()
+                }
+            }
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.substitute_at_depth(idx, replacement, depth + 1)),
+            },
+            LambdaTerm::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.substitute_at_depth(idx, replacement, depth)),
+                argument: Rc::new(argument.substitute_at_depth(idx, replacement, depth)),
+            },
+        }
+    }
+
+    /// Treating the `LambdaTerm` as the body of an abstraction, replace the variable bound by
+    /// that abstraction with `argument`, so that the result is valid outside of the abstraction.
+    ///
+    /// This is exactly the substitution performed by β-reduction: the body of `λx:T. b` applied
+    /// to `a` is `b.open(a)`.
+    #[must_use]
+    pub fn open(&self, argument: &LambdaTerm) -> Self {
+        self.substitute(0, &argument.shift(1, 0)).shift(-1, 0)
+    }
+
+    /// Turn the free variable with de Bruijn index `idx` into the variable bound by a new
+    /// enclosing abstraction, so that the result is valid as the body of that abstraction.
+    ///
+    /// This is the inverse of `open`, in the sense that `t.close(i).open(&Variable { idx: i })`
+    /// is `t`.
+    #[must_use]
+    pub fn close(&self, idx: u64) -> Self {
+        self.close_at_depth(idx, 0)
+    }
+
+    fn close_at_depth(&self, idx: u64, depth: u64) -> Self {
+        match self {
+            LambdaTerm::Variable { idx: i } => {
+                if *i < depth {
+                    self.clone()
+                } else if *i == idx + depth {
+                    LambdaTerm::Variable { idx: depth }
+                } else {
+                    LambdaTerm::Variable { idx: i + 1 }
+                }
+            }
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.close_at_depth(idx, depth + 1)),
+            },
+            LambdaTerm::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.close_at_depth(idx, depth)),
+                argument: Rc::new(argument.close_at_depth(idx, depth)),
+            },
+        }
+    }
+}
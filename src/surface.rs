@@ -0,0 +1,520 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::{LambdaTerm, Type};
+
+/// A range of byte offsets into the source from which a term was parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Return whether the given byte offset lies within the `Span`.
+    #[must_use]
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+}
+
+/// A representation of an expression in the lambda calculus exactly as it was written, with
+/// variables referred to by name rather than by de Bruijn index.
+///
+/// Every node records the `Span` of source it was parsed from. Terms which were not parsed from
+/// anywhere, such as those produced by `SurfaceTerm::from_core`, have empty spans.
+#[derive(Debug, Clone)]
+pub enum SurfaceTerm {
+    Variable {
+        name: String,
+        span: Span,
+    },
+    Abstraction {
+        variable: String,
+        argument_type: Type,
+        body: Box<SurfaceTerm>,
+        span: Span,
+    },
+    Application {
+        function: Box<SurfaceTerm>,
+        argument: Box<SurfaceTerm>,
+        span: Span,
+    },
+}
+
+/// A definition of a name as standing for a term, as in `let name = term;`.
+#[derive(Debug, Clone)]
+pub struct SurfaceDefinition {
+    /// The text of the documentation comments preceding the definition, without their leading
+    /// `///`, one line for each comment, or the empty string if there are none.
+    pub doc: String,
+    pub name: String,
+    pub term: SurfaceTerm,
+    pub span: Span,
+}
+
+/// A macro, as in `macro twice f x = f (f x);`, which is expanded wherever it is applied to
+/// enough arguments, before the term it appears in is type checked.
+#[derive(Debug, Clone)]
+pub struct SurfaceMacro {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: SurfaceTerm,
+    pub span: Span,
+}
+
+/// A macro whose body has been resolved, and so is ready to be expanded.
+///
+/// The body is closed but for its parameters, which are the free variables with de Bruijn indices
+/// below `parameters`, the last parameter having index 0, and the outermost `context` variables
+/// of whatever term it is expanded into, which follow them. Since every other name in the body was
+/// resolved where the macro was defined, and arguments are substituted without capture, expansion
+/// is hygienic: neither the binders of the body nor those around the use of the macro can capture
+/// each other's variables.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Macro {
+    pub parameters: usize,
+    /// The number of variables bound outside the term being resolved which the body refers to,
+    /// which is zero unless the term is resolved under some `parameters` of its own.
+    pub context: usize,
+    pub body: LambdaTerm,
+}
+
+impl Macro {
+    /// Return the body of the `Macro` with the given arguments, one for each parameter,
+    /// substituted for its parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of arguments is not the number of parameters.
+    #[must_use]
+    pub fn expand(&self, arguments: &[LambdaTerm]) -> LambdaTerm {
+        assert_eq!(
+            arguments.len(),
+            self.parameters,
+            "a macro should be given exactly one argument for each parameter"
+        );
+
+        // The arguments are shifted past the parameters while they are substituted, so that none
+        // of their free variables is mistaken for a parameter which is yet to be replaced.
+        let amount = i64::try_from(self.parameters).expect("parameters should fit in an i64");
+        let body = arguments
+            .iter()
+            .enumerate()
+            .fold(self.body.clone(), |body, (i, argument)| {
+                body.substitute((self.parameters - i - 1) as u64, &argument.shift(amount, 0))
+            });
+        body.shift(-amount, 0)
+    }
+
+    /// Return the `Macro` with the variables of its context shifted past the given number of
+    /// binders, which are those between the context and the place the macro is expanded.
+    fn under(self, binders: usize) -> Self {
+        if self.context == 0 || binders == 0 {
+            return self;
+        }
+        let amount = i64::try_from(binders).expect("binders should fit in an i64");
+        Self {
+            body: self.body.shift(amount, self.parameters as u64),
+            ..self
+        }
+    }
+}
+
+/// An `import` directive, as in `import "lib.kombi";`, which brings every definition and macro
+/// of another module into scope.
+#[derive(Debug, Clone)]
+pub struct SurfaceImport {
+    /// The path of the module, exactly as it was written.
+    pub path: String,
+    pub span: Span,
+}
+
+/// A claim about terms made by an `assert` directive.
+#[derive(Debug, Clone)]
+pub enum Assertion {
+    /// `assert left = right;`, claiming that the terms are equivalent.
+    Equivalent {
+        left: SurfaceTerm,
+        right: SurfaceTerm,
+    },
+    /// `assert term : ty;`, claiming that the term has the type.
+    HasType { term: SurfaceTerm, ty: Type },
+}
+
+/// An `assert` directive, which may refer to everything defined before it.
+#[derive(Debug, Clone)]
+pub struct SurfaceAssertion {
+    pub assertion: Assertion,
+    pub span: Span,
+}
+
+/// A whole source file: any number of imports, definitions, assertions and macros, optionally
+/// followed by a term to evaluate.
+///
+/// Each kind of item is kept in its own list; `Program::items` interleaves them again in the
+/// order in which they were written.
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub definitions: Vec<SurfaceDefinition>,
+    pub assertions: Vec<SurfaceAssertion>,
+    pub macros: Vec<SurfaceMacro>,
+    pub imports: Vec<SurfaceImport>,
+    pub term: Option<SurfaceTerm>,
+}
+
+/// An item of a `Program`, other than its term.
+#[derive(Debug, Clone, Copy)]
+pub enum Item<'a> {
+    Definition(&'a SurfaceDefinition),
+    Assertion(&'a SurfaceAssertion),
+    Macro(&'a SurfaceMacro),
+    Import(&'a SurfaceImport),
+}
+
+impl Item<'_> {
+    /// Return the `Span` of source from which the `Item` was parsed.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Definition(definition) => definition.span,
+            Item::Assertion(assertion) => assertion.span,
+            Item::Macro(r#macro) => r#macro.span,
+            Item::Import(import) => import.span,
+        }
+    }
+}
+
+impl Program {
+    /// Return every item of the `Program` in the order in which they appear in the source.
+    #[must_use]
+    pub fn items(&self) -> Vec<Item<'_>> {
+        let mut items: Vec<_> = self
+            .definitions
+            .iter()
+            .map(Item::Definition)
+            .chain(self.assertions.iter().map(Item::Assertion))
+            .chain(self.macros.iter().map(Item::Macro))
+            .chain(self.imports.iter().map(Item::Import))
+            .collect();
+        items.sort_by_key(|item| item.span().start);
+        items
+    }
+}
+
+// NOTE: For now, the only error which scope checking may encounter is a variable which is not
+// bound by any enclosing abstraction. This is left as an enum in case future expansion of the
+// language leads to more possible errors.
+#[derive(Debug)]
+pub enum ScopeError {
+    UnboundVariable {
+        name: String,
+        span: Span,
+    },
+    /// A macro is applied to fewer arguments than it has parameters.
+    MacroArity {
+        name: String,
+        parameters: usize,
+        arguments: usize,
+        span: Span,
+    },
+}
+
+impl ScopeError {
+    /// Return the `Span` of source responsible for the error.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnboundVariable { span, .. } | Self::MacroArity { span, .. } => *span,
+        }
+    }
+}
+
+impl Display for ScopeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnboundVariable { name, .. } => write!(f, "variable {name} is not bound"),
+            Self::MacroArity {
+                name,
+                parameters,
+                arguments,
+                ..
+            } => write!(
+                f,
+                "macro {name} takes {parameters} arguments, but is applied to {arguments}"
+            ),
+        }
+    }
+}
+
+impl Error for ScopeError {}
+
+impl SurfaceTerm {
+    /// Return the `Span` of source from which the `SurfaceTerm` was parsed.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            SurfaceTerm::Variable { span, .. }
+            | SurfaceTerm::Abstraction { span, .. }
+            | SurfaceTerm::Application { span, .. } => *span,
+        }
+    }
+
+    /// Resolve every variable in the `SurfaceTerm` to the abstraction which binds it, producing
+    /// the equivalent `LambdaTerm`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ScopeError` if some variable is not bound by any enclosing abstraction.
+    pub fn to_core(&self) -> Result<LambdaTerm, ScopeError> {
+        self.to_core_with(&|_| None)
+    }
+
+    /// Resolve every variable in the `SurfaceTerm` to the abstraction which binds it, producing
+    /// the equivalent `LambdaTerm`. Variables which are not bound by any abstraction are looked
+    /// up with `definitions`, and replaced by the closed term it returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ScopeError` if some variable is neither bound by any enclosing abstraction nor
+    /// defined.
+    pub fn to_core_with(
+        &self,
+        definitions: &impl Fn(&str) -> Option<LambdaTerm>,
+    ) -> Result<LambdaTerm, ScopeError> {
+        self.to_core_in_context(&mut Vec::new(), &|name| {
+            definitions(name).map(|body| Macro {
+                parameters: 0,
+                context: 0,
+                body,
+            })
+        })
+    }
+
+    /// Resolve every variable in the `SurfaceTerm` to the abstraction which binds it, producing
+    /// the equivalent `LambdaTerm`, where the term is found under abstractions binding each of
+    /// `parameters`, the last innermost. Variables which are not bound by any abstraction are
+    /// looked up with `macros`, and every application of a macro to enough arguments is expanded.
+    /// A definition is simply a macro with no parameters. The context of a macro is the outermost
+    /// of `parameters`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ScopeError` if some variable is neither bound by any enclosing abstraction nor
+    /// defined, or some macro is applied to too few arguments.
+    pub fn to_core_with_macros(
+        &self,
+        parameters: &[String],
+        macros: &impl Fn(&str) -> Option<Macro>,
+    ) -> Result<LambdaTerm, ScopeError> {
+        let mut ctx = parameters.iter().map(String::as_str).collect();
+        self.to_core_in_context(&mut ctx, macros)
+    }
+
+    fn to_core_in_context<'a>(
+        &'a self,
+        ctx: &mut Vec<&'a str>,
+        macros: &impl Fn(&str) -> Option<Macro>,
+    ) -> Result<LambdaTerm, ScopeError> {
+        match self {
+            SurfaceTerm::Variable { name, span } => {
+                // The de Bruijn index of a variable is the number of abstractions between it and
+                // its binder, so the innermost binder with the right name is the one we want.
+                // Only once every binder has been ruled out do we fall back on macros.
+                match ctx.iter().rposition(|v| v == name) {
+                    Some(position) => Ok(LambdaTerm::Variable {
+                        idx: (ctx.len() - position - 1) as u64,
+                    }),
+                    None => match macros(name) {
+                        Some(r#macro) if r#macro.parameters == 0 => {
+                            let binders = ctx.len() - r#macro.context;
+                            Ok(r#macro.under(binders).body)
+                        }
+                        Some(r#macro) => Err(ScopeError::MacroArity {
+                            name: name.clone(),
+                            parameters: r#macro.parameters,
+                            arguments: 0,
+                            span: *span,
+                        }),
+                        None => Err(ScopeError::UnboundVariable {
+                            name: name.clone(),
+                            span: *span,
+                        }),
+                    },
+                }
+            }
+            SurfaceTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+                ..
+            } => {
+                ctx.push(variable);
+                let body = body.to_core_in_context(ctx, macros);
+                ctx.pop();
+
+                Ok(LambdaTerm::Abstraction {
+                    variable: variable.clone(),
+                    argument_type: argument_type.clone(),
+                    body: Rc::new(body?),
+                })
+            }
+            SurfaceTerm::Application { .. } => {
+                // A macro may be applied to more arguments than it has parameters, so the whole
+                // spine of the application is needed to find those which belong to it.
+                let mut head = self;
+                let mut spine = Vec::new();
+                while let SurfaceTerm::Application {
+                    function, argument, ..
+                } = head
+                {
+                    spine.push(&**argument);
+                    head = function;
+                }
+                spine.reverse();
+
+                let (mut term, rest) = match head {
+                    SurfaceTerm::Variable { name, .. } if !ctx.contains(&name.as_str()) => {
+                        match macros(name) {
+                            Some(r#macro) if r#macro.parameters > 0 => {
+                                let Some((arguments, rest)) =
+                                    spine.split_at_checked(r#macro.parameters)
+                                else {
+                                    return Err(ScopeError::MacroArity {
+                                        name: name.clone(),
+                                        parameters: r#macro.parameters,
+                                        arguments: spine.len(),
+                                        span: self.span(),
+                                    });
+                                };
+                                let arguments = arguments
+                                    .iter()
+                                    .map(|argument| argument.to_core_in_context(ctx, macros))
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                let binders = ctx.len() - r#macro.context;
+                                (r#macro.under(binders).expand(&arguments), rest)
+                            }
+                            _ => (head.to_core_in_context(ctx, macros)?, &spine[..]),
+                        }
+                    }
+                    _ => (head.to_core_in_context(ctx, macros)?, &spine[..]),
+                };
+                for argument in rest {
+                    term = LambdaTerm::Application {
+                        function: Rc::new(term),
+                        argument: Rc::new(argument.to_core_in_context(ctx, macros)?),
+                    };
+                }
+                Ok(term)
+            }
+        }
+    }
+
+    /// Give names to the variables of a `LambdaTerm`, producing the equivalent `SurfaceTerm`.
+    ///
+    /// The names recorded in the `LambdaTerm` are reused where possible, and are only changed
+    /// where they would cause a variable to be captured by the wrong abstraction. Free variables,
+    /// which have no name to reuse, are named after their de Bruijn index, as in `_0`.
+    #[must_use]
+    pub fn from_core(term: &LambdaTerm) -> Self {
+        Self::from_core_in_context(term, &mut Vec::new(), &|_| None, &[])
+    }
+
+    /// Give names to the variables of a `LambdaTerm`, just as `from_core` does, where the term is
+    /// found under abstractions binding each of `free`, the last innermost, so that its free
+    /// variables are given those names.
+    #[must_use]
+    pub fn from_core_with(term: &LambdaTerm, free: &[String]) -> Self {
+        Self::from_core_in_context(term, &mut free.to_vec(), &|_| None, &[])
+    }
+
+    /// Give names to the variables of a closed `LambdaTerm`, just as `from_core` does, but write
+    /// every subterm for which `name_of` gives a name as that name, referring to a definition.
+    /// No abstraction is given any of the names in `reserved`, which should include every name
+    /// `name_of` gives, so that none of the references is captured.
+    #[must_use]
+    pub fn from_core_referring(
+        term: &LambdaTerm,
+        name_of: &dyn Fn(&LambdaTerm) -> Option<String>,
+        reserved: &[String],
+    ) -> Self {
+        Self::from_core_in_context(term, &mut Vec::new(), name_of, reserved)
+    }
+
+    fn from_core_in_context(
+        term: &LambdaTerm,
+        ctx: &mut Vec<String>,
+        name_of: &dyn Fn(&LambdaTerm) -> Option<String>,
+        reserved: &[String],
+    ) -> Self {
+        if let Some(name) = name_of(term) {
+            return SurfaceTerm::Variable {
+                name,
+                span: Span::default(),
+            };
+        }
+        match term {
+            LambdaTerm::Variable { idx } => {
+                let name = usize::try_from(*idx)
+                    .ok()
+                    .and_then(|i| ctx.len().checked_sub(i + 1))
+                    .map_or_else(
+                        || format!("_{}", idx - ctx.len() as u64),
+                        |i| ctx[i].clone(),
+                    );
+                SurfaceTerm::Variable {
+                    name,
+                    span: Span::default(),
+                }
+            }
+            LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body,
+            } => {
+                // A name is only unusable if some variable in the body would be captured by it,
+                // which happens exactly when the body refers to an enclosing binder sharing the
+                // name.
+                let captured: Vec<&String> = body
+                    .free_indices()
+                    .into_iter()
+                    .filter(|i| *i > 0)
+                    .filter_map(|i| {
+                        usize::try_from(i)
+                            .ok()
+                            .and_then(|i| ctx.len().checked_sub(i))
+                            .map(|i| &ctx[i])
+                    })
+                    .collect();
+                let mut name = variable.clone();
+                let mut suffix = 1;
+                while captured.contains(&&name) || reserved.contains(&name) {
+                    name = format!("{variable}{suffix}");
+                    suffix += 1;
+                }
+
+                ctx.push(name.clone());
+                let body = Self::from_core_in_context(body, ctx, name_of, reserved);
+                ctx.pop();
+
+                SurfaceTerm::Abstraction {
+                    variable: name,
+                    argument_type: argument_type.clone(),
+                    body: Box::new(body),
+                    span: Span::default(),
+                }
+            }
+            LambdaTerm::Application { function, argument } => SurfaceTerm::Application {
+                function: Box::new(Self::from_core_in_context(function, ctx, name_of, reserved)),
+                argument: Box::new(Self::from_core_in_context(argument, ctx, name_of, reserved)),
+                span: Span::default(),
+            },
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// An immutable, cheaply cloned name, such as that of a base type.
+///
+/// With the `std` feature, every `Symbol` is interned, so that all `Symbol`s with the same name
+/// share a single allocation and can be compared by pointer alone. Without it, there is nowhere to
+/// keep a shared interner, so each `Symbol` owns its own allocation; `Symbol`s still compare
+/// correctly, just more slowly when they are not clones of one another.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+#[cfg(feature = "std")]
+static INTERNER: std::sync::Mutex<alloc::collections::BTreeSet<Arc<str>>> =
+    std::sync::Mutex::new(alloc::collections::BTreeSet::new());
+
+impl Symbol {
+    /// Return the `Symbol` with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another thread panicked while interning a `Symbol`.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        #[cfg(feature = "std")]
+        {
+            let mut interner = INTERNER.lock().expect("interner should not be poisoned");
+            if let Some(name) = interner.get(name) {
+                return Self(name.clone());
+            }
+            let name: Arc<str> = Arc::from(name);
+            interner.insert(name.clone());
+            Self(name)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            Self(Arc::from(name))
+        }
+    }
+
+    /// Return the name of the `Symbol`.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::new(&name)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&*self.0, f)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
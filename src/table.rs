@@ -0,0 +1,187 @@
+//! Writing terms out as tables of nodes, in which every subterm they share is written only once.
+//!
+//! Subterms are reference-counted, and a term built from definitions shares the terms of the
+//! definitions it refers to, as does every later definition which refers to it. Written out as a
+//! tree, each of those would be repeated wherever it occurs, so that the size of the output could
+//! be exponential in the depth to which definitions refer to one another. A `TermTable` writes
+//! each node once, referring to its children by their positions, and rebuilds the same sharing
+//! when it is read back.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::ptr;
+
+use crate::parse::{LambdaTerm, Type};
+
+/// A node of a term in a `TermTable`, which refers to its children by their positions in the
+/// table, all of which come before it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Node {
+    Variable {
+        idx: u64,
+    },
+    Abstraction {
+        variable: String,
+        argument_type: Type,
+        body: usize,
+    },
+    Application {
+        function: usize,
+        argument: usize,
+    },
+}
+
+/// Some node of a `TermTable`, or a term read from one, refers to a node which does not come
+/// before it in the table.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableError {
+    /// The position of the node referred to.
+    pub node: usize,
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node {} is referred to before it is in the table",
+            self.node
+        )
+    }
+}
+
+impl Error for TableError {}
+
+/// The nodes of some terms, each written once however many times it is shared between them.
+///
+/// Only subterms which are shared in memory are written once; equal subterms which were built
+/// separately are written as many times as they were built.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TermTable {
+    nodes: Vec<Node>,
+}
+
+impl TermTable {
+    /// Create a `TermTable` holding the given terms, returning it along with the position of
+    /// each of them in it, in order.
+    #[must_use]
+    pub fn new<'a>(terms: impl IntoIterator<Item = &'a LambdaTerm>) -> (Self, Vec<usize>) {
+        // NOTE: As in hashing a term, each node is taken off the stack twice, first to add its
+        // children and then, once they have been, itself, so that it comes after them.
+        let mut nodes = Vec::new();
+        let mut positions = BTreeMap::<*const LambdaTerm, usize>::new();
+        let mut roots = Vec::new();
+        for term in terms {
+            let mut stack = vec![(term, false)];
+            while let Some((term, finished)) = stack.pop() {
+                let key = ptr::from_ref(term);
+                if positions.contains_key(&key) {
+                    continue;
+                }
+                let child = |child: &LambdaTerm| positions[&ptr::from_ref(child)];
+                let node = match (term, finished) {
+                    (LambdaTerm::Variable { idx }, _) => Node::Variable { idx: *idx },
+                    (LambdaTerm::Abstraction { body, .. }, false) => {
+                        stack.extend([(term, true), (body.as_ref(), false)]);
+                        continue;
+                    }
+                    (LambdaTerm::Application { function, argument }, false) => {
+                        stack.extend([(term, true), (function, false), (argument, false)]);
+                        continue;
+                    }
+                    (
+                        LambdaTerm::Abstraction {
+                            variable,
+                            argument_type,
+                            body,
+                        },
+                        true,
+                    ) => Node::Abstraction {
+                        variable: variable.clone(),
+                        argument_type: argument_type.clone(),
+                        body: child(body),
+                    },
+                    (LambdaTerm::Application { function, argument }, true) => Node::Application {
+                        function: child(function),
+                        argument: child(argument),
+                    },
+                };
+                positions.insert(key, nodes.len());
+                nodes.push(node);
+            }
+            roots.push(positions[&ptr::from_ref(term)]);
+        }
+        (Self { nodes }, roots)
+    }
+
+    /// Return the terms at the given positions in the table, sharing every subterm which was
+    /// shared between the terms the table was created from.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TableError` if some node refers to one which does not come before it, or some
+    /// position given is not in the table.
+    pub fn terms(&self, positions: &[usize]) -> Result<Vec<LambdaTerm>, TableError> {
+        let mut terms = Vec::<Rc<LambdaTerm>>::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let child = |node: usize| terms.get(node).cloned().ok_or(TableError { node });
+            let term = match node {
+                Node::Variable { idx } => LambdaTerm::Variable { idx: *idx },
+                Node::Abstraction {
+                    variable,
+                    argument_type,
+                    body,
+                } => LambdaTerm::Abstraction {
+                    variable: variable.clone(),
+                    argument_type: argument_type.clone(),
+                    body: child(*body)?,
+                },
+                Node::Application { function, argument } => LambdaTerm::Application {
+                    function: child(*function)?,
+                    argument: child(*argument)?,
+                },
+            };
+            terms.push(Rc::new(term));
+        }
+        positions
+            .iter()
+            .map(|&node| {
+                terms
+                    .get(node)
+                    .map(|term| term.as_ref().clone())
+                    .ok_or(TableError { node })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::TermTable;
+    use crate::parse::LambdaTerm;
+
+    #[test]
+    fn shared_subterms_are_written_once() {
+        // NOTE: Each term applies the last to itself, so that it is twice the size of the last,
+        // but shares everything with it.
+        let mut term = "\\x:A. x".parse::<LambdaTerm>().unwrap();
+        for _ in 0..12 {
+            let shared = Rc::new(term);
+            term = LambdaTerm::Application {
+                function: Rc::clone(&shared),
+                argument: shared,
+            };
+        }
+        let (table, positions) = TermTable::new([&term]);
+        assert_eq!(table.nodes.len(), 14);
+        assert_eq!(table.terms(&positions).unwrap(), [term]);
+    }
+}
@@ -0,0 +1,90 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parse::LambdaTerm;
+
+/// A single step from a `LambdaTerm` to one of its immediate subterms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Step {
+    /// From an abstraction to its body.
+    Body,
+    /// From an application to the function being applied.
+    Function,
+    /// From an application to the argument being supplied.
+    Argument,
+}
+
+/// A subterm of some `LambdaTerm`, together with its position in that term.
+#[derive(Debug, Clone)]
+pub struct Subterm<'a> {
+    pub term: &'a LambdaTerm,
+    /// The number of abstractions enclosing the subterm, i.e. the number of de Bruijn indices
+    /// which are bound at this point.
+    pub depth: u64,
+    /// The sequence of steps leading from the root of the term to the subterm.
+    pub path: Vec<Step>,
+}
+
+/// An iterator over the subterms of a `LambdaTerm` in pre-order, created by
+/// [`LambdaTerm::subterms`].
+pub struct Subterms<'a> {
+    stack: Vec<Subterm<'a>>,
+}
+
+impl<'a> Iterator for Subterms<'a> {
+    type Item = Subterm<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let subterm = self.stack.pop()?;
+
+        match subterm.term {
+            LambdaTerm::Variable { .. } => {}
+            LambdaTerm::Abstraction { body, .. } => {
+                let mut path = subterm.path.clone();
+                path.push(Step::Body);
+                self.stack.push(Subterm {
+                    term: body,
+                    depth: subterm.depth + 1,
+                    path,
+                });
+            }
+            LambdaTerm::Application { function, argument } => {
+                // NOTE: The argument is pushed first so that the function is visited first, which
+                // keeps the traversal in left-to-right pre-order.
+                let mut path = subterm.path.clone();
+                path.push(Step::Argument);
+                self.stack.push(Subterm {
+                    term: argument,
+                    depth: subterm.depth,
+                    path,
+                });
+
+                let mut path = subterm.path.clone();
+                path.push(Step::Function);
+                self.stack.push(Subterm {
+                    term: function,
+                    depth: subterm.depth,
+                    path,
+                });
+            }
+        }
+
+        Some(subterm)
+    }
+}
+
+impl LambdaTerm {
+    /// Return an iterator over every subterm of the `LambdaTerm`, including the term itself, in
+    /// pre-order.
+    #[must_use]
+    pub fn subterms(&self) -> Subterms<'_> {
+        Subterms {
+            stack: vec![Subterm {
+                term: self,
+                depth: 0,
+                path: Vec::new(),
+            }],
+        }
+    }
+}
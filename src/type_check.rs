@@ -1,7 +1,15 @@
-use std::error::Error;
-use std::fmt::{self, Display, Formatter};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+use core::{mem, ptr};
 
 use crate::parse::{LambdaTerm, Type};
+use crate::traverse::Step;
 
 // NOTE: For now, the only error which the type checker may encounter is an attempt to apply a
 // function which does not take a term of type T as an argument to a term of type T. This is left
@@ -9,9 +17,9 @@ use crate::parse::{LambdaTerm, Type};
 #[derive(Debug)]
 pub enum TypeError {
     InvalidApplication {
-        function: LambdaTerm,
+        function: Rc<LambdaTerm>,
         function_type: Type,
-        argument: LambdaTerm,
+        argument: Rc<LambdaTerm>,
         argument_type: Type,
     },
 }
@@ -33,54 +41,446 @@ impl Display for TypeError {
 
 impl Error for TypeError {}
 
-impl LambdaTerm {
-    /// Return the `Type` of the `LambaTerm` if it is well-typed, or an appropriate `TypeError` if
-    /// it is not.
-    pub fn get_type(&self) -> Result<Type, TypeError> {
-        self.get_type_in_context(Vec::new())
+/// A `LambdaTerm` in which every node has been annotated with its type by the type checker.
+///
+/// Later phases which need the types of subterms can read them from here rather than checking
+/// each subterm again.
+#[derive(Debug, Clone)]
+pub struct TypedTerm {
+    pub ty: Type,
+    pub node: TypedNode,
+}
+
+/// A single node of a `TypedTerm`, mirroring the variants of `LambdaTerm`.
+#[derive(Debug, Clone)]
+pub enum TypedNode {
+    Variable {
+        idx: u64,
+    },
+    Abstraction {
+        variable: String,
+        argument_type: Type,
+        body: Box<TypedTerm>,
+    },
+    Application {
+        function: Box<TypedTerm>,
+        argument: Box<TypedTerm>,
+    },
+}
+
+// NOTE: The derived drop glue would recurse once per level of nesting, overflowing the stack on
+// exactly the deeply nested terms which the type checker is careful to handle without recursion.
+// Instead, the compound children of every node are detached onto an explicit stack, leaving each
+// one to be dropped without any children of its own.
+impl Drop for TypedNode {
+    fn drop(&mut self) {
+        fn detach(node: &mut TypedNode, stack: &mut Vec<TypedNode>) {
+            let children = match node {
+                TypedNode::Variable { .. } => [None, None],
+                TypedNode::Abstraction { body, .. } => [Some(body), None],
+                TypedNode::Application { function, argument } => [Some(function), Some(argument)],
+            };
+            for child in children.into_iter().flatten() {
+                if !matches!(child.node, TypedNode::Variable { .. }) {
+                    stack.push(mem::replace(
+                        &mut child.node,
+                        TypedNode::Variable { idx: 0 },
+                    ));
+                }
+            }
+        }
+
+        let mut stack = Vec::new();
+        detach(self, &mut stack);
+        while let Some(mut node) = stack.pop() {
+            detach(&mut node, &mut stack);
+        }
     }
+}
 
-    fn get_type_in_context(&self, mut ctx: Vec<Type>) -> Result<Type, TypeError> {
-        match self {
-            LambdaTerm::Variable { idx } => Ok(ctx.swap_remove(ctx.len() - (idx + 1) as usize)),
-            LambdaTerm::Abstraction {
+impl TypedTerm {
+    /// Return the `Type` of the whole term.
+    #[must_use]
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    /// Return the `Type` of the whole term, discarding the annotations on its subterms.
+    #[must_use]
+    pub fn into_type(self) -> Type {
+        self.ty
+    }
+
+    /// Return the typed subterm at the end of the given path, or `None` if the path does not lead
+    /// to a subterm.
+    #[must_use]
+    pub fn at_path(&self, path: &[Step]) -> Option<&TypedTerm> {
+        path.iter().try_fold(self, |t, step| match (step, &t.node) {
+            (Step::Body, TypedNode::Abstraction { body, .. }) => Some(body.as_ref()),
+            (Step::Function, TypedNode::Application { function, .. }) => Some(function.as_ref()),
+            (Step::Argument, TypedNode::Application { argument, .. }) => Some(argument.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Discard every type annotation, recovering the original `LambdaTerm`.
+    #[must_use]
+    pub fn erase(&self) -> LambdaTerm {
+        match &self.node {
+            TypedNode::Variable { idx } => LambdaTerm::Variable { idx: *idx },
+            TypedNode::Abstraction {
+                variable,
                 argument_type,
                 body,
-            } => {
-                ctx.push(argument_type.clone());
-                let return_type = body.get_type_in_context(ctx)?;
+            } => LambdaTerm::Abstraction {
+                variable: variable.clone(),
+                argument_type: argument_type.clone(),
+                body: Rc::new(body.erase()),
+            },
+            TypedNode::Application { function, argument } => LambdaTerm::Application {
+                function: Rc::new(function.erase()),
+                argument: Rc::new(argument.erase()),
+            },
+        }
+    }
+}
+
+/// The types found by `LambdaTerm::type_of` for the subterms it has checked, keyed by address.
+struct Typings<'a> {
+    /// For each subterm, one more than the largest de Bruijn index free in it, or zero if it is
+    /// closed.
+    free: BTreeMap<*const LambdaTerm, usize>,
+    /// The type of each subterm, along with the types of the variables free in it, the innermost
+    /// last, also keyed by address, since the same types are found in the same binders.
+    types: BTreeMap<(*const LambdaTerm, Vec<*const Type>), Type>,
+    /// The types of the enclosing binders, the innermost last.
+    ctx: Vec<&'a Type>,
+}
+
+impl<'a> Typings<'a> {
+    /// Return the key under which the type of the given subterm is kept, if it has been checked
+    /// before, in any context.
+    fn key(&self, term: &LambdaTerm) -> Option<(*const LambdaTerm, Vec<*const Type>)> {
+        let free = *self.free.get(&ptr::from_ref(term))?;
+        let suffix = self.ctx[self.ctx.len() - free..]
+            .iter()
+            .map(|ty| ptr::from_ref(*ty))
+            .collect();
+        Some((ptr::from_ref(term), suffix))
+    }
+
+    /// Record the type of the given subterm, in which `free` is one more than the largest de
+    /// Bruijn index free.
+    fn insert(&mut self, term: &'a LambdaTerm, free: usize, ty: &Type) {
+        self.free.insert(ptr::from_ref(term), free);
+        let key = self
+            .key(term)
+            .expect("the subterm should just have been recorded");
+        self.types.insert(key, ty.clone());
+    }
+}
+
+/// A unit of work for the type checker.
+enum Task<'a> {
+    /// Check the given term, leaving its `TypedTerm` on top of the stack.
+    Check(&'a LambdaTerm),
+    /// Combine the `TypedTerm`s on top of the stack for the children of the given abstraction or
+    /// application, which have just been checked, into one for the whole term.
+    Finish(&'a LambdaTerm),
+}
+
+impl LambdaTerm {
+    /// Return the `LambdaTerm` elaborated with the `Type` of every one of its subterms if it is
+    /// well-typed, or an appropriate `TypeError` if it is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if the `LambdaTerm` is not well-typed.
+    pub fn get_type(&self) -> Result<TypedTerm, TypeError> {
+        self.get_type_in_context(&mut Vec::new())
+    }
+
+    /// Return the `Type` of the `LambdaTerm` if it is well-typed, or the same `TypeError` as
+    /// `get_type` if it is not.
+    ///
+    /// Unlike `get_type`, this checks each subterm shared between several parts of the term, as
+    /// the definitions and macros of a program are once they are expanded, only once for every
+    /// assignment of types to its free variables, so that checking takes time in proportion to
+    /// the number of distinct subterms rather than to the size of the term.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if the `LambdaTerm` is not well-typed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `LambdaTerm` has a free variable, or if the types of its subterms are not
+    /// found where they were left, which should never happen.
+    pub fn type_of(&self) -> Result<Type, TypeError> {
+        let mut typings = Typings {
+            free: BTreeMap::new(),
+            types: BTreeMap::new(),
+            ctx: Vec::new(),
+        };
+        let mut tasks = vec![Task::Check(self)];
+        // NOTE: Along with the type of each subterm checked is one more than the largest de
+        // Bruijn index free in it.
+        let mut types: Vec<(Type, usize)> = Vec::new();
 
-                Ok(Type::FunctionType(
-                    Box::new(argument_type.clone()),
-                    Box::new(return_type),
-                ))
+        while let Some(task) = tasks.pop() {
+            if let Task::Check(term) = task {
+                let known = typings.key(term).and_then(|key| typings.types.get(&key));
+                if let Some(ty) = known {
+                    let free = typings.free[&ptr::from_ref(term)];
+                    types.push((ty.clone(), free));
+                    continue;
+                }
             }
-            LambdaTerm::Application { function, argument } => {
-                let function_type = function.get_type_in_context(ctx.clone())?;
-                let argument_type = argument.get_type_in_context(ctx)?;
-
-                if let Type::FunctionType(function_argument_type, return_type) =
-                    function_type.clone()
-                {
-                    if *function_argument_type == argument_type {
-                        Ok(*return_type)
-                    } else {
-                        Err(TypeError::InvalidApplication {
-                            function: *function.clone(),
-                            function_type,
-                            argument: *argument.clone(),
-                            argument_type,
-                        })
-                    }
-                } else {
-                    Err(TypeError::InvalidApplication {
-                        function: *function.clone(),
-                        function_type,
-                        argument: *argument.clone(),
+            match task {
+                Task::Check(LambdaTerm::Variable { idx }) => {
+                    let i = usize::try_from(*idx).expect("de Bruijn index should fit in a usize");
+                    let ty = typings
+                        .ctx
+                        .len()
+                        .checked_sub(i + 1)
+                        .map(|i| typings.ctx[i])
+                        .expect("variable should be bound");
+                    types.push((ty.clone(), i + 1));
+                }
+                Task::Check(
+                    term @ LambdaTerm::Abstraction {
                         argument_type,
-                    })
+                        body,
+                        ..
+                    },
+                ) => {
+                    typings.ctx.push(argument_type);
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(body));
                 }
+                Task::Check(term @ LambdaTerm::Application { function, argument }) => {
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(argument));
+                    tasks.push(Task::Check(function));
+                }
+                Task::Finish(term @ LambdaTerm::Abstraction { argument_type, .. }) => {
+                    typings.ctx.pop();
+                    let (body, free) = types.pop().expect("body should have been checked");
+                    let ty = Type::FunctionType(Box::new(argument_type.clone()), Box::new(body));
+                    let free = free.saturating_sub(1);
+                    typings.insert(term, free, &ty);
+                    types.push((ty, free));
+                }
+                Task::Finish(term @ LambdaTerm::Application { function, argument }) => {
+                    let (argument_type, argument_free) =
+                        types.pop().expect("argument should have been checked");
+                    let (function_type, function_free) =
+                        types.pop().expect("function should have been checked");
+                    let return_type = match function_type {
+                        Type::FunctionType(function_argument_type, return_type)
+                            if *function_argument_type == argument_type =>
+                        {
+                            *return_type
+                        }
+                        function_type => {
+                            return Err(TypeError::InvalidApplication {
+                                function: function.clone(),
+                                function_type,
+                                argument: argument.clone(),
+                                argument_type,
+                            })
+                        }
+                    };
+                    let free = function_free.max(argument_free);
+                    typings.insert(term, free, &return_type);
+                    types.push((return_type, free));
+                }
+                Task::Finish(LambdaTerm::Variable { .. }) => unreachable!(),
             }
         }
+
+        Ok(types.pop().expect("term should have been checked").0)
+    }
+
+    /// Return the `LambdaTerm` elaborated with the `Type` of every one of its subterms, just as
+    /// `get_type` does, where its free variables have the given types, that of the variable
+    /// bound outermost first.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if the `LambdaTerm` is not well-typed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `LambdaTerm` has a free variable which is not given a type.
+    pub fn get_type_in(&self, free: &[Type]) -> Result<TypedTerm, TypeError> {
+        self.get_type_in_context(&mut free.iter().collect())
+    }
+
+    /// Return the type which the `LambdaTerm` would have if each of its errors were fixed where
+    /// it is, where its free variables have the given types, that of the variable bound outermost
+    /// first, or `None` if it cannot be known.
+    ///
+    /// Rather than stopping at the first error, the checker gives every ill-typed subterm an
+    /// error type and carries on: an application of a function to an argument of the wrong type
+    /// has the function's return type, a variable with no type given, or an application of
+    /// something which is not a function, has the error type, and so does every term built on a
+    /// subterm of the error type, except an application of a function to it. Well-typed terms
+    /// have the type which `get_type` gives them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the types of the subterms are not found where they were left, which should
+    /// never happen.
+    #[must_use]
+    pub fn get_partial_type(&self, free: &[Option<Type>]) -> Option<Type> {
+        let mut ctx: Vec<Option<&Type>> = free.iter().map(Option::as_ref).collect();
+        let mut tasks = vec![Task::Check(self)];
+        let mut types: Vec<Option<Type>> = Vec::new();
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Check(LambdaTerm::Variable { idx }) => {
+                    let ty = usize::try_from(*idx)
+                        .ok()
+                        .and_then(|i| ctx.len().checked_sub(i + 1))
+                        .and_then(|i| ctx[i]);
+                    types.push(ty.cloned());
+                }
+                Task::Check(
+                    term @ LambdaTerm::Abstraction {
+                        argument_type,
+                        body,
+                        ..
+                    },
+                ) => {
+                    ctx.push(Some(argument_type));
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(body));
+                }
+                Task::Check(term @ LambdaTerm::Application { function, argument }) => {
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(argument));
+                    tasks.push(Task::Check(function));
+                }
+                Task::Finish(LambdaTerm::Abstraction { argument_type, .. }) => {
+                    ctx.pop();
+                    let body = types.pop().expect("body should have been checked");
+                    types.push(body.map(|body| {
+                        Type::FunctionType(Box::new(argument_type.clone()), Box::new(body))
+                    }));
+                }
+                Task::Finish(LambdaTerm::Application { .. }) => {
+                    types.pop().expect("argument should have been checked");
+                    let function = types.pop().expect("function should have been checked");
+                    types.push(match function {
+                        Some(Type::FunctionType(_, return_type)) => Some(*return_type),
+                        _ => None,
+                    });
+                }
+                Task::Finish(LambdaTerm::Variable { .. }) => unreachable!(),
+            }
+        }
+
+        types.pop().expect("term should have been checked")
+    }
+
+    /// Type check the `LambdaTerm` in a context holding the type of each enclosing binder, the
+    /// innermost last.
+    ///
+    /// The context borrows the types from the binders themselves, and is shared by every subterm,
+    /// each abstraction pushing its type on the way down and popping it on the way back up, so
+    /// that checking a term never copies the context.
+    ///
+    /// Rather than recursing into subterms, which would overflow the call stack on deeply nested
+    /// terms, this keeps an explicit stack of `Task`s, and a stack of the `TypedTerm`s produced
+    /// for the subterms checked so far.
+    fn get_type_in_context<'a>(&'a self, ctx: &mut Vec<&'a Type>) -> Result<TypedTerm, TypeError> {
+        let mut tasks = vec![Task::Check(self)];
+        let mut typed = Vec::new();
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Check(LambdaTerm::Variable { idx }) => {
+                    let i = usize::try_from(*idx).expect("de Bruijn index should fit in a usize");
+                    let ty = ctx
+                        .len()
+                        .checked_sub(i + 1)
+                        .map(|i| ctx[i])
+                        .expect("variable should be bound");
+                    typed.push(TypedTerm {
+                        ty: ty.clone(),
+                        node: TypedNode::Variable { idx: *idx },
+                    });
+                }
+                Task::Check(
+                    term @ LambdaTerm::Abstraction {
+                        argument_type,
+                        body,
+                        ..
+                    },
+                ) => {
+                    ctx.push(argument_type);
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(body));
+                }
+                Task::Check(term @ LambdaTerm::Application { function, argument }) => {
+                    // NOTE: The argument is pushed first so that the function is checked first,
+                    // which keeps the order in which errors are found the same as it was when
+                    // checking recursively.
+                    tasks.push(Task::Finish(term));
+                    tasks.push(Task::Check(argument));
+                    tasks.push(Task::Check(function));
+                }
+                Task::Finish(LambdaTerm::Abstraction {
+                    variable,
+                    argument_type,
+                    ..
+                }) => {
+                    ctx.pop();
+                    let body = typed.pop().expect("body should have been checked");
+                    typed.push(TypedTerm {
+                        ty: Type::FunctionType(
+                            Box::new(argument_type.clone()),
+                            Box::new(body.ty.clone()),
+                        ),
+                        node: TypedNode::Abstraction {
+                            variable: variable.clone(),
+                            argument_type: argument_type.clone(),
+                            body: Box::new(body),
+                        },
+                    });
+                }
+                Task::Finish(LambdaTerm::Application { function, argument }) => {
+                    let typed_argument = typed.pop().expect("argument should have been checked");
+                    let typed_function = typed.pop().expect("function should have been checked");
+
+                    match &typed_function.ty {
+                        Type::FunctionType(function_argument_type, return_type)
+                            if **function_argument_type == typed_argument.ty =>
+                        {
+                            typed.push(TypedTerm {
+                                ty: *return_type.clone(),
+                                node: TypedNode::Application {
+                                    function: Box::new(typed_function),
+                                    argument: Box::new(typed_argument),
+                                },
+                            });
+                        }
+                        _ => {
+                            return Err(TypeError::InvalidApplication {
+                                function: function.clone(),
+                                function_type: typed_function.ty,
+                                argument: argument.clone(),
+                                argument_type: typed_argument.ty,
+                            })
+                        }
+                    }
+                }
+                Task::Finish(LambdaTerm::Variable { .. }) => unreachable!(),
+            }
+        }
+
+        Ok(typed.pop().expect("term should have been checked"))
     }
 }
@@ -0,0 +1,226 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::parse::LambdaTerm;
+
+/// A term of the untyped lambda calculus, using the same de Bruijn representation as
+/// `LambdaTerm`, but with no type annotations on its abstractions.
+///
+/// Untyped terms are how kombi exchanges terms with formats which have no notion of type. They
+/// can be produced from any `LambdaTerm` by `LambdaTerm::erase_types`, and turned back into one,
+/// where possible, by `UntypedTerm::infer_types`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UntypedTerm {
+    Variable {
+        idx: u64,
+    },
+    Abstraction {
+        variable: String,
+        body: Box<UntypedTerm>,
+    },
+    Application {
+        function: Box<UntypedTerm>,
+        argument: Box<UntypedTerm>,
+    },
+}
+
+impl UntypedTerm {
+    /// Return whether the `UntypedTerm` contains no free variables.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        let mut stack = vec![(self, 0)];
+        while let Some((term, depth)) = stack.pop() {
+            match term {
+                UntypedTerm::Variable { idx } => {
+                    if *idx >= depth {
+                        return false;
+                    }
+                }
+                UntypedTerm::Abstraction { body, .. } => stack.push((body, depth + 1)),
+                UntypedTerm::Application { function, argument } => {
+                    stack.push((argument, depth));
+                    stack.push((function, depth));
+                }
+            }
+        }
+        true
+    }
+
+    /// Shift every free variable in the `UntypedTerm` with de Bruijn index at least `cutoff` by
+    /// `amount`, as `LambdaTerm::shift` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if shifting would make some de Bruijn index negative.
+    #[must_use]
+    pub fn shift(&self, amount: i64, cutoff: u64) -> Self {
+        match self {
+            UntypedTerm::Variable { idx } => {
+                if *idx >= cutoff {
+                    UntypedTerm::Variable {
+                        idx: idx
+                            .checked_add_signed(amount)
+                            .expect("shifting should not make a de Bruijn index negative"),
+                    }
+                } else {
+                    self.clone()
+                }
+            }
+            UntypedTerm::Abstraction { variable, body } => UntypedTerm::Abstraction {
+                variable: variable.clone(),
+                body: Box::new(body.shift(amount, cutoff + 1)),
+            },
+            UntypedTerm::Application { function, argument } => UntypedTerm::Application {
+                function: Box::new(function.shift(amount, cutoff)),
+                argument: Box::new(argument.shift(amount, cutoff)),
+            },
+        }
+    }
+
+    /// Replace every occurrence of the free variable with de Bruijn index `idx` with
+    /// `replacement`, as `LambdaTerm::substitute` does.
+    #[must_use]
+    pub fn substitute(&self, idx: u64, replacement: &UntypedTerm) -> Self {
+        self.substitute_at_depth(idx, replacement, 0)
+    }
+
+    fn substitute_at_depth(&self, idx: u64, replacement: &UntypedTerm, depth: u64) -> Self {
+        match self {
+            UntypedTerm::Variable { idx: i } => {
+                if *i == idx + depth {
+                    replacement.shift(i64::try_from(depth).expect("depth should fit in an i64"), 0)
+                } else {
+                    self.clone()
+                }
+            }
+            UntypedTerm::Abstraction { variable, body } => UntypedTerm::Abstraction {
+                variable: variable.clone(),
+                body: Box::new(body.substitute_at_depth(idx, replacement, depth + 1)),
+            },
+            UntypedTerm::Application { function, argument } => UntypedTerm::Application {
+                function: Box::new(function.substitute_at_depth(idx, replacement, depth)),
+                argument: Box::new(argument.substitute_at_depth(idx, replacement, depth)),
+            },
+        }
+    }
+
+    /// Treating the `UntypedTerm` as the body of an abstraction, replace the variable bound by
+    /// that abstraction with `argument`, as `LambdaTerm::open` does.
+    #[must_use]
+    pub fn open(&self, argument: &UntypedTerm) -> Self {
+        self.substitute(0, &argument.shift(1, 0)).shift(-1, 0)
+    }
+
+    /// Contract the leftmost outermost β-redex in the `UntypedTerm`, returning the resulting
+    /// term, or `None` if the term is in normal form.
+    ///
+    /// Unlike evaluation of typed terms, this reduces under abstractions and in arguments, so that
+    /// repeated steps reach the normal form of the term whenever it has one.
+    #[must_use]
+    pub fn step(&self) -> Option<Self> {
+        match self {
+            UntypedTerm::Variable { .. } => None,
+            UntypedTerm::Abstraction { variable, body } => {
+                body.step().map(|body| UntypedTerm::Abstraction {
+                    variable: variable.clone(),
+                    body: Box::new(body),
+                })
+            }
+            UntypedTerm::Application { function, argument } => {
+                if let UntypedTerm::Abstraction { body, .. } = function.as_ref() {
+                    return Some(body.open(argument));
+                }
+                if let Some(function) = function.step() {
+                    return Some(UntypedTerm::Application {
+                        function: Box::new(function),
+                        argument: argument.clone(),
+                    });
+                }
+                argument.step().map(|argument| UntypedTerm::Application {
+                    function: function.clone(),
+                    argument: Box::new(argument),
+                })
+            }
+        }
+    }
+
+    /// Return the normal form of the `UntypedTerm`, or `None` if it is not reached within `fuel`
+    /// steps, which may be because the term has no normal form at all.
+    #[must_use]
+    pub fn normalize(&self, fuel: usize) -> Option<Self> {
+        let mut term = self.clone();
+        for _ in 0..fuel {
+            match term.step() {
+                Some(next) => term = next,
+                None => return Some(term),
+            }
+        }
+        term.step().is_none().then_some(term)
+    }
+
+    /// Write the `UntypedTerm` as though it were found under `depth` abstractions, parenthesizing
+    /// abstractions unless they extend to the right end of the enclosing term.
+    pub(crate) fn write(&self, f: &mut Formatter<'_>, depth: u64, rightmost: bool) -> fmt::Result {
+        match self {
+            UntypedTerm::Variable { idx } => {
+                if *idx < depth {
+                    write!(f, "x{}", depth - idx - 1)
+                } else {
+                    write!(f, "_{}", idx - depth)
+                }
+            }
+            UntypedTerm::Abstraction { body, .. } => {
+                if !rightmost {
+                    write!(f, "(")?;
+                }
+                write!(f, "\\x{depth}. ")?;
+                body.write(f, depth + 1, true)?;
+                if !rightmost {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            UntypedTerm::Application { function, argument } => {
+                function.write(f, depth, false)?;
+                write!(f, " ")?;
+                if let UntypedTerm::Application { .. } = **argument {
+                    write!(f, "(")?;
+                    argument.write(f, depth, true)?;
+                    write!(f, ")")
+                } else {
+                    argument.write(f, depth, rightmost)
+                }
+            }
+        }
+    }
+}
+
+/// Untyped terms are displayed in conventional untyped notation, as in `\x0. x0 _0`, with every
+/// bound variable named after the number of abstractions enclosing its binder, and every free
+/// variable after its de Bruijn index.
+impl Display for UntypedTerm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write(f, 0, true)
+    }
+}
+
+impl LambdaTerm {
+    /// Return the `UntypedTerm` obtained by discarding every type annotation in the `LambdaTerm`.
+    #[must_use]
+    pub fn erase_types(&self) -> UntypedTerm {
+        match self {
+            LambdaTerm::Variable { idx } => UntypedTerm::Variable { idx: *idx },
+            LambdaTerm::Abstraction { variable, body, .. } => UntypedTerm::Abstraction {
+                variable: variable.clone(),
+                body: Box::new(body.erase_types()),
+            },
+            LambdaTerm::Application { function, argument } => UntypedTerm::Application {
+                function: Box::new(function.erase_types()),
+                argument: Box::new(argument.erase_types()),
+            },
+        }
+    }
+}
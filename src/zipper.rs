@@ -0,0 +1,190 @@
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::parse::{LambdaTerm, Type};
+use crate::traverse::Step;
+
+/// The part of a `LambdaTerm` surrounding the focus of a `TermZipper`, one node at a time.
+#[derive(Debug, Clone)]
+enum Frame {
+    /// The focus is the body of an abstraction with the given variable and argument type.
+    Body {
+        variable: String,
+        argument_type: Type,
+    },
+    /// The focus is the function of an application to the given argument.
+    Function { argument: Rc<LambdaTerm> },
+    /// The focus is the argument of an application of the given function.
+    Argument { function: Rc<LambdaTerm> },
+}
+
+/// A zipper over a `LambdaTerm`, allowing the focus to be moved around the term and the focused
+/// subterm to be replaced without rebuilding the rest of the term.
+#[derive(Debug, Clone)]
+pub struct TermZipper {
+    focus: LambdaTerm,
+    frames: Vec<Frame>,
+}
+
+impl TermZipper {
+    /// Create a new `TermZipper` focused on the root of the given term.
+    #[must_use]
+    pub fn new(term: LambdaTerm) -> Self {
+        Self {
+            focus: term,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Create a new `TermZipper` focused on the subterm at the end of the given path, or `None`
+    /// if the path does not lead to a subterm.
+    #[must_use]
+    pub fn at_path(term: LambdaTerm, path: &[Step]) -> Option<Self> {
+        let mut zipper = Self::new(term);
+        for step in path {
+            if !zipper.down(*step) {
+                return None;
+            }
+        }
+        Some(zipper)
+    }
+
+    /// Return the subterm currently in focus.
+    #[must_use]
+    pub fn focus(&self) -> &LambdaTerm {
+        &self.focus
+    }
+
+    /// Return the number of abstractions enclosing the focus.
+    #[must_use]
+    pub fn depth(&self) -> u64 {
+        self.frames
+            .iter()
+            .filter(|f| matches!(f, Frame::Body { .. }))
+            .count() as u64
+    }
+
+    /// Return the sequence of steps leading from the root of the term to the focus.
+    #[must_use]
+    pub fn path(&self) -> Vec<Step> {
+        self.frames
+            .iter()
+            .map(|f| match f {
+                Frame::Body { .. } => Step::Body,
+                Frame::Function { .. } => Step::Function,
+                Frame::Argument { .. } => Step::Argument,
+            })
+            .collect()
+    }
+
+    /// Move the focus one step down the term, returning whether the move was possible.
+    pub fn down(&mut self, step: Step) -> bool {
+        // NOTE: The focus is temporarily replaced with a placeholder so that its children can be
+        // moved out of it rather than cloned.
+        let focus = mem::replace(&mut self.focus, LambdaTerm::Variable { idx: 0 });
+
+        match (step, focus) {
+            (
+                Step::Body,
+                LambdaTerm::Abstraction {
+                    variable,
+                    argument_type,
+                    body,
+                },
+            ) => {
+                self.frames.push(Frame::Body {
+                    variable,
+                    argument_type,
+                });
+                self.focus = Rc::unwrap_or_clone(body);
+                true
+            }
+            (Step::Function, LambdaTerm::Application { function, argument }) => {
+                self.frames.push(Frame::Function { argument });
+                self.focus = Rc::unwrap_or_clone(function);
+                true
+            }
+            (Step::Argument, LambdaTerm::Application { function, argument }) => {
+                self.frames.push(Frame::Argument { function });
+                self.focus = Rc::unwrap_or_clone(argument);
+                true
+            }
+            (_, focus) => {
+                self.focus = focus;
+                false
+            }
+        }
+    }
+
+    /// Move the focus to the leftmost child of the current focus, which is the body of an
+    /// abstraction or the function of an application, returning whether the move was possible.
+    pub fn down_left(&mut self) -> bool {
+        match self.focus {
+            LambdaTerm::Abstraction { .. } => self.down(Step::Body),
+            _ => self.down(Step::Function),
+        }
+    }
+
+    /// Move the focus to the argument of the application currently in focus, returning whether
+    /// the move was possible.
+    pub fn down_right(&mut self) -> bool {
+        self.down(Step::Argument)
+    }
+
+    /// Move the focus to the parent of the current focus, returning whether the move was
+    /// possible.
+    pub fn up(&mut self) -> bool {
+        let Some(frame) = self.frames.pop() else {
+            return false;
+        };
+
+        let focus = Rc::new(mem::replace(
+            &mut self.focus,
+            LambdaTerm::Variable { idx: 0 },
+        ));
+        self.focus = match frame {
+            Frame::Body {
+                variable,
+                argument_type,
+            } => LambdaTerm::Abstraction {
+                variable,
+                argument_type,
+                body: focus,
+            },
+            Frame::Function { argument } => LambdaTerm::Application {
+                function: focus,
+                argument,
+            },
+            Frame::Argument { function } => LambdaTerm::Application {
+                function,
+                argument: focus,
+            },
+        };
+        true
+    }
+
+    /// Move the focus back to the root of the term.
+    pub fn top(&mut self) {
+        while self.up() {}
+    }
+
+    /// Replace the subterm currently in focus, returning the subterm which was replaced.
+    pub fn replace(&mut self, term: LambdaTerm) -> LambdaTerm {
+        mem::replace(&mut self.focus, term)
+    }
+
+    /// Reconstruct the whole term, leaving the zipper untouched.
+    #[must_use]
+    pub fn to_term(&self) -> LambdaTerm {
+        self.clone().into_term()
+    }
+
+    /// Reconstruct the whole term, consuming the zipper.
+    #[must_use]
+    pub fn into_term(mut self) -> LambdaTerm {
+        self.top();
+        self.focus
+    }
+}